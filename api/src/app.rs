@@ -0,0 +1,159 @@
+use crate::rest::audit::handler::audit_handler;
+use crate::rest::auth::handler::{auth_handler, well_known_handler};
+use crate::rest::health::handler::{api_root_handler, health_handler};
+use crate::rest::material::handler::material_handler;
+use crate::rest::metrics::handler::metrics_handler;
+use crate::rest::middleware::auth::AuthLayer;
+use crate::rest::middleware::error::ErrorLayer;
+use crate::rest::middleware::idempotency::IdempotencyLayer;
+use crate::rest::middleware::metrics::MetricsLayer;
+use crate::rest::middleware::rate_limit::RateLimitLayer;
+use crate::rest::middleware::request_id::RequestIdLayer;
+use crate::rest::middleware::timeout::TimeoutLayer;
+use crate::rest::openapi::openapi_handler;
+use crate::rest::role::handler::role_handler;
+use crate::rest::search::handler::search_handler;
+use crate::rest::supplier::handler::supplier_handler;
+use crate::rest::user::handler::user_handler;
+use crate::AppState;
+use axum::http::HeaderValue;
+use axum::{Extension, Router};
+use config::{Config, CorsConfig, RateLimit};
+use lib::{
+    app_ctx::AppCtx, audit::audit::PgAuditLogRepository, auth::auth::Auth,
+    idempotency::idempotency::PgIdempotencyRepository, material::material::PgMaterialRepository,
+    oidc::oidc::PgOidcRepository, role::role::PgRoleRepository, search::search::PgSearchRepository,
+    session::session::PgSessionRepository, supplier::supplier::PgSupplierRepository,
+    user::user::PgUserRepository,
+};
+use sqlx::postgres::Postgres;
+use sqlx::Pool;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+// the use cases on their Postgres repositories, shared by the server and invctl
+pub async fn build_app_ctx(config: &Config, pool: Arc<Pool<Postgres>>) -> AppCtx {
+    let user_repository = Box::new(
+        PgUserRepository::new(
+            pool.clone(),
+            config.login_policy.clone(),
+            config.auth.clone(),
+        )
+        .await,
+    );
+    let role_repository = Box::new(PgRoleRepository::new(pool.clone()).await);
+    let supplier_repository = Box::new(PgSupplierRepository::new(pool.clone()).await);
+    let material_repository = Box::new(PgMaterialRepository::new(pool.clone()).await);
+    let auth_repository = Box::new(Auth::new(pool.clone(), config.auth.keys.clone()).await);
+    let session_repository = Box::new(PgSessionRepository::new(pool.clone()).await);
+    let audit_log_repository = Box::new(PgAuditLogRepository::new(pool.clone()).await);
+    let search_repository = Box::new(PgSearchRepository::new(pool.clone()).await);
+    let idempotency_repository = Box::new(
+        PgIdempotencyRepository::new(pool.clone(), config.server.idempotency_ttl_seconds).await,
+    );
+    let oidc_repository = Box::new(
+        PgOidcRepository::new(pool.clone(), config.oidc.clone(), config.auth.clone()).await,
+    );
+    AppCtx::new(
+        user_repository,
+        role_repository,
+        supplier_repository,
+        material_repository,
+        auth_repository,
+        oidc_repository,
+        session_repository,
+        audit_log_repository,
+        search_repository,
+        idempotency_repository,
+    )
+    .await
+}
+
+// the whole application on top of `pool`, served by main.rs and driven by the api tests.
+// Handlers read the client address from `ConnectInfo<SocketAddr>`.
+pub async fn build_app(config: Config, pool: Arc<Pool<Postgres>>) -> Router {
+    let app_ctx: AppCtx = build_app_ctx(&config, pool.clone()).await;
+    let arc_state: Arc<AppCtx> = Arc::new(app_ctx);
+    let app_state = Arc::new(AppState {
+        arc_state: arc_state.clone(),
+        config: config.clone(),
+        pool,
+    });
+
+    let rate_limits = &config.rate_limits;
+    let public_api: Router = Router::new().merge(api_root_handler()).nest(
+        "/auth",
+        auth_handler().layer(RateLimitLayer::new(rate_limits.auth, &config.auth.keys)),
+    );
+
+    let api = Router::new()
+        .nest(
+            "/users",
+            protected(user_handler(), &app_state, rate_limits.users),
+        )
+        .nest(
+            "/roles",
+            protected(role_handler(), &app_state, rate_limits.roles),
+        )
+        .nest(
+            "/materials",
+            protected(material_handler(), &app_state, rate_limits.materials),
+        )
+        .nest(
+            "/suppliers",
+            protected(supplier_handler(), &app_state, rate_limits.suppliers),
+        )
+        .nest(
+            "/audit-logs",
+            protected(audit_handler(), &app_state, rate_limits.audit_logs),
+        )
+        .nest(
+            "/search",
+            protected(search_handler(), &app_state, rate_limits.search),
+        );
+
+    let app = Router::new()
+        .nest("/api", api)
+        .nest("/api", public_api)
+        .nest("/.well-known", well_known_handler())
+        .merge(openapi_handler())
+        .nest("/metrics", metrics_handler())
+        .nest("/health", health_handler())
+        .layer(Extension(arc_state.clone()))
+        .layer(Extension(app_state.clone()));
+    // answers 408 when a handler takes longer, exports excepted
+    let app = match config.server.request_timeout_seconds {
+        0 => app,
+        seconds => app.layer(TimeoutLayer::new(Duration::from_secs(seconds))),
+    };
+    app.layer(ErrorLayer::new(config.server.problem_json))
+        .layer(MetricsLayer::new())
+        .layer(RequestIdLayer)
+        .layer(cors_layer(&config.cors))
+}
+
+// the rate limit comes first, requests over it are answered without touching the database
+fn protected(router: Router, app_state: &Arc<AppState>, limit: RateLimit) -> Router {
+    router
+        .layer(IdempotencyLayer::new(app_state.clone()))
+        .layer(AuthLayer::new(app_state.clone()))
+        .layer(RateLimitLayer::new(limit, &app_state.config.auth.keys))
+}
+
+// browsers may only call the api from the configured origins
+fn cors_layer(cors: &CorsConfig) -> CorsLayer {
+    let origins = match cors.allows_any() {
+        true => AllowOrigin::any(),
+        false => AllowOrigin::list(
+            cors.allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        ),
+    };
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers(Any)
+}
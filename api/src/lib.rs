@@ -1,8 +1,18 @@
 use std::sync::Arc;
 
+pub mod app;
 pub mod util {
+    pub mod bulk;
+    pub mod client;
     pub mod error;
+    pub mod etag;
+    pub mod export;
+    pub mod import;
+    pub mod list;
+    pub mod logging;
+    pub mod metrics;
     pub mod res;
+    pub mod shutdown;
     pub mod validation;
 }
 pub mod rest {
@@ -26,12 +36,35 @@ pub mod rest {
         pub mod dto;
         pub mod handler;
     }
+    pub mod audit {
+        pub mod dto;
+        pub mod handler;
+    }
+    pub mod search {
+        pub mod dto;
+        pub mod handler;
+    }
+    pub mod metrics {
+        pub mod handler;
+    }
+    pub mod health {
+        pub mod dto;
+        pub mod handler;
+    }
+    pub mod openapi;
     pub mod middleware {
         pub mod auth;
+        pub mod error;
+        pub mod idempotency;
+        pub mod metrics;
+        pub mod rate_limit;
+        pub mod request_id;
+        pub mod timeout;
     }
 }
 
 pub struct AppState {
     pub arc_state: Arc<lib::app_ctx::AppCtx>,
     pub config: config::Config,
+    pub pool: Arc<sqlx::Pool<sqlx::Postgres>>,
 }
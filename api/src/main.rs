@@ -1,73 +1,47 @@
-use api::rest::auth::handler::auth_handler;
-use api::rest::material::handler::material_handler;
-use api::rest::middleware::auth::{AuthLayer, RoleCheckLayer};
-use api::rest::role::handler::role_handler;
-use api::rest::supplier::handler::supplier_handler;
-use api::rest::user::handler::user_handler;
-use api::AppState;
-use axum::{routing::get, Extension, Json, Router};
+use api::app::build_app;
+use api::util::logging::init_logging;
+use api::util::shutdown;
 use config::Config;
-use lib::util::postgres::get_connection_pool;
-use lib::{
-    app_ctx::AppCtx, auth::auth::Auth, material::material::PgMaterialRepository,
-    role::role::PgRoleRepository, supplier::supplier::PgSupplierRepository,
-    user::user::PgUserRepository,
-};
+use lib::util::postgres::{check_schema, get_connection_pool, MIGRATOR};
 use sqlx::postgres::Postgres;
 use sqlx::Pool;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tower_http::cors::CorsLayer;
 
 #[tokio::main]
 async fn main() {
-    let config = Config::default();
-    let pool: Arc<Pool<Postgres>> = Arc::new(get_connection_pool(config.clone()));
-    let user_repository = Box::new(PgUserRepository::new(pool.clone()).await);
-    let role_repository = Box::new(PgRoleRepository::new(pool.clone()).await);
-    let supplier_repository = Box::new(PgSupplierRepository::new(pool.clone()).await);
-    let material_repository = Box::new(PgMaterialRepository::new(pool.clone()).await);
-    let auth_repository = Box::new(Auth::new().await);
-    let app_ctx: AppCtx = AppCtx::new(
-        user_repository,
-        role_repository,
-        supplier_repository,
-        material_repository,
-        auth_repository,
-    )
-    .await;
-    let arc_state: Arc<AppCtx> = Arc::new(app_ctx);
-    let app_state = Arc::new(AppState {
-        arc_state: arc_state.clone(),
-        config: config.clone(),
+    // every invalid setting is listed at once instead of failing on the first
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
     });
-
-    let public_api: Router = Router::new()
-        .route("/", get(root))
-        .nest("/auth", auth_handler());
-
-    let api = Router::new()
-        .nest("/users", user_handler())
-        .nest("/roles", role_handler())
-        .nest("/materials", material_handler())
-        .nest("/suppliers", supplier_handler())
-        .layer(AuthLayer::new(app_state.clone()));
-
-    let app = Router::new()
-        .nest("/api", api)
-        .nest("/api", public_api)
-        .layer(Extension(arc_state.clone()))
-        .layer(CorsLayer::permissive());
-
-    let listener = TcpListener::bind(&format!("0.0.0.0:{}", config.port))
+    let telemetry = init_logging(&config.log, config.otlp.as_ref());
+    let pool: Arc<Pool<Postgres>> = Arc::new(get_connection_pool(config.clone()));
+    if config.database.migrate_on_startup {
+        MIGRATOR
+            .run(pool.as_ref())
+            .await
+            .unwrap_or_else(|e| panic!("Migrations must apply: {e}"));
+    }
+    // a schema this build does not match would otherwise only fail on the first request
+    check_schema(&pool)
         .await
-        .expect("Tcp listener must be valid!");
+        .unwrap_or_else(|e| panic!("Database schema must be up to date: {e}"));
+    let app = build_app(config.clone(), pool.clone()).await;
 
-    axum::serve(listener, app)
+    let address = SocketAddr::new(config.server.host, config.server.port);
+    let listener = TcpListener::bind(address)
         .await
-        .expect("Axum must be able to start");
-}
+        .expect("Tcp listener must be valid!");
+    tracing::info!("listening on {address}");
 
-async fn root() -> Json<&'static str> {
-    Json("OK")
+    tokio::spawn(shutdown::wait_for_signal());
+    // requests and background tasks together get `shutdown_timeout_seconds` to finish
+    let grace = Duration::from_secs(config.server.shutdown_timeout_seconds);
+    shutdown::serve(listener, app, shutdown::shutting_down(), grace).await;
+    tracing::info!("shutdown complete");
+    pool.close().await;
+    telemetry.shutdown();
 }
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use lib::audit::model::AuditLogFilter;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use validator::Validate;
+
+static RE_ENTITY_TYPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(user|role|supplier|material_group|material)$").unwrap());
+static RE_ACTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(create|update|delete|restore)$").unwrap());
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestAuditLogQueryDto {
+    pub actor_id: Option<Uuid>,
+    #[validate(regex(path = *RE_ENTITY_TYPE, message = "invalid"))]
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    #[validate(regex(path = *RE_ACTION, message = "invalid"))]
+    pub action: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[validate(range(min = 1, max = 500, message = "invalid"))]
+    pub limit: Option<i64>,
+}
+
+impl RequestAuditLogQueryDto {
+    pub fn to_audit_log_filter(&self) -> AuditLogFilter {
+        AuditLogFilter {
+            actor_id: self.actor_id,
+            entity_type: self.entity_type.clone(),
+            entity_id: self.entity_id,
+            action: self.action.clone(),
+            from: self.from,
+            to: self.to,
+            limit: self.limit.unwrap_or(100),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::rest::audit::dto::RequestAuditLogQueryDto;
+use crate::rest::middleware::auth::role_check;
+use crate::util::error::RestApiError;
+use crate::util::res::ApiResult;
+use crate::util::validation::ValidatedQuery;
+use axum::{
+    extract::Extension, http::StatusCode, middleware, response::IntoResponse, routing::get, Json,
+    Router,
+};
+use lib::app_ctx::AppCtx;
+use lib::audit::model::ResponseAuditLog;
+use std::sync::Arc;
+use tracing::instrument;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_audit_logs,))]
+pub struct AuditApi;
+
+pub fn audit_handler() -> Router {
+    Router::new()
+        .route("/", get(get_audit_logs))
+        .route_layer(middleware::from_fn(|req, next| {
+            role_check(req, next, vec!["Admin"])
+        }))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "Browse the audit log",
+    params(RequestAuditLogQueryDto),
+    responses(
+        (status = 200, description = "Audit logs found", body = ApiResult<Vec<ResponseAuditLog>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_audit_logs(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    ValidatedQuery(_dto): ValidatedQuery<RequestAuditLogQueryDto>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .audit_log_use_case
+        .get_audit_logs(&_dto.to_audit_log_filter())
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Audit logs found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
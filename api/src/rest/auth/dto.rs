@@ -1,17 +1,21 @@
+use lib::oidc::model::OidcCallback;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 static RE_PASS: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_]{6,32}$").unwrap());
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RequestLoginDto {
     #[validate(email(message = "invalid"))]
     #[validate(required(message = "missing"))]
+    #[schema(value_type = String)]
     pub email: Option<String>,
     #[validate(regex(path = *RE_PASS, message = "invalid"))]
     #[validate(required(message = "missing"))]
+    #[schema(value_type = String)]
     pub password: Option<String>,
 }
 
@@ -23,3 +27,30 @@ impl RequestLoginDto {
         )
     }
 }
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestOidcCallbackDto {
+    #[validate(length(min = 1, message = "invalid"))]
+    pub code: Option<String>,
+    #[validate(length(min = 1, message = "invalid"))]
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+impl RequestOidcCallbackDto {
+    // the provider reports a failed login with `error` instead of a code
+    pub fn to_oidc_callback(&self) -> Result<OidcCallback, String> {
+        if let Some(error) = &self.error {
+            return Err(self.error_description.clone().unwrap_or(error.clone()));
+        }
+        match (&self.code, &self.state) {
+            (Some(code), Some(state)) => Ok(OidcCallback {
+                code: code.clone(),
+                state: state.clone(),
+            }),
+            _ => Err("missing code or state".to_string()),
+        }
+    }
+}
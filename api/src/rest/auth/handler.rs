@@ -1,32 +1,64 @@
-use crate::rest::auth::dto::RequestLoginDto;
+use crate::rest::auth::dto::{RequestLoginDto, RequestOidcCallbackDto};
 use crate::rest::user::dto::RequestCreateUserDto;
+use crate::util::client::ExtractClientInfo;
 use crate::util::error::RestApiError;
-use crate::util::res::ApiResult;
-use crate::util::validation::ValidatedJson;
+use crate::util::res::{ApiResult, Null};
+use crate::util::validation::{ValidatedJson, ValidatedQuery};
+use crate::AppState;
 use axum::{
     extract::Extension,
     http::StatusCode,
-    response::IntoResponse,
-    routing::{post, Router},
+    response::{IntoResponse, Redirect},
+    routing::{get, post, Router},
     Json,
 };
 use lib::app_ctx::AppCtx;
+use lib::user::model::{AuthBody, ResponseUser};
 use std::sync::Arc;
+use tracing::instrument;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(login, register, oidc_login, oidc_callback,))]
+pub struct AuthApi;
 
 pub fn auth_handler() -> Router {
     Router::new()
         .route("/login", post(login))
         .route("/register", post(register))
+        .route("/oidc/login", get(oidc_login))
+        .route("/oidc/callback", get(oidc_callback))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(jwks))]
+pub struct WellKnownApi;
+
+pub fn well_known_handler() -> Router {
+    Router::new().route("/jwks.json", get(jwks))
 }
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    summary = "Log in with email and password",
+    request_body = RequestLoginDto,
+    security(()),
+    responses(
+        (status = 201, description = "Logged in", body = ApiResult<AuthBody>),
+        (status = 401, description = "Wrong credentials", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn login(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    ExtractClientInfo(client): ExtractClientInfo,
     ValidatedJson(_dto): ValidatedJson<RequestLoginDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let (email, password) = &_dto.to_login();
     let result = _app_ctx
         .user_use_case
-        .login(&email, &password)
+        .login(email, password, &client)
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -40,13 +72,26 @@ async fn login(
     ))
 }
 
+#[utoipa::path(
+    post,
+    path = "/register",
+    summary = "Register a user",
+    request_body = RequestCreateUserDto,
+    security(()),
+    responses(
+        (status = 201, description = "User created", body = ApiResult<ResponseUser>),
+        (status = 409, description = "Name or email already taken", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn register(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     ValidatedJson(_dto): ValidatedJson<RequestCreateUserDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .user_use_case
-        .create_user(&_dto.to_create_user())
+        // self registration has no authenticated actor
+        .create_user(&_dto.to_create_user(), None)
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -59,3 +104,76 @@ async fn register(
         )),
     ))
 }
+
+#[utoipa::path(
+    get,
+    path = "/oidc/login",
+    summary = "Start an OIDC login",
+    security(()),
+    responses(
+        (status = 303, description = "Redirect to the identity provider"),
+    )
+)]
+#[instrument(skip_all)]
+async fn oidc_login(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let url = _app_ctx
+        .oidc_use_case
+        .authorization_url()
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok(Redirect::to(&url))
+}
+
+#[utoipa::path(
+    get,
+    path = "/oidc/callback",
+    summary = "Finish an OIDC login",
+    params(RequestOidcCallbackDto),
+    security(()),
+    responses(
+        (status = 201, description = "Logged in", body = ApiResult<AuthBody>),
+        (status = 401, description = "Login refused by the provider", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
+async fn oidc_callback(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    ExtractClientInfo(client): ExtractClientInfo,
+    ValidatedQuery(_dto): ValidatedQuery<RequestOidcCallbackDto>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let callback = _dto
+        .to_oidc_callback()
+        .map_err(RestApiError::Unauthorized)?;
+    let result = _app_ctx
+        .oidc_use_case
+        .login(&callback, &client)
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResult::from(
+            result,
+            "Logged in successful".to_string(),
+            StatusCode::CREATED.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jwks.json",
+    summary = "Public keys that verify tokens",
+    security(()),
+    responses(
+        (status = 200, description = "JSON Web Key Set", body = Object),
+    )
+)]
+// served as a plain JWK Set so JWT libraries can consume it directly
+#[instrument(skip_all)]
+async fn jwks(Extension(app_state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    Json(app_state.config.auth.keys.jwks())
+}
@@ -0,0 +1,27 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResponseHealthDto {
+    // `ok` or `unavailable`
+    pub status: &'static str,
+    // why the service is not ready, missing when it is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl ResponseHealthDto {
+    pub fn ok() -> Self {
+        Self {
+            status: "ok",
+            reason: None,
+        }
+    }
+
+    pub fn unavailable(reason: String) -> Self {
+        Self {
+            status: "unavailable",
+            reason: Some(reason),
+        }
+    }
+}
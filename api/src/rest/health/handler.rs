@@ -0,0 +1,106 @@
+use crate::rest::health::dto::ResponseHealthDto;
+use crate::util::res::ApiResult;
+use crate::util::shutdown::is_shutting_down;
+use crate::AppState;
+use axum::{
+    extract::Extension, http::StatusCode, response::IntoResponse, routing::get, Json, Router,
+};
+use lib::util::postgres::{check_schema, SchemaError};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::OpenApi;
+
+// probes fail fast instead of waiting for the pool's acquire timeout
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(OpenApi)]
+#[openapi(paths(get_live, get_ready))]
+pub struct HealthApi;
+
+// probes for load balancers and orchestrators, without a token
+pub fn health_handler() -> Router {
+    Router::new()
+        .route("/live", get(get_live))
+        .route("/ready", get(get_ready))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(root))]
+pub struct ApiRootApi;
+
+pub fn api_root_handler() -> Router {
+    Router::new().route("/", get(root))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "Check that the api answers",
+    security(()),
+    responses(
+        (status = 200, description = "Always `\"OK\"`", body = String),
+    )
+)]
+async fn root() -> Json<&'static str> {
+    Json("OK")
+}
+
+#[utoipa::path(
+    get,
+    path = "/live",
+    summary = "Liveness probe",
+    security(()),
+    responses(
+        (status = 200, description = "The process is serving requests", body = ApiResult<ResponseHealthDto>),
+    )
+)]
+// the process is up and serving, nothing else is checked so a database outage does not get it restarted
+async fn get_live() -> impl IntoResponse {
+    health(StatusCode::OK, ResponseHealthDto::ok())
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    summary = "Readiness probe",
+    security(()),
+    responses(
+        (status = 200, description = "Postgres answers and the schema is up to date", body = ApiResult<ResponseHealthDto>),
+        (status = 503, description = "Not ready, `reason` says why", body = ApiResult<ResponseHealthDto>),
+    )
+)]
+// whether requests can be served: Postgres answers and has every migration of this build,
+// false as soon as a shutdown started so no new traffic is sent while requests drain
+async fn get_ready(Extension(app_state): Extension<Arc<AppState>>) -> impl IntoResponse {
+    if is_shutting_down() {
+        return health(
+            StatusCode::SERVICE_UNAVAILABLE,
+            ResponseHealthDto::unavailable("shutting down".to_string()),
+        );
+    }
+    let reason =
+        match tokio::time::timeout(READY_CHECK_TIMEOUT, check_schema(&app_state.pool)).await {
+            Ok(Ok(())) => return health(StatusCode::OK, ResponseHealthDto::ok()),
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "not ready");
+                match e {
+                    // connection details stay in the log
+                    SchemaError::Unreachable(_) => "database is unreachable".to_string(),
+                    e => e.to_string(),
+                }
+            }
+            Err(_) => "database did not answer in time".to_string(),
+        };
+    health(
+        StatusCode::SERVICE_UNAVAILABLE,
+        ResponseHealthDto::unavailable(reason),
+    )
+}
+
+fn health(status: StatusCode, health: ResponseHealthDto) -> impl IntoResponse {
+    let message = health.status.to_string();
+    (
+        status,
+        Json(ApiResult::from(health, message, status.into())),
+    )
+}
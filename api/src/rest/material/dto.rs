@@ -1,12 +1,20 @@
-use lib::material::model::{CreateMaterialGroup, UpdateMaterialGroup};
+use crate::util::export::{ExportCell, ExportRecord};
+use crate::util::list::filters;
+use chrono::NaiveDate;
+use lib::material::model::{
+    CreateMaterial, CreateMaterialGroup, ResponseMaterial, ResponseMaterialGroup,
+    UpdateMaterialGroup,
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
 
 static RE_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_. ]{3,32}$").unwrap());
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RequestCreateMaterialGroupDto {
     #[validate(regex(path = *RE_NAME , message = "Invalid"))]
     pub name: String,
@@ -23,7 +31,7 @@ impl RequestCreateMaterialGroupDto {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RequestUpdateMaterialGroupDto {
     #[validate(regex(path = *RE_NAME , message = "Invalid"))]
     pub name: Option<String>,
@@ -42,3 +50,131 @@ impl RequestUpdateMaterialGroupDto {
         }
     }
 }
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestMaterialGroupFilterDto {
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub sub_group: Option<String>,
+}
+
+impl RequestMaterialGroupFilterDto {
+    pub fn to_filters(&self) -> Vec<(String, String)> {
+        filters([("name", &self.name), ("sub_group_name", &self.sub_group)])
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestMaterialFilterDto {
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub name: Option<String>,
+    pub supplier_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
+}
+
+impl RequestMaterialFilterDto {
+    pub fn to_filters(&self) -> Vec<(String, String)> {
+        let supplier_id = self.supplier_id.map(|id| id.to_string());
+        let group_id = self.group_id.map(|id| id.to_string());
+        filters([
+            ("name", &self.name),
+            ("supplier_id", &supplier_id),
+            ("group_id", &group_id),
+        ])
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+#[validate(schema(function = "validate_material_dates", skip_on_field_errors = false))]
+pub struct RequestCreateMaterialDto {
+    #[validate(length(min = 1, max = 100, message = "Invalid"))]
+    pub name: String,
+    #[validate(range(min = 0, message = "Invalid"))]
+    pub price: i32,
+    #[validate(length(min = 1, max = 1000, message = "Invalid"))]
+    pub description: String,
+    #[validate(range(min = 0, message = "Invalid"))]
+    pub quantity: i32,
+    pub mfg_date: NaiveDate,
+    pub exp_date: NaiveDate,
+    pub supplier_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
+}
+
+impl RequestCreateMaterialDto {
+    pub fn to_create_material(&self) -> CreateMaterial {
+        CreateMaterial {
+            name: self.name.trim().to_string(),
+            price: self.price,
+            description: self.description.trim().to_string(),
+            quantity: self.quantity,
+            mfg_date: self.mfg_date,
+            exp_date: self.exp_date,
+            supplier_id: self.supplier_id,
+            group_id: self.group_id,
+        }
+    }
+}
+
+fn validate_material_dates(dto: &RequestCreateMaterialDto) -> Result<(), ValidationError> {
+    if dto.exp_date < dto.mfg_date {
+        let mut error = ValidationError::new("exp_date");
+        error.message = Some("exp_date must not be before mfg_date".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+impl ExportRecord for ResponseMaterialGroup {
+    const HEADERS: &'static [&'static str] =
+        &["id", "name", "sub_group_name", "created_at", "updated_at"];
+
+    fn to_row(&self) -> Vec<ExportCell> {
+        vec![
+            self.id.into(),
+            (&self.name).into(),
+            (&self.sub_group_name).into(),
+            self.created_at.into(),
+            self.updated_at.into(),
+        ]
+    }
+}
+
+impl ExportRecord for ResponseMaterial {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "price",
+        "description",
+        "quantity",
+        "mfg_date",
+        "exp_date",
+        "supplier_id",
+        "supplier_name",
+        "group_id",
+        "group_name",
+        "created_at",
+        "updated_at",
+    ];
+
+    fn to_row(&self) -> Vec<ExportCell> {
+        vec![
+            self.id.into(),
+            (&self.name).into(),
+            self.price.into(),
+            (&self.description).into(),
+            self.quantity.into(),
+            self.mfg_date.into(),
+            self.exp_date.into(),
+            self.supplier_id.into(),
+            self.supplier_name.as_ref().into(),
+            self.group_id.into(),
+            self.group_name.as_ref().into(),
+            self.created_at.into(),
+            self.updated_at.into(),
+        ]
+    }
+}
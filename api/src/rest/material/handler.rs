@@ -1,23 +1,47 @@
-use crate::rest::material::dto::{RequestCreateMaterialGroupDto, RequestUpdateMaterialGroupDto};
+use crate::rest::material::dto::{
+    RequestCreateMaterialDto, RequestCreateMaterialGroupDto, RequestMaterialFilterDto,
+    RequestMaterialGroupFilterDto, RequestUpdateMaterialGroupDto,
+};
 use crate::rest::middleware::auth::role_check;
+use crate::util::bulk::{
+    BulkBatch, BulkReport, RequestBulkOperation, RequestBulkQueryDto, MAX_BULK_ITEMS,
+};
 use crate::util::error::RestApiError;
-use crate::util::res::ApiResult;
-use crate::util::validation::ValidatedJson;
+use crate::util::etag::{etag, IfMatch};
+use crate::util::export::{export_response, Export, ExportQuery};
+use crate::util::import::{
+    import_response, ImportFile, ImportFileUpload, RequestImportQueryDto, MAX_IMPORT_BYTES,
+};
+use crate::util::list::RequestListQueryDto;
+use crate::util::res::{ApiResult, Null};
+use crate::util::validation::{ValidatedJson, ValidatedQuery};
 use axum::{
-    extract::{Extension, Path},
+    extract::{rejection::JsonRejection, DefaultBodyLimit, Extension, Multipart, Path},
     http::StatusCode,
     middleware,
-    response::IntoResponse,
-    routing::{delete, get, post, put},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
     Json, Router,
 };
 use lib::app_ctx::AppCtx;
+use lib::auth::model::AuthInfo;
+use lib::material::model::{ResponseMaterial, ResponseMaterialGroup};
+use lib::util::model::{DeletedRecord, ImportReport};
 use std::sync::Arc;
+use tracing::instrument;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 fn material_handler_admin() -> Router {
     Router::new()
         .route("/groups", post(create_material_group))
+        .route(
+            "/import",
+            post(import_materials).layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route("/groups/bulk", post(bulk_material_groups))
+        .route("/groups/deleted", get(get_deleted_material_groups))
+        .route("/groups/:id/restore", post(restore_material_group_by_id))
         .route(
             "/groups/:id",
             delete(delete_material_group_by_id).put(update_material_group_by_id),
@@ -29,6 +53,7 @@ fn material_handler_admin() -> Router {
 
 fn material_handler_user() -> Router {
     Router::new()
+        .route("/", get(get_all_materials))
         .route("/groups", get(get_all_material_groups))
         .route("/groups/:id", get(get_material_group_by_id))
         .route("/groups/sub/:group_name", get(get_sub_group_by_group_name))
@@ -37,19 +62,50 @@ fn material_handler_user() -> Router {
         }))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_material_group,
+    get_all_material_groups,
+    get_all_materials,
+    get_material_group_by_id,
+    get_sub_group_by_group_name,
+    delete_material_group_by_id,
+    update_material_group_by_id,
+    get_deleted_material_groups,
+    restore_material_group_by_id,
+    import_materials,
+    bulk_material_groups,
+))]
+pub struct MaterialApi;
+
 pub fn material_handler() -> Router {
     Router::new()
         .merge(material_handler_user())
         .merge(material_handler_admin())
 }
 
+#[utoipa::path(
+    post,
+    path = "/groups",
+    summary = "Create a material group",
+    request_body = RequestCreateMaterialGroupDto,
+    responses(
+        (status = 201, description = "Material group created", body = ApiResult<ResponseMaterialGroup>),
+        (status = 409, description = "Group already exists", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn create_material_group(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     ValidatedJson(_dto): ValidatedJson<RequestCreateMaterialGroupDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .material_use_case
-        .create_material_group(&_dto.to_create_material_group())
+        .create_material_group(
+            &_dto.to_create_material_group(),
+            Some(&_user_info.user_info.sub),
+        )
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -63,24 +119,112 @@ async fn create_material_group(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/groups",
+    summary = "List or export material groups",
+    params(RequestListQueryDto, RequestMaterialGroupFilterDto, ExportQuery),
+    responses(
+        (status = 200, description = "Material groups found", content(
+            (ApiResult<Vec<ResponseMaterialGroup>> = "application/json"),
+            (String = "text/csv"),
+            (String = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+            (String = "application/x-ndjson"),
+        )),
+    )
+)]
+#[instrument(skip_all)]
 async fn get_all_material_groups(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
-) -> Result<impl IntoResponse, RestApiError> {
+    Export(_format): Export,
+    ValidatedQuery(_query): ValidatedQuery<RequestListQueryDto>,
+    ValidatedQuery(_filter): ValidatedQuery<RequestMaterialGroupFilterDto>,
+) -> Result<Response, RestApiError> {
+    let query = _query.to_list_query(_filter.to_filters());
+    if let Some(format) = _format {
+        return export_response(format, "material_groups", move |sender| async move {
+            _app_ctx
+                .material_use_case
+                .export_material_groups(&query, &sender)
+                .await
+        })
+        .await;
+    }
     let result = _app_ctx
         .material_use_case
-        .get_all_material_groups()
+        .get_all_material_groups(&query)
         .await
         .map_err(RestApiError::from_lib)?;
     Ok((
         StatusCode::OK,
-        Json(ApiResult::from(
+        Json(ApiResult::from_page(
             result,
             "Material groups found".to_string(),
             StatusCode::OK.into(),
         )),
-    ))
+    )
+        .into_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "List or export materials",
+    params(RequestListQueryDto, RequestMaterialFilterDto, ExportQuery),
+    responses(
+        (status = 200, description = "Materials found", content(
+            (ApiResult<Vec<ResponseMaterial>> = "application/json"),
+            (String = "text/csv"),
+            (String = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+            (String = "application/x-ndjson"),
+        )),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_all_materials(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Export(_format): Export,
+    ValidatedQuery(_query): ValidatedQuery<RequestListQueryDto>,
+    ValidatedQuery(_filter): ValidatedQuery<RequestMaterialFilterDto>,
+) -> Result<Response, RestApiError> {
+    let query = _query.to_list_query(_filter.to_filters());
+    if let Some(format) = _format {
+        return export_response(format, "materials", move |sender| async move {
+            _app_ctx
+                .material_use_case
+                .export_materials(&query, &sender)
+                .await
+        })
+        .await;
+    }
+    let result = _app_ctx
+        .material_use_case
+        .get_all_materials(&query)
+        .await
+        .map_err(RestApiError::from_lib)?;
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from_page(
+            result,
+            "Materials found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/groups/{id}",
+    summary = "Get a material group",
+    params(("id" = Uuid, Path, description = "Material group id")),
+    responses(
+        (status = 200, description = "Material group found", body = ApiResult<ResponseMaterialGroup>,
+            headers(("ETag" = String, description = "Current version of the material group"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
 async fn get_material_group_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     Path(id): Path<Uuid>,
@@ -92,6 +236,7 @@ async fn get_material_group_by_id(
         .map_err(RestApiError::from_lib)?;
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "Material group found".to_string(),
@@ -100,18 +245,30 @@ async fn get_material_group_by_id(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/groups/sub/{group_name}",
+    summary = "List the sub groups of a group",
+    params(("group_name" = String, Path, description = "Group name"), RequestListQueryDto, RequestMaterialGroupFilterDto),
+    responses(
+        (status = 200, description = "Sub groups found", body = ApiResult<Vec<ResponseMaterialGroup>>),
+    )
+)]
+#[instrument(skip_all)]
 async fn get_sub_group_by_group_name(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     Path(group_name): Path<String>,
+    ValidatedQuery(_query): ValidatedQuery<RequestListQueryDto>,
+    ValidatedQuery(_filter): ValidatedQuery<RequestMaterialGroupFilterDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .material_use_case
-        .get_sub_group_by_group_name(&group_name)
+        .get_sub_group_by_group_name(&group_name, &_query.to_list_query(_filter.to_filters()))
         .await
         .map_err(RestApiError::from_lib)?;
     Ok((
         StatusCode::OK,
-        Json(ApiResult::from(
+        Json(ApiResult::from_page(
             result,
             "Sub groups found".to_string(),
             StatusCode::OK.into(),
@@ -119,39 +276,85 @@ async fn get_sub_group_by_group_name(
     ))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/groups/{id}",
+    summary = "Delete a material group",
+    params(
+        ("id" = Uuid, Path, description = "Material group id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being deleted"),
+    ),
+    responses(
+        (status = 200, description = "Material group deleted", body = ApiResult<Null>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "Materials still reference the group", body = ApiResult<Null>),
+        (status = 412, description = "Material group changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
 async fn delete_material_group_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     Path(id): Path<Uuid>,
+    IfMatch(_version): IfMatch,
 ) -> Result<impl IntoResponse, RestApiError> {
     _app_ctx
         .material_use_case
-        .delete_material_group_by_id(&id)
+        .delete_material_group_by_id(&id, _version, Some(&_user_info.user_info.sub))
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
         Json(ApiResult::from(
-            {},
+            (),
             "Material Group deleted successfully".to_string(),
             StatusCode::OK.into(),
         )),
     ))
 }
 
+#[utoipa::path(
+    put,
+    path = "/groups/{id}",
+    summary = "Update a material group",
+    params(
+        ("id" = Uuid, Path, description = "Material group id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed"),
+    ),
+    request_body = RequestUpdateMaterialGroupDto,
+    responses(
+        (status = 200, description = "Material group updated", body = ApiResult<ResponseMaterialGroup>,
+            headers(("ETag" = String, description = "New version of the material group"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "Group already exists", body = ApiResult<Null>),
+        (status = 412, description = "Material group changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %id))]
 async fn update_material_group_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     Path(id): Path<Uuid>,
+    IfMatch(_version): IfMatch,
     ValidatedJson(_dto): ValidatedJson<RequestUpdateMaterialGroupDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .material_use_case
-        .update_material_group_by_id(&id, &_dto.to_update_material_group_by_id())
+        .update_material_group_by_id(
+            &id,
+            &_dto.to_update_material_group_by_id(),
+            _version,
+            Some(&_user_info.user_info.sub),
+        )
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "Material Group updated successfully".to_string(),
@@ -159,3 +362,153 @@ async fn update_material_group_by_id(
         )),
     ))
 }
+
+#[utoipa::path(
+    get,
+    path = "/groups/deleted",
+    summary = "List deleted material groups",
+    responses(
+        (status = 200, description = "Deleted material groups found", body = ApiResult<Vec<DeletedRecord<ResponseMaterialGroup>>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_deleted_material_groups(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .material_use_case
+        .get_deleted_material_groups()
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Deleted material groups found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/groups/{id}/restore",
+    summary = "Restore a deleted material group",
+    params(("id" = Uuid, Path, description = "Material group id")),
+    responses(
+        (status = 200, description = "Material group restored", body = ApiResult<ResponseMaterialGroup>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn restore_material_group_by_id(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .material_use_case
+        .restore_material_group_by_id(&_id, Some(&_user_info.user_info.sub))
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Material group restored successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    summary = "Import materials from CSV or XLSX",
+    params(RequestImportQueryDto),
+    request_body(content = ImportFileUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Dry run report", body = ApiResult<ImportReport>),
+        (status = 201, description = "Materials imported", body = ApiResult<ImportReport>),
+        (status = 422, description = "Some rows are invalid", body = ApiResult<ImportReport>),
+    )
+)]
+#[instrument(skip_all)]
+async fn import_materials(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    ValidatedQuery(_query): ValidatedQuery<RequestImportQueryDto>,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, RestApiError> {
+    let file = ImportFile::from_multipart(multipart).await?;
+    let (rows, errors) = file.parse(RequestCreateMaterialDto::to_create_material);
+    // invalid rows still get their references checked so the report is complete
+    let report = _app_ctx
+        .material_use_case
+        .import_materials(
+            &rows,
+            _query.is_dry_run() || !errors.is_empty(),
+            Some(&_user_info.user_info.sub),
+        )
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok(import_response(
+        report,
+        file.len(),
+        errors,
+        _query.is_dry_run(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/groups/bulk",
+    summary = "Create, update and delete material groups in one request",
+    params(RequestBulkQueryDto),
+    request_body = [RequestBulkOperation<RequestCreateMaterialGroupDto, RequestUpdateMaterialGroupDto>],
+    responses(
+        (status = 200, description = "Every item succeeded", body = ApiResult<BulkReport<ResponseMaterialGroup>>),
+        (status = 207, description = "Best effort, some items failed", body = ApiResult<BulkReport<ResponseMaterialGroup>>),
+        (status = 422, description = "All or nothing, an item failed and nothing was written", body = ApiResult<BulkReport<ResponseMaterialGroup>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn bulk_material_groups(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    ValidatedQuery(_query): ValidatedQuery<RequestBulkQueryDto>,
+    payload: Result<
+        Json<
+            Vec<RequestBulkOperation<RequestCreateMaterialGroupDto, RequestUpdateMaterialGroupDto>>,
+        >,
+        JsonRejection,
+    >,
+) -> Result<impl IntoResponse, RestApiError> {
+    let Json(_items) = payload?;
+    let batch = BulkBatch::parse(
+        &_items,
+        _query.mode(),
+        MAX_BULK_ITEMS,
+        RequestCreateMaterialGroupDto::to_create_material_group,
+        RequestUpdateMaterialGroupDto::to_update_material_group_by_id,
+    )?;
+    let result = match batch.should_run() {
+        true => Some(
+            _app_ctx
+                .material_use_case
+                .bulk_material_groups(
+                    &batch.operations,
+                    batch.mode,
+                    Some(&_user_info.user_info.sub),
+                )
+                .await
+                .map_err(RestApiError::from_lib)?,
+        ),
+        false => None,
+    };
+
+    Ok(batch.into_response(result, |material_group| material_group.id))
+}
@@ -0,0 +1,90 @@
+use crate::util::error::RestApiError;
+use crate::util::metrics::{
+    metrics_handle, DB_POOL_ACQUIRE_SECONDS, DB_POOL_CONNECTIONS, DB_POOL_IDLE_CONNECTIONS,
+    DB_POOL_MAX_CONNECTIONS, LOW_STOCK_MATERIALS, STOCK_MATERIALS, STOCK_VALUE,
+};
+use crate::AppState;
+use axum::{
+    extract::Extension,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use std::time::Instant;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_metrics))]
+pub struct MetricsApi;
+
+// prometheus scrapes this with the METRICS_TOKEN of the config, without a login
+pub fn metrics_handler() -> Router {
+    Router::new().route("/", get(get_metrics))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "Prometheus metrics",
+    security((), ("metrics_token" = [])),
+    responses(
+        (status = 200, description = "Request, error, pool and stock metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 401, description = "METRICS_TOKEN is set and the request does not carry it"),
+    )
+)]
+// request metrics are recorded as they happen, pool and stock gauges are read on every scrape
+async fn get_metrics(
+    Extension(app_state): Extension<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, RestApiError> {
+    if let Some(token) = &app_state.config.server.metrics_token {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if bearer != Some(token.as_str()) {
+            return Err(RestApiError::Unauthorized(
+                "A valid metrics token is required".to_string(),
+            ));
+        }
+    }
+    record_pool(&app_state).await;
+    record_stock(&app_state).await;
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics_handle().render(),
+    ))
+}
+
+async fn record_pool(app_state: &AppState) {
+    let pool = &app_state.pool;
+    metrics::gauge!(DB_POOL_CONNECTIONS).set(pool.size() as f64);
+    metrics::gauge!(DB_POOL_IDLE_CONNECTIONS).set(pool.num_idle() as f64);
+    metrics::gauge!(DB_POOL_MAX_CONNECTIONS).set(pool.options().get_max_connections() as f64);
+    // how long a request would wait for a connection right now, high when the pool is exhausted
+    let started = Instant::now();
+    match pool.acquire().await {
+        Ok(_) => metrics::gauge!(DB_POOL_ACQUIRE_SECONDS).set(started.elapsed().as_secs_f64()),
+        Err(e) => tracing::warn!(error = %e, "no connection for the pool metrics"),
+    }
+}
+
+async fn record_stock(app_state: &AppState) {
+    let totals = app_state
+        .arc_state
+        .material_use_case
+        .get_stock_totals(app_state.config.low_stock_threshold)
+        .await;
+    match totals {
+        Ok(totals) => {
+            metrics::gauge!(STOCK_VALUE).set(totals.value as f64);
+            metrics::gauge!(STOCK_MATERIALS).set(totals.materials as f64);
+            metrics::gauge!(LOW_STOCK_MATERIALS).set(totals.low_stock as f64);
+        }
+        // the previous values are kept, the scrape still returns the request metrics
+        Err(e) => tracing::warn!(error = %e, "no stock totals for the metrics"),
+    }
+}
@@ -3,12 +3,18 @@ use std::{
     task::{Context, Poll},
 };
 
-use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
 use futures_util::future::BoxFuture;
 use lib::auth::model::AuthInfo;
 use tower::{Layer, Service};
+use tracing::field;
 
-use crate::util::res::{ApiResult, Null};
+use crate::util::error::RestApiError;
 use crate::AppState;
 
 // Authentification Middleware
@@ -53,7 +59,7 @@ where
         self.inner.poll_ready(cx)
     }
 
-    fn call<'a>(&'a mut self, mut request: Request) -> Self::Future {
+    fn call(&mut self, mut request: Request) -> Self::Future {
         let clone = self.inner.clone();
         let use_case = self.state.arc_state.auth_use_case.clone();
         let mut srv = std::mem::replace(&mut self.inner, clone);
@@ -65,24 +71,19 @@ where
                     if let Some(token_no_bearer) = token_str.strip_prefix("Bearer ") {
                         let result = use_case.get_info(token_no_bearer).await;
                         if let Ok(user_info) = result {
+                            // shows up on the request span and every line logged after it
+                            tracing::Span::current()
+                                .record("user_id", field::display(&user_info.user_info.sub));
                             request.extensions_mut().insert(user_info);
                             return srv.call(request).await;
                         }
                     }
                 }
             }
-            Ok(Response::builder()
-                .header("Content-Type", "application/json")
-                .status(401)
-                .body(
-                    ApiResult::from(
-                        Null,
-                        "no credentials or invalid credentials".to_string(),
-                        StatusCode::UNAUTHORIZED.as_u16(),
-                    )
-                    .into(),
-                )
-                .unwrap())
+            Ok(
+                RestApiError::Unauthorized("no credentials or invalid credentials".to_string())
+                    .into_response(),
+            )
         })
     }
 }
@@ -151,18 +152,7 @@ where
                     return srv.call(req).await;
                 }
             }
-            Ok(Response::builder()
-                .header("Content-Type", "application/json")
-                .status(403)
-                .body(
-                    ApiResult::from(
-                        Null,
-                        "no permission".to_string(),
-                        StatusCode::FORBIDDEN.as_u16(),
-                    )
-                    .into(),
-                )
-                .unwrap())
+            Ok(RestApiError::Forbidden("no permission".to_string()).into_response())
         })
     }
 }
@@ -179,16 +169,5 @@ pub async fn role_check(
             return Ok(next.run(req).await);
         }
     }
-    Ok(Response::builder()
-        .header("Content-Type", "application/json")
-        .status(403)
-        .body(
-            ApiResult::from(
-                Null,
-                "no permission".to_string(),
-                StatusCode::FORBIDDEN.as_u16(),
-            )
-            .into(),
-        )
-        .unwrap())
+    Ok(RestApiError::Forbidden("no permission".to_string()).into_response())
 }
@@ -0,0 +1,124 @@
+use std::task::{Context, Poll};
+
+use axum::{
+    body::to_bytes,
+    extract::Request,
+    http::{header, HeaderMap},
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::rest::middleware::request_id::RequestId;
+use crate::util::error::{ErrorCode, Problem, PROBLEM_JSON};
+
+// plain text error bodies from axum are short, anything longer is not read
+const MAX_PLAIN_ERROR_BYTES: usize = 4096;
+
+// Error Format Middleware
+// renders every error response the same way: with the request id and the path, as
+// `application/problem+json` when the client asks for it (or always with `problem_json`)
+// and as the ApiResult envelope otherwise; must sit inside RequestIdLayer
+#[derive(Clone)]
+pub struct ErrorLayer {
+    problem_json: bool,
+}
+
+impl ErrorLayer {
+    pub fn new(problem_json: bool) -> Self {
+        Self { problem_json }
+    }
+}
+
+impl<S> Layer<S> for ErrorLayer {
+    type Service = ErrorMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ErrorMiddleware {
+            inner,
+            problem_json: self.problem_json,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ErrorMiddleware<S> {
+    inner: S,
+    problem_json: bool,
+}
+
+impl<S> Service<Request> for ErrorMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut srv = std::mem::replace(&mut self.inner, clone);
+        let problem_json = self.problem_json || accepts_problem_json(request.headers());
+        let request_id = request.extensions().get::<RequestId>().cloned();
+        let instance = request.uri().path().to_string();
+        Box::pin(async move {
+            let response = srv.call(request).await?;
+            let status = response.status();
+            if !status.is_client_error() && !status.is_server_error() {
+                return Ok(response);
+            }
+            // reports a handler answers with on purpose, like an import with invalid rows
+            if response.extensions().get::<Problem>().is_none() && is_json(response.headers()) {
+                return Ok(response);
+            }
+            let (mut parts, body) = response.into_parts();
+            let mut problem = match parts.extensions.remove::<Problem>() {
+                Some(problem) => problem,
+                // errors axum answers by itself, like unknown routes or a malformed path parameter
+                None => {
+                    let detail = to_bytes(body, MAX_PLAIN_ERROR_BYTES)
+                        .await
+                        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+                        .unwrap_or_default();
+                    let detail = if detail.is_empty() {
+                        status.canonical_reason().unwrap_or_default().to_string()
+                    } else {
+                        detail
+                    };
+                    Problem::new(status, ErrorCode::from_status(status), detail)
+                }
+            };
+            problem.instance = Some(instance);
+            problem.request_id = request_id.map(|id| id.0);
+
+            // headers such as Retry-After are kept, the body is rendered again
+            let rendered = problem.to_response(problem_json);
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts.headers.extend(rendered.headers().clone());
+            let (rendered_parts, body) = rendered.into_parts();
+            parts.extensions.extend(rendered_parts.extensions);
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+fn accepts_problem_json(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| media_type.split(';').next().unwrap_or_default().trim() == PROBLEM_JSON)
+}
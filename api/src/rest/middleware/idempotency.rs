@@ -0,0 +1,235 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{OriginalUri, Request},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures_util::future::BoxFuture;
+use lib::auth::model::AuthInfo;
+use lib::idempotency::model::{IdempotencyStatus, IdempotentRequest, StoredResponse};
+use tower::{Layer, Service};
+
+use crate::util::error::RestApiError;
+use crate::AppState;
+
+pub static IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+pub static IDEMPOTENCY_REPLAYED_HEADER: HeaderName =
+    HeaderName::from_static("idempotency-replayed");
+
+const MAX_KEY_LEN: usize = 255;
+// above the body limit of every route, imports included
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+// response headers worth replaying, the rest belongs to the original exchange
+const STORED_HEADERS: [HeaderName; 4] = [
+    header::CONTENT_TYPE,
+    header::ETAG,
+    header::LOCATION,
+    header::CONTENT_DISPOSITION,
+];
+
+// Idempotency Middleware
+// POST, PUT, PATCH and DELETE requests sent with an `Idempotency-Key` header run once per key
+// and user, a retry gets the stored response back; must sit inside AuthLayer
+#[derive(Clone)]
+pub struct IdempotencyLayer {
+    state: Arc<AppState>,
+}
+
+impl IdempotencyLayer {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+}
+
+impl<S> Layer<S> for IdempotencyLayer {
+    type Service = IdempotencyMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IdempotencyMiddleware {
+            inner,
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IdempotencyMiddleware<S> {
+    inner: S,
+    state: Arc<AppState>,
+}
+
+impl<S> Service<Request> for IdempotencyMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut srv = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+        Box::pin(async move {
+            let mutating = matches!(
+                *request.method(),
+                Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+            );
+            let key = request.headers().get(&IDEMPOTENCY_KEY_HEADER).cloned();
+            let user_id = request
+                .extensions()
+                .get::<AuthInfo>()
+                .map(|auth_info| auth_info.user_info.sub);
+            let (Some(key), Some(user_id), true) = (key, user_id, mutating) else {
+                return srv.call(request).await;
+            };
+            let Some(key) = key.to_str().ok().filter(|key| is_valid_key(key)) else {
+                return Ok(RestApiError::BadRequest(format!(
+                    "Idempotency-Key must be 1 to {MAX_KEY_LEN} visible ascii characters"
+                ))
+                .into_response());
+            };
+
+            let (parts, body) = request.into_parts();
+            let Ok(body) = to_bytes(body, MAX_BODY_BYTES).await else {
+                return Ok(
+                    RestApiError::PayloadTooLarge("Request body is too large".to_string())
+                        .into_response(),
+                );
+            };
+            // nested routers see the path without their prefix
+            let path = parts
+                .extensions
+                .get::<OriginalUri>()
+                .map(|uri| uri.0.clone())
+                .unwrap_or_else(|| parts.uri.clone());
+            let path = path
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or_default();
+            let idempotent =
+                IdempotentRequest::new(user_id, key, parts.method.as_str(), path, &body);
+
+            let use_case = &state.arc_state.idempotency_use_case;
+            match use_case.begin(&idempotent).await {
+                Ok(IdempotencyStatus::Started) => {}
+                Ok(IdempotencyStatus::Completed(stored)) => return Ok(replay(stored)),
+                Ok(IdempotencyStatus::InProgress) => {
+                    return Ok(RestApiError::IdempotencyKeyInUse(
+                        "A request with this Idempotency-Key is still being processed".to_string(),
+                    )
+                    .into_response())
+                }
+                Ok(IdempotencyStatus::Mismatch) => {
+                    return Ok(RestApiError::IdempotencyKeyReused(
+                        "This Idempotency-Key was already used for a different request".to_string(),
+                    )
+                    .into_response())
+                }
+                Err(e) => return Ok(RestApiError::from_lib(e).into_response()),
+            }
+            let guard = ReleaseOnDrop::new(state.clone(), &idempotent);
+
+            let response = srv
+                .call(Request::from_parts(parts, Body::from(body)))
+                .await?;
+            // errors change nothing, the key is freed so a retry runs the request again
+            if !response.status().is_success() {
+                let _ = use_case.release(&idempotent).await;
+                guard.disarm();
+                return Ok(response);
+            }
+
+            let (parts, body) = response.into_parts();
+            let Ok(body) = to_bytes(body, MAX_BODY_BYTES).await else {
+                let _ = use_case.release(&idempotent).await;
+                guard.disarm();
+                return Ok(RestApiError::InternalServerError(
+                    "Response is too large to be stored".to_string(),
+                )
+                .into_response());
+            };
+            let stored = StoredResponse {
+                status_code: parts.status.as_u16() as i32,
+                headers: STORED_HEADERS
+                    .iter()
+                    .filter_map(|name| {
+                        let value = parts.headers.get(name)?.to_str().ok()?;
+                        Some((name.to_string(), value.to_string()))
+                    })
+                    .collect(),
+                body: body.to_vec(),
+            };
+            if use_case.complete(&idempotent, &stored).await.is_err() {
+                let _ = use_case.release(&idempotent).await;
+            }
+            guard.disarm();
+            Ok(Response::from_parts(parts, Body::from(body)))
+        })
+    }
+}
+
+// frees a started key when the request is dropped half way, by a client that went away or a
+// timeout, which would otherwise leave the key in progress until it expires
+struct ReleaseOnDrop {
+    state: Arc<AppState>,
+    request: Option<IdempotentRequest>,
+}
+
+impl ReleaseOnDrop {
+    fn new(state: Arc<AppState>, request: &IdempotentRequest) -> Self {
+        Self {
+            state,
+            request: Some(request.clone()),
+        }
+    }
+
+    // the request finished and took care of the key itself
+    fn disarm(mut self) {
+        self.request = None;
+    }
+}
+
+impl Drop for ReleaseOnDrop {
+    fn drop(&mut self) {
+        let Some(request) = self.request.take() else {
+            return;
+        };
+        let state = self.state.clone();
+        // drop cannot wait, the release runs on its own
+        tokio::spawn(async move {
+            let _ = state.arc_state.idempotency_use_case.release(&request).await;
+        });
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status_code as u16).unwrap_or(StatusCode::OK);
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(
+        IDEMPOTENCY_REPLAYED_HEADER.clone(),
+        HeaderValue::from_static("true"),
+    );
+    response
+}
@@ -0,0 +1,79 @@
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::util::metrics::{metrics_handle, HTTP_REQUESTS, HTTP_REQUEST_DURATION};
+
+// Metrics Middleware
+// counts and times every request by its route pattern (`/api/users/:id`), not the concrete path,
+// so ids do not create a series each. Requests no route matched share the route `unmatched`.
+#[derive(Clone)]
+pub struct MetricsLayer;
+
+impl MetricsLayer {
+    pub fn new() -> Self {
+        // the recorder has to be in place before the first request is counted
+        metrics_handle();
+        Self
+    }
+}
+
+impl Default for MetricsLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsMiddleware<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for MetricsMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut srv = std::mem::replace(&mut self.inner, clone);
+        let method = request.method().to_string();
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        Box::pin(async move {
+            let started = Instant::now();
+            let response = srv.call(request).await?;
+            let status = response.status().as_u16().to_string();
+            metrics::histogram!(HTTP_REQUEST_DURATION, "method" => method.clone(), "route" => route.clone())
+                .record(started.elapsed().as_secs_f64());
+            metrics::counter!(HTTP_REQUESTS, "method" => method, "route" => route, "status" => status)
+                .increment(1);
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::Request,
+    http::{
+        header::{AUTHORIZATION, RETRY_AFTER},
+        HeaderValue,
+    },
+    response::{IntoResponse, Response},
+};
+use config::{Keys, RateLimit};
+use futures_util::future::BoxFuture;
+use lib::auth::auth::Auth;
+use tower::{Layer, Service};
+
+use crate::util::client::client_ip;
+use crate::util::error::RestApiError;
+
+// the store never holds more clients than this, see `make_room`
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    limit: RateLimit,
+    // tell whose token a request carries
+    keys: Keys,
+    clients: Mutex<HashMap<String, Bucket>>,
+}
+
+impl Buckets {
+    fn refill_per_second(&self) -> f64 {
+        self.limit.per_minute as f64 / 60.0
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_per_second()).min(self.limit.burst as f64)
+    }
+
+    // takes one token for `key`, or returns how long the client has to wait for the next one
+    fn acquire(&self, key: String) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if clients.len() >= MAX_TRACKED_CLIENTS && !clients.contains_key(&key) {
+            self.make_room(&mut clients, now);
+        }
+
+        let bucket = clients.entry(key).or_insert(Bucket {
+            tokens: self.limit.burst as f64,
+            updated_at: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let refill_per_second = self.refill_per_second();
+        if refill_per_second <= 0.0 {
+            return Err(Duration::MAX);
+        }
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / refill_per_second,
+        ))
+    }
+
+    // drops the buckets that refilled completely, forgetting them changes nothing. When clients
+    // keep coming with new keys there may be none, then the tenth seen longest ago goes, so
+    // a flood of keys costs one pass per thousand of them instead of one per request.
+    fn make_room(&self, clients: &mut HashMap<String, Bucket>, now: Instant) {
+        let burst = self.limit.burst as f64;
+        clients.retain(|_, bucket| self.refilled(bucket, now) < burst);
+        if clients.len() < MAX_TRACKED_CLIENTS {
+            return;
+        }
+        let mut seen: Vec<(Instant, String)> = clients
+            .iter()
+            .map(|(key, bucket)| (bucket.updated_at, key.clone()))
+            .collect();
+        let evicted = MAX_TRACKED_CLIENTS / 10;
+        seen.select_nth_unstable(evicted);
+        for (_, key) in &seen[..evicted] {
+            clients.remove(key);
+        }
+    }
+}
+
+// Rate Limiting Middleware
+// sits outside AuthLayer so rejected requests never reach the database. Requests with a token
+// signed by us are limited per user, whether its session is still active is checked afterwards.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    buckets: Arc<Buckets>,
+}
+
+impl RateLimitLayer {
+    pub fn new(limit: RateLimit, keys: &Keys) -> Self {
+        Self {
+            buckets: Arc::new(Buckets {
+                limit,
+                keys: keys.clone(),
+                clients: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitMiddleware {
+            inner,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    buckets: Arc<Buckets>,
+}
+
+impl<S> Service<Request> for RateLimitMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut srv = std::mem::replace(&mut self.inner, clone);
+        if self.buckets.limit.burst == 0 {
+            return Box::pin(srv.call(request));
+        }
+
+        let (parts, body) = request.into_parts();
+        let user = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| Auth::decode_token(&self.buckets.keys, token).ok());
+        let key = match user {
+            Some(user_info) => format!("user:{}", user_info.sub),
+            None => format!("ip:{}", client_ip(&parts).unwrap_or_default()),
+        };
+        let request = Request::from_parts(parts, body);
+        let acquired = self.buckets.acquire(key);
+
+        Box::pin(async move {
+            match acquired {
+                Ok(()) => srv.call(request).await,
+                Err(retry_after) => {
+                    let mut response =
+                        RestApiError::TooManyRequests("too many requests".to_string())
+                            .into_response();
+                    response.headers_mut().insert(
+                        RETRY_AFTER,
+                        HeaderValue::from(
+                            retry_after.as_secs_f64().ceil().min(u32::MAX as f64) as u32
+                        ),
+                    );
+                    Ok(response)
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    response::Response,
+};
+use futures_util::future::BoxFuture;
+use std::time::Instant;
+use tower::{Layer, Service};
+use tracing::{field, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+
+use crate::util::logging::remote_context;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// longest id accepted from a client, anything else gets a fresh one
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    // keeps the caller's id so requests can be traced through a proxy, if it is sane
+    fn from_request(request: &Request) -> Self {
+        let id = request
+            .headers()
+            .get(&REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_LEN
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c))
+            })
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        Self(id)
+    }
+}
+
+// Request Id Middleware
+// tags every request with an id, available to handlers as an extension and echoed in the response.
+// Everything logged while serving the request belongs to its `request` span, which carries the id.
+#[derive(Clone, Default)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdMiddleware { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestIdMiddleware<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RequestIdMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut srv = std::mem::replace(&mut self.inner, clone);
+        let request_id = RequestId::from_request(&request);
+        let span = tracing::info_span!(
+            "request",
+            request_id = %request_id.0,
+            method = %request.method(),
+            path = %request.uri().path(),
+            status = field::Empty,
+            user_id = field::Empty,
+        );
+        // only fails when spans are not exported
+        let _ = span.set_parent(remote_context(request.headers()));
+        request.extensions_mut().insert(request_id.clone());
+        Box::pin(async move {
+            let started = Instant::now();
+            let mut response = srv.call(request).instrument(span.clone()).await?;
+            let status = response.status().as_u16();
+            span.record("status", status);
+            tracing::info!(
+                parent: &span,
+                status,
+                latency_ms = started.elapsed().as_millis() as u64,
+                "request completed"
+            );
+            if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+                response
+                    .headers_mut()
+                    .insert(REQUEST_ID_HEADER.clone(), value);
+            }
+            Ok(response)
+        })
+    }
+}
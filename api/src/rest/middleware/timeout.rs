@@ -0,0 +1,81 @@
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures_util::future::BoxFuture;
+use tower::{Layer, Service};
+
+use crate::util::export::Export;
+
+// Timeout Middleware
+// answers 408 when a handler takes longer than `timeout`, dropping it rolls back its open
+// transaction. Exports are left alone, an xlsx file is written in full before its first byte.
+#[derive(Clone)]
+pub struct TimeoutLayer {
+    timeout: Duration,
+}
+
+impl TimeoutLayer {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S> Layer<S> for TimeoutLayer {
+    type Service = TimeoutMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimeoutMiddleware {
+            inner,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TimeoutMiddleware<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+// a listing asked for as csv, xlsx or ndjson, an invalid format is refused quickly anyway
+fn is_export(request: &Request) -> bool {
+    request.method() == Method::GET
+        && matches!(
+            Export::requested(request.uri(), request.headers()),
+            Ok(Export(Some(_)))
+        )
+}
+
+impl<S> Service<Request> for TimeoutMiddleware<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut srv = std::mem::replace(&mut self.inner, clone);
+        if is_export(&request) {
+            return Box::pin(srv.call(request));
+        }
+        let timeout = self.timeout;
+        Box::pin(async move {
+            match tokio::time::timeout(timeout, srv.call(request)).await {
+                Ok(response) => response,
+                Err(_) => Ok(StatusCode::REQUEST_TIMEOUT.into_response()),
+            }
+        })
+    }
+}
@@ -0,0 +1,180 @@
+use crate::rest::audit::handler::AuditApi;
+use crate::rest::auth::handler::{AuthApi, WellKnownApi};
+use crate::rest::health::handler::{ApiRootApi, HealthApi};
+use crate::rest::material::handler::MaterialApi;
+use crate::rest::metrics::handler::MetricsApi;
+use crate::rest::role::handler::RoleApi;
+use crate::rest::search::handler::SearchApi;
+use crate::rest::supplier::handler::SupplierApi;
+use crate::rest::user::handler::UserApi;
+use crate::util::error::{ApiError, ErrorCode, FieldError, Problem, PROBLEM_JSON};
+use crate::util::res::{ApiResult, Null};
+use axum::Router;
+use utoipa::openapi::path::{Operation, ParameterBuilder, ParameterIn};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{Components, Object, RefOr, Required, Response, ResponseBuilder};
+use utoipa::{Modify, OpenApi, PartialSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+// the nest paths mirror where build_app mounts each router
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Inventory Management System API",
+        description = "Responses are wrapped in `ApiResult`: the payload is in `rslt.data`, lists add a `meta` object. \
+            Errors carry an `error` object with a stable `code`, or are RFC 7807 problem details \
+            when `application/problem+json` is accepted."
+    ),
+    components(schemas(ApiError, ErrorCode, FieldError, Problem)),
+    nest(
+        (path = "/api/auth", api = AuthApi, tags = ["auth"]),
+        (path = "/api/users", api = UserApi, tags = ["users"]),
+        (path = "/api/roles", api = RoleApi, tags = ["roles"]),
+        (path = "/api/materials", api = MaterialApi, tags = ["materials"]),
+        (path = "/api/suppliers", api = SupplierApi, tags = ["suppliers"]),
+        (path = "/api/audit-logs", api = AuditApi, tags = ["audit logs"]),
+        (path = "/api/search", api = SearchApi, tags = ["search"]),
+        (path = "/.well-known", api = WellKnownApi, tags = ["auth"]),
+        (path = "/api", api = ApiRootApi, tags = ["health"]),
+        (path = "/health", api = HealthApi, tags = ["health"]),
+        (path = "/metrics", api = MetricsApi, tags = ["metrics"]),
+    ),
+    modifiers(&SecurityAddon, &ErrorResponses, &IdempotencyHeader),
+    security(("bearer" = [])),
+)]
+pub struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // the crate has no license, drop the empty one taken from Cargo.toml
+        openapi.info.license = None;
+        openapi
+            .components
+            .get_or_insert_with(Components::new)
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("JWT")
+                        .build(),
+                ),
+            );
+        openapi
+            .components
+            .get_or_insert_with(Components::new)
+            .add_security_scheme(
+                "metrics_token",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .description(Some("METRICS_TOKEN of the server configuration"))
+                        .build(),
+                ),
+            );
+    }
+}
+
+// errors every route can answer with, so the handlers only document their own
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                let public = is_public(operation);
+                let responses = &mut operation.responses.responses;
+                let mut add = |status: &str, description: &str| {
+                    responses
+                        .entry(status.to_string())
+                        .or_insert_with(|| error(description));
+                };
+                add("400", "Invalid input");
+                if !public {
+                    add("401", "Missing, invalid or revoked token");
+                    add("403", "The caller's role may not use this route");
+                }
+                add("429", "Rate limit exceeded, see Retry-After");
+                add("500", "Internal server error");
+            }
+        }
+    }
+}
+
+// authenticated writes can be retried safely with an Idempotency-Key
+struct IdempotencyHeader;
+
+impl Modify for IdempotencyHeader {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [&mut item.put, &mut item.post, &mut item.delete]
+                .into_iter()
+                .flatten()
+                .filter(|operation| !is_public(operation))
+            {
+                operation.parameters.get_or_insert_with(Vec::new).push(
+                    ParameterBuilder::new()
+                        .name("Idempotency-Key")
+                        .parameter_in(ParameterIn::Header)
+                        .required(Required::False)
+                        .description(Some(
+                            "Runs the request once, a retry with the same key and body gets \
+                            the stored response back with `Idempotency-Replayed: true`",
+                        ))
+                        .schema(Some(Object::with_type(utoipa::openapi::Type::String)))
+                        .build(),
+                );
+                let responses = &mut operation.responses.responses;
+                responses.entry("409".to_string()).or_insert_with(|| {
+                    error("The first request with this Idempotency-Key is still running")
+                });
+                responses.entry("422".to_string()).or_insert_with(|| {
+                    error("The Idempotency-Key was used for a different request")
+                });
+            }
+        }
+    }
+}
+
+fn error(description: &str) -> RefOr<Response> {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            utoipa::openapi::ContentBuilder::new()
+                .schema(Some(ApiResult::<Null>::schema()))
+                .build(),
+        )
+        .content(
+            PROBLEM_JSON,
+            utoipa::openapi::ContentBuilder::new()
+                .schema(Some(Problem::schema()))
+                .build(),
+        )
+        .build()
+        .into()
+}
+
+// public routes opt out of the global bearer requirement with an empty one
+fn is_public(operation: &Operation) -> bool {
+    operation.security.as_ref().is_some_and(|security| {
+        serde_json::to_value(security).is_ok_and(|value| value == serde_json::json!([{}]))
+    })
+}
+
+// the generated document and a Swagger UI to browse it
+pub fn openapi_handler() -> Router {
+    SwaggerUi::new("/api/docs")
+        .url("/api/openapi.json", ApiDoc::openapi())
+        .into()
+}
@@ -1,12 +1,14 @@
+use crate::util::list::filters;
 use lib::role::model::RequestRole;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 static RE_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_.]{3,32}$").unwrap());
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RequestRoleDto {
     #[validate(regex(path = *RE_NAME, message = "invalid"))]
     pub name: String,
@@ -19,3 +21,16 @@ impl RequestRoleDto {
         }
     }
 }
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestRoleFilterDto {
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub name: Option<String>,
+}
+
+impl RequestRoleFilterDto {
+    pub fn to_filters(&self) -> Vec<(String, String)> {
+        filters([("name", &self.name)])
+    }
+}
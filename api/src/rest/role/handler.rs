@@ -1,23 +1,44 @@
 use crate::rest::middleware::auth::role_check;
-use crate::rest::role::dto::RequestRoleDto;
+use crate::rest::role::dto::{RequestRoleDto, RequestRoleFilterDto};
 use crate::util::error::RestApiError;
+use crate::util::etag::{etag, IfMatch};
+use crate::util::list::RequestListQueryDto;
 use crate::util::res::{ApiResult, Null};
-use crate::util::validation::ValidatedJson;
+use crate::util::validation::{ValidatedJson, ValidatedQuery};
 use axum::{
     extract::{Extension, Path},
     http::StatusCode,
     middleware,
     response::IntoResponse,
-    routing::{delete, get, post, put},
+    routing::{get, post},
     Json, Router,
 };
 use lib::app_ctx::AppCtx;
+use lib::auth::model::AuthInfo;
+use lib::role::model::ResponseRole;
+use lib::util::model::DeletedRecord;
 use std::sync::Arc;
+use tracing::instrument;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_role,
+    get_roles,
+    get_deleted_roles,
+    restore_role,
+    get_role_by_id,
+    update_role,
+    delete_role,
+))]
+pub struct RoleApi;
+
 pub fn role_handler() -> Router {
     Router::new()
         .route("/", post(create_role).get(get_roles))
+        .route("/deleted", get(get_deleted_roles))
+        .route("/:id/restore", post(restore_role))
         .route(
             "/:id",
             get(get_role_by_id).put(update_role).delete(delete_role),
@@ -27,13 +48,25 @@ pub fn role_handler() -> Router {
         }))
 }
 
+#[utoipa::path(
+    post,
+    path = "",
+    summary = "Create a role",
+    request_body = RequestRoleDto,
+    responses(
+        (status = 201, description = "Role created", body = ApiResult<ResponseRole>),
+        (status = 409, description = "Name already taken", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn create_role(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     ValidatedJson(_dto): ValidatedJson<RequestRoleDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .role_use_case
-        .create_role(&_dto.to_request_role())
+        .create_role(&_dto.to_request_role(), Some(&_user_info.user_info.sub))
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -47,18 +80,30 @@ async fn create_role(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "List roles",
+    params(RequestListQueryDto, RequestRoleFilterDto),
+    responses(
+        (status = 200, description = "Roles found", body = ApiResult<Vec<ResponseRole>>),
+    )
+)]
+#[instrument(skip_all)]
 async fn get_roles(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    ValidatedQuery(_query): ValidatedQuery<RequestListQueryDto>,
+    ValidatedQuery(_filter): ValidatedQuery<RequestRoleFilterDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .role_use_case
-        .get_roles()
+        .get_roles(&_query.to_list_query(_filter.to_filters()))
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
-        Json(ApiResult::from(
+        Json(ApiResult::from_page(
             result,
             "Roles found".to_string(),
             StatusCode::OK.into(),
@@ -66,19 +111,46 @@ async fn get_roles(
     ))
 }
 
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    summary = "Update a role",
+    params(
+        ("id" = Uuid, Path, description = "Role id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed"),
+    ),
+    request_body = RequestRoleDto,
+    responses(
+        (status = 200, description = "Role updated", body = ApiResult<ResponseRole>,
+            headers(("ETag" = String, description = "New version of the role"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "Name already taken", body = ApiResult<Null>),
+        (status = 412, description = "Role changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
 async fn update_role(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     Path(_id): Path<Uuid>,
+    IfMatch(_version): IfMatch,
     ValidatedJson(_dto): ValidatedJson<RequestRoleDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .role_use_case
-        .update_role(&_id, &_dto.to_request_role())
+        .update_role(
+            &_id,
+            &_dto.to_request_role(),
+            _version,
+            Some(&_user_info.user_info.sub),
+        )
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "Role updated successfully".to_string(),
@@ -87,13 +159,32 @@ async fn update_role(
     ))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    summary = "Delete a role",
+    params(
+        ("id" = Uuid, Path, description = "Role id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being deleted"),
+    ),
+    responses(
+        (status = 200, description = "Role deleted", body = ApiResult<Null>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "Users still have the role", body = ApiResult<Null>),
+        (status = 412, description = "Role changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
 async fn delete_role(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     Path(_id): Path<Uuid>,
+    IfMatch(_version): IfMatch,
 ) -> Result<impl IntoResponse, RestApiError> {
     _app_ctx
         .role_use_case
-        .delete_role(&_id)
+        .delete_role(&_id, _version, Some(&_user_info.user_info.sub))
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -107,6 +198,18 @@ async fn delete_role(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    summary = "Get a role",
+    params(("id" = Uuid, Path, description = "Role id")),
+    responses(
+        (status = 200, description = "Role found", body = ApiResult<ResponseRole>,
+            headers(("ETag" = String, description = "Current version of the role"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
 async fn get_role_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     Path(_id): Path<Uuid>,
@@ -119,6 +222,7 @@ async fn get_role_by_id(
 
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "Role found".to_string(),
@@ -126,3 +230,63 @@ async fn get_role_by_id(
         )),
     ))
 }
+
+#[utoipa::path(
+    get,
+    path = "/deleted",
+    summary = "List deleted roles",
+    responses(
+        (status = 200, description = "Deleted roles found", body = ApiResult<Vec<DeletedRecord<ResponseRole>>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_deleted_roles(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .role_use_case
+        .get_deleted_roles()
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Deleted roles found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    summary = "Restore a deleted role",
+    params(("id" = Uuid, Path, description = "Role id")),
+    responses(
+        (status = 200, description = "Role restored", body = ApiResult<ResponseRole>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn restore_role(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .role_use_case
+        .restore_role(&_id, Some(&_user_info.user_info.sub))
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Role restored successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
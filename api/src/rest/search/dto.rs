@@ -0,0 +1,39 @@
+use lib::search::model::{SearchKind, SearchQuery};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+static RE_TYPES: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(material|supplier|material_group)(,(material|supplier|material_group))*$")
+        .unwrap()
+});
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestSearchQueryDto {
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub q: String,
+    // comma separated result types, all of them when missing
+    #[validate(regex(path = *RE_TYPES, message = "invalid"))]
+    pub types: Option<String>,
+    #[validate(range(min = 1, max = 100, message = "invalid"))]
+    pub limit: Option<i64>,
+    pub include_deleted: Option<bool>,
+}
+
+impl RequestSearchQueryDto {
+    pub fn to_search_query(&self, is_admin: bool) -> SearchQuery {
+        let kinds = match &self.types {
+            Some(types) => types.split(',').filter_map(SearchKind::from_name).collect(),
+            None => SearchKind::ALL.to_vec(),
+        };
+        SearchQuery {
+            term: self.q.trim().to_string(),
+            kinds,
+            include_deleted: is_admin && self.include_deleted.unwrap_or(false),
+            limit: self.limit.unwrap_or(20),
+        }
+    }
+}
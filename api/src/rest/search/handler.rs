@@ -0,0 +1,59 @@
+use crate::rest::middleware::auth::role_check;
+use crate::rest::search::dto::RequestSearchQueryDto;
+use crate::util::error::RestApiError;
+use crate::util::res::ApiResult;
+use crate::util::validation::ValidatedQuery;
+use axum::{
+    extract::Extension, http::StatusCode, middleware, response::IntoResponse, routing::get, Json,
+    Router,
+};
+use lib::app_ctx::AppCtx;
+use lib::auth::model::AuthInfo;
+use lib::search::model::ResponseSearchResult;
+use std::sync::Arc;
+use tracing::instrument;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(paths(search,))]
+pub struct SearchApi;
+
+pub fn search_handler() -> Router {
+    Router::new()
+        .route("/", get(search))
+        .route_layer(middleware::from_fn(|req, next| {
+            role_check(req, next, vec!["Admin", "User"])
+        }))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "Search materials, suppliers and material groups",
+    params(RequestSearchQueryDto),
+    responses(
+        (status = 200, description = "Search results found", body = ApiResult<Vec<ResponseSearchResult>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn search(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    ValidatedQuery(_dto): ValidatedQuery<RequestSearchQueryDto>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let is_admin = _user_info.user_info.roles == "Admin";
+    let result = _app_ctx
+        .search_use_case
+        .search(&_dto.to_search_query(is_admin))
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Search results found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
@@ -1,12 +1,15 @@
-use lib::supplier::model::{CreateSupplier, UpdateSupplier};
+use crate::util::export::{ExportCell, ExportRecord};
+use crate::util::list::filters;
+use lib::supplier::model::{CreateSupplier, ResponseSupplier, UpdateSupplier};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 static RE_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_.]{3,32}$").unwrap());
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RequestCreateSupplierDto {
     #[validate(regex(path = *RE_NAME , message = "Invalid"))]
     pub name: String,
@@ -29,7 +32,7 @@ impl RequestCreateSupplierDto {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RequestUpdateSupplierDto {
     #[validate(regex(path = *RE_NAME , message = "Invalid"))]
     pub name: Option<String>,
@@ -54,3 +57,48 @@ impl RequestUpdateSupplierDto {
         }
     }
 }
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestSupplierFilterDto {
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub phone: Option<String>,
+}
+
+impl RequestSupplierFilterDto {
+    pub fn to_filters(&self) -> Vec<(String, String)> {
+        filters([
+            ("name", &self.name),
+            ("email", &self.email),
+            ("phone", &self.phone),
+        ])
+    }
+}
+
+impl ExportRecord for ResponseSupplier {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "email",
+        "phone",
+        "address",
+        "created_at",
+        "updated_at",
+    ];
+
+    fn to_row(&self) -> Vec<ExportCell> {
+        vec![
+            self.id.into(),
+            (&self.name).into(),
+            (&self.email).into(),
+            (&self.phone).into(),
+            (&self.address).into(),
+            (&self.created_at).into(),
+            (&self.updated_at).into(),
+        ]
+    }
+}
@@ -1,23 +1,46 @@
 use crate::rest::middleware::auth::role_check;
-use crate::rest::supplier::dto::{RequestCreateSupplierDto, RequestUpdateSupplierDto};
+use crate::rest::supplier::dto::{
+    RequestCreateSupplierDto, RequestSupplierFilterDto, RequestUpdateSupplierDto,
+};
+use crate::util::bulk::{
+    BulkBatch, BulkReport, RequestBulkOperation, RequestBulkQueryDto, MAX_BULK_ITEMS,
+};
 use crate::util::error::RestApiError;
-use crate::util::res::ApiResult;
-use crate::util::validation::ValidatedJson;
+use crate::util::etag::{etag, IfMatch};
+use crate::util::export::{export_response, Export, ExportQuery};
+use crate::util::import::{
+    import_response, ImportFile, ImportFileUpload, RequestImportQueryDto, MAX_IMPORT_BYTES,
+};
+use crate::util::list::RequestListQueryDto;
+use crate::util::res::{ApiResult, Null};
+use crate::util::validation::{ValidatedJson, ValidatedQuery};
 use axum::{
-    extract::{Extension, Path},
+    extract::{rejection::JsonRejection, DefaultBodyLimit, Extension, Multipart, Path},
     http::StatusCode,
     middleware,
-    response::IntoResponse,
-    routing::{delete, get, post, put},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
     Json, Router,
 };
 use lib::app_ctx::AppCtx;
+use lib::auth::model::AuthInfo;
+use lib::supplier::model::ResponseSupplier;
+use lib::util::model::{DeletedRecord, ImportReport};
 use std::sync::Arc;
+use tracing::instrument;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 fn supplier_handler_admin() -> Router {
     Router::new()
         .route("/", post(create_supplier))
+        .route(
+            "/import",
+            post(import_suppliers).layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route("/bulk", post(bulk_suppliers))
+        .route("/deleted", get(get_deleted_suppliers))
+        .route("/:id/restore", post(restore_supplier_by_id))
         .route(
             "/:id",
             put(update_supplier_by_id).delete(delete_supplier_by_id),
@@ -36,37 +59,92 @@ fn supplier_handler_user() -> Router {
         }))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_all_suppliers,
+    create_supplier,
+    get_supplier_by_id,
+    delete_supplier_by_id,
+    update_supplier_by_id,
+    get_deleted_suppliers,
+    restore_supplier_by_id,
+    import_suppliers,
+    bulk_suppliers,
+))]
+pub struct SupplierApi;
+
 pub fn supplier_handler() -> Router {
     Router::new()
         .merge(supplier_handler_admin())
         .merge(supplier_handler_user())
 }
 
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "List or export suppliers",
+    params(RequestListQueryDto, RequestSupplierFilterDto, ExportQuery),
+    responses(
+        (status = 200, description = "Suppliers found", content(
+            (ApiResult<Vec<ResponseSupplier>> = "application/json"),
+            (String = "text/csv"),
+            (String = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+            (String = "application/x-ndjson"),
+        )),
+    )
+)]
+#[instrument(skip_all)]
 async fn get_all_suppliers(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
-) -> Result<impl IntoResponse, RestApiError> {
+    Export(_format): Export,
+    ValidatedQuery(_query): ValidatedQuery<RequestListQueryDto>,
+    ValidatedQuery(_filter): ValidatedQuery<RequestSupplierFilterDto>,
+) -> Result<Response, RestApiError> {
+    let query = _query.to_list_query(_filter.to_filters());
+    if let Some(format) = _format {
+        return export_response(format, "suppliers", move |sender| async move {
+            _app_ctx
+                .supplier_use_case
+                .export_suppliers(&query, &sender)
+                .await
+        })
+        .await;
+    }
     let result = _app_ctx
         .supplier_use_case
-        .get_all_suppliers()
+        .get_all_suppliers(&query)
         .await
         .map_err(RestApiError::from_lib)?;
     Ok((
         StatusCode::OK,
-        Json(ApiResult::from(
+        Json(ApiResult::from_page(
             result,
             "Suppliers found".to_string(),
             StatusCode::OK.into(),
         )),
-    ))
+    )
+        .into_response())
 }
 
+#[utoipa::path(
+    post,
+    path = "",
+    summary = "Create a supplier",
+    request_body = RequestCreateSupplierDto,
+    responses(
+        (status = 201, description = "Supplier created", body = ApiResult<ResponseSupplier>),
+        (status = 409, description = "Name or email already taken", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn create_supplier(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     ValidatedJson(_dto): ValidatedJson<RequestCreateSupplierDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .supplier_use_case
-        .create_supplier(&_dto.to_create_supplier())
+        .create_supplier(&_dto.to_create_supplier(), Some(&_user_info.user_info.sub))
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -80,6 +158,18 @@ async fn create_supplier(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    summary = "Get a supplier",
+    params(("id" = Uuid, Path, description = "Supplier id")),
+    responses(
+        (status = 200, description = "Supplier found", body = ApiResult<ResponseSupplier>,
+            headers(("ETag" = String, description = "Current version of the supplier"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_dto))]
 async fn get_supplier_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     Path(_dto): Path<Uuid>,
@@ -92,6 +182,7 @@ async fn get_supplier_by_id(
 
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "Supplier found".to_string(),
@@ -100,39 +191,85 @@ async fn get_supplier_by_id(
     ))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    summary = "Delete a supplier",
+    params(
+        ("id" = Uuid, Path, description = "Supplier id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being deleted"),
+    ),
+    responses(
+        (status = 200, description = "Supplier deleted", body = ApiResult<Null>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "Materials still reference the supplier", body = ApiResult<Null>),
+        (status = 412, description = "Supplier changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_dto))]
 async fn delete_supplier_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     Path(_dto): Path<Uuid>,
+    IfMatch(_version): IfMatch,
 ) -> Result<impl IntoResponse, RestApiError> {
     _app_ctx
         .supplier_use_case
-        .delete_supplier_by_id(&_dto)
+        .delete_supplier_by_id(&_dto, _version, Some(&_user_info.user_info.sub))
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
         Json(ApiResult::from(
-            {},
+            (),
             "Supplier deleted successfully".to_string(),
             StatusCode::OK.into(),
         )),
     ))
 }
 
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    summary = "Update a supplier",
+    params(
+        ("id" = Uuid, Path, description = "Supplier id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed"),
+    ),
+    request_body = RequestUpdateSupplierDto,
+    responses(
+        (status = 200, description = "Supplier updated", body = ApiResult<ResponseSupplier>,
+            headers(("ETag" = String, description = "New version of the supplier"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "Name or email already taken", body = ApiResult<Null>),
+        (status = 412, description = "Supplier changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
 async fn update_supplier_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     Path(_id): Path<Uuid>,
+    IfMatch(_version): IfMatch,
     ValidatedJson(_dto): ValidatedJson<RequestUpdateSupplierDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .supplier_use_case
-        .update_supplier_by_id(&_id, &_dto.to_update_supplier())
+        .update_supplier_by_id(
+            &_id,
+            &_dto.to_update_supplier(),
+            _version,
+            Some(&_user_info.user_info.sub),
+        )
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "Supplier updated successfully".to_string(),
@@ -140,3 +277,150 @@ async fn update_supplier_by_id(
         )),
     ))
 }
+
+#[utoipa::path(
+    get,
+    path = "/deleted",
+    summary = "List deleted suppliers",
+    responses(
+        (status = 200, description = "Deleted suppliers found", body = ApiResult<Vec<DeletedRecord<ResponseSupplier>>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_deleted_suppliers(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .supplier_use_case
+        .get_deleted_suppliers()
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Deleted suppliers found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    summary = "Restore a deleted supplier",
+    params(("id" = Uuid, Path, description = "Supplier id")),
+    responses(
+        (status = 200, description = "Supplier restored", body = ApiResult<ResponseSupplier>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn restore_supplier_by_id(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .supplier_use_case
+        .restore_supplier_by_id(&_id, Some(&_user_info.user_info.sub))
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Supplier restored successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/import",
+    summary = "Import suppliers from CSV or XLSX",
+    params(RequestImportQueryDto),
+    request_body(content = ImportFileUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Dry run report", body = ApiResult<ImportReport>),
+        (status = 201, description = "Suppliers imported", body = ApiResult<ImportReport>),
+        (status = 422, description = "Some rows are invalid", body = ApiResult<ImportReport>),
+    )
+)]
+#[instrument(skip_all)]
+async fn import_suppliers(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    ValidatedQuery(_query): ValidatedQuery<RequestImportQueryDto>,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, RestApiError> {
+    let file = ImportFile::from_multipart(multipart).await?;
+    let (rows, errors) = file.parse(RequestCreateSupplierDto::to_create_supplier);
+    let report = _app_ctx
+        .supplier_use_case
+        .import_suppliers(
+            &rows,
+            _query.is_dry_run() || !errors.is_empty(),
+            Some(&_user_info.user_info.sub),
+        )
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok(import_response(
+        report,
+        file.len(),
+        errors,
+        _query.is_dry_run(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bulk",
+    summary = "Create, update and delete suppliers in one request",
+    params(RequestBulkQueryDto),
+    request_body = [RequestBulkOperation<RequestCreateSupplierDto, RequestUpdateSupplierDto>],
+    responses(
+        (status = 200, description = "Every item succeeded", body = ApiResult<BulkReport<ResponseSupplier>>),
+        (status = 207, description = "Best effort, some items failed", body = ApiResult<BulkReport<ResponseSupplier>>),
+        (status = 422, description = "All or nothing, an item failed and nothing was written", body = ApiResult<BulkReport<ResponseSupplier>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn bulk_suppliers(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    ValidatedQuery(_query): ValidatedQuery<RequestBulkQueryDto>,
+    payload: Result<
+        Json<Vec<RequestBulkOperation<RequestCreateSupplierDto, RequestUpdateSupplierDto>>>,
+        JsonRejection,
+    >,
+) -> Result<impl IntoResponse, RestApiError> {
+    let Json(_items) = payload?;
+    let batch = BulkBatch::parse(
+        &_items,
+        _query.mode(),
+        MAX_BULK_ITEMS,
+        RequestCreateSupplierDto::to_create_supplier,
+        RequestUpdateSupplierDto::to_update_supplier,
+    )?;
+    let result = match batch.should_run() {
+        true => Some(
+            _app_ctx
+                .supplier_use_case
+                .bulk_suppliers(
+                    &batch.operations,
+                    batch.mode,
+                    Some(&_user_info.user_info.sub),
+                )
+                .await
+                .map_err(RestApiError::from_lib)?,
+        ),
+        false => None,
+    };
+
+    Ok(batch.into_response(result, |supplier| supplier.id))
+}
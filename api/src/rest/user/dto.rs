@@ -1,23 +1,30 @@
+use crate::util::export::{ExportCell, ExportRecord};
+use crate::util::list::filters;
 use lib::role::entity::Role;
-use lib::user::model::{CreateUser, UpdateUser};
+use lib::user::model::{CreateUser, LoginEventFilter, ResponseUser, UpdateUser};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 use validator::Validate;
 
 static RE_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_.]{3,32}$").unwrap());
 static RE_PASS: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_]{6,32}$").unwrap());
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RequestCreateUserDto {
     #[validate(regex(path = *RE_NAME , message = "invalid"))]
     #[validate(required(message = "missing"))]
+    #[schema(value_type = String)]
     pub name: Option<String>,
     #[validate(email(message = "invalid"))]
     #[validate(required(message = "missing"))]
+    #[schema(value_type = String)]
     pub email: Option<String>,
     #[validate(regex(path = *RE_PASS, message = "invalid"))]
     #[validate(required(message = "missing"))]
+    #[schema(value_type = String)]
     pub password: Option<String>,
     #[validate(length(min = 1, message = "invalid"))]
     pub address: Option<String>,
@@ -36,13 +43,13 @@ impl RequestCreateUserDto {
                 .as_ref()
                 .map_or("".to_string(), |address| address.trim().to_string()),
             role: self.role.as_ref().map_or(Role::User, |role| {
-                Role::from_str(&role.trim().to_string()).unwrap_or(Role::User)
+                Role::from_str(role.trim()).unwrap_or(Role::User)
             }),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RequestUpdateUserDto {
     #[validate(regex(path = *RE_NAME, message = "invalid"))]
     pub name: Option<String>,
@@ -69,8 +76,80 @@ impl RequestUpdateUserDto {
             role: self
                 .role
                 .as_ref()
-                .map(|role| Role::from_str(&role.trim().to_string()))
-                .flatten(),
+                .and_then(|role| Role::from_str(role.trim())),
         }
     }
 }
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestLoginEventQueryDto {
+    pub user_id: Option<Uuid>,
+    #[validate(email(message = "invalid"))]
+    pub email: Option<String>,
+    #[validate(length(min = 1, message = "invalid"))]
+    pub ip: Option<String>,
+    #[validate(range(min = 1, max = 500, message = "invalid"))]
+    pub limit: Option<i64>,
+}
+
+impl RequestLoginEventQueryDto {
+    pub fn to_login_event_filter(&self) -> LoginEventFilter {
+        LoginEventFilter {
+            user_id: self.user_id,
+            email: self.email.as_ref().map(|email| email.trim().to_string()),
+            ip_address: self.ip.as_ref().map(|ip| ip.trim().to_string()),
+            limit: self.limit.unwrap_or(100),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestUserFilterDto {
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub email: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "invalid"))]
+    pub role: Option<String>,
+    pub role_id: Option<Uuid>,
+}
+
+impl RequestUserFilterDto {
+    pub fn to_filters(&self) -> Vec<(String, String)> {
+        let role_id = self.role_id.map(|id| id.to_string());
+        filters([
+            ("name", &self.name),
+            ("email", &self.email),
+            ("role", &self.role),
+            ("role_id", &role_id),
+        ])
+    }
+}
+
+impl ExportRecord for ResponseUser {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "email",
+        "address",
+        "role_id",
+        "role_name",
+        "created_at",
+        "updated_at",
+    ];
+
+    fn to_row(&self) -> Vec<ExportCell> {
+        vec![
+            self.id.into(),
+            (&self.name).into(),
+            (&self.email).into(),
+            (&self.address).into(),
+            self.role_id.into(),
+            (&self.role_name).into(),
+            (&self.created_at).into(),
+            (&self.updated_at).into(),
+        ]
+    }
+}
@@ -1,25 +1,43 @@
 use crate::rest::middleware::auth::role_check;
-use crate::rest::user::dto::{RequestCreateUserDto, RequestUpdateUserDto};
+use crate::rest::user::dto::{
+    RequestCreateUserDto, RequestLoginEventQueryDto, RequestUpdateUserDto, RequestUserFilterDto,
+};
+use crate::util::bulk::{
+    BulkBatch, BulkReport, RequestBulkOperation, RequestBulkQueryDto, MAX_BULK_USERS,
+};
 use crate::util::error::RestApiError;
+use crate::util::etag::{etag, IfMatch};
+use crate::util::export::{export_response, Export, ExportQuery};
+use crate::util::list::RequestListQueryDto;
 use crate::util::res::{ApiResult, Null};
-use crate::util::validation::ValidatedJson;
+use crate::util::validation::{ValidatedJson, ValidatedQuery};
 use axum::{
-    extract::{Extension, Path},
+    extract::{rejection::JsonRejection, Extension, Path},
     http::StatusCode,
     middleware,
-    response::IntoResponse,
-    routing::{delete, get, post, put},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
     Json, Router,
 };
 use lib::app_ctx::AppCtx;
 use lib::auth::model::AuthInfo;
+use lib::session::model::ResponseSession;
+use lib::user::model::{ResponseLoginEvent, ResponseUser};
+use lib::util::model::DeletedRecord;
 use std::sync::Arc;
+use tracing::instrument;
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 fn user_handler_user() -> Router {
     Router::new()
         .route("/", post(create_user).put(update_user).delete(delete_user))
         .route("/info", get(get_info))
+        .route(
+            "/sessions",
+            get(get_own_sessions).delete(revoke_own_sessions),
+        )
+        .route("/sessions/:session_id", delete(revoke_own_session))
         .route_layer(middleware::from_fn(|req, next| {
             role_check(req, next, vec!["Admin", "User"])
         }))
@@ -28,28 +46,74 @@ fn user_handler_user() -> Router {
 fn user_handler_admin() -> Router {
     Router::new()
         .route("/", get(get_users))
+        .route("/login-events", get(get_login_events))
+        .route("/bulk", post(bulk_users))
+        .route("/deleted", get(get_deleted_users))
+        .route("/:id/restore", post(restore_user))
         .route(
             "/:id",
-            get(get_user_by_id).put(update_user).delete(delete_user),
+            get(get_user_by_id)
+                .put(update_user_by_id)
+                .delete(delete_user_by_id),
+        )
+        .route("/:id/unlock", post(unlock_user))
+        .route(
+            "/:id/sessions",
+            get(get_user_sessions).delete(revoke_user_sessions),
         )
         .route_layer(middleware::from_fn(|req, next| {
             role_check(req, next, vec!["Admin"])
         }))
 }
 
+#[derive(OpenApi)]
+#[openapi(paths(
+    create_user,
+    update_user,
+    update_user_by_id,
+    delete_user,
+    delete_user_by_id,
+    get_user_by_id,
+    get_users,
+    get_info,
+    unlock_user,
+    get_login_events,
+    get_own_sessions,
+    revoke_own_session,
+    revoke_own_sessions,
+    get_user_sessions,
+    revoke_user_sessions,
+    get_deleted_users,
+    restore_user,
+    bulk_users,
+))]
+pub struct UserApi;
+
 pub fn user_handler() -> Router {
     Router::new()
         .merge(user_handler_user())
         .merge(user_handler_admin())
 }
 
+#[utoipa::path(
+    post,
+    path = "",
+    summary = "Create a user",
+    request_body = RequestCreateUserDto,
+    responses(
+        (status = 201, description = "User created", body = ApiResult<ResponseUser>),
+        (status = 409, description = "Name or email already taken", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn create_user(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
     ValidatedJson(_dto): ValidatedJson<RequestCreateUserDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
     let result = _app_ctx
         .user_use_case
-        .create_user(&_dto.to_create_user())
+        .create_user(&_dto.to_create_user(), Some(&_user_info.user_info.sub))
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -63,24 +127,83 @@ async fn create_user(
     ))
 }
 
+#[utoipa::path(
+    put,
+    path = "",
+    summary = "Update the signed in user",
+    request_body = RequestUpdateUserDto,
+    responses(
+        (status = 200, description = "User updated", body = ApiResult<ResponseUser>),
+        (status = 409, description = "Name or email already taken", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn update_user(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     Extension(_user_info): Extension<AuthInfo>,
-    path: Option<Path<Uuid>>,
     ValidatedJson(_dto): ValidatedJson<RequestUpdateUserDto>,
 ) -> Result<impl IntoResponse, RestApiError> {
-    let id = match path {
-        Some(id) => id.0,
-        None => _user_info.user_info.sub,
-    };
     let result = _app_ctx
         .user_use_case
-        .update_user(&id, &_dto.to_update_user())
+        .update_user(
+            &_user_info.user_info.sub,
+            &_dto.to_update_user(),
+            None,
+            Some(&_user_info.user_info.sub),
+        )
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "User updated successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    summary = "Update a user",
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being changed"),
+    ),
+    request_body = RequestUpdateUserDto,
+    responses(
+        (status = 200, description = "User updated", body = ApiResult<ResponseUser>,
+            headers(("ETag" = String, description = "New version of the user"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "Name or email already taken", body = ApiResult<Null>),
+        (status = 412, description = "User changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn update_user_by_id(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    Path(_id): Path<Uuid>,
+    IfMatch(_version): IfMatch,
+    ValidatedJson(_dto): ValidatedJson<RequestUpdateUserDto>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .user_use_case
+        .update_user(
+            &_id,
+            &_dto.to_update_user(),
+            _version,
+            Some(&_user_info.user_info.sub),
+        )
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "User updated successfully".to_string(),
@@ -89,18 +212,64 @@ async fn update_user(
     ))
 }
 
+#[utoipa::path(
+    delete,
+    path = "",
+    summary = "Delete the signed in user",
+    responses(
+        (status = 200, description = "User deleted", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
 async fn delete_user(
     Extension(app_ctx): Extension<Arc<AppCtx>>,
     Extension(_user_info): Extension<AuthInfo>,
-    path: Option<Path<Uuid>>,
 ) -> Result<impl IntoResponse, RestApiError> {
-    let id = match path {
-        Some(id) => id.0,
-        None => _user_info.user_info.sub,
-    };
     app_ctx
         .user_use_case
-        .delete_user(&id)
+        .delete_user(
+            &_user_info.user_info.sub,
+            None,
+            Some(&_user_info.user_info.sub),
+        )
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            Null,
+            "User deleted successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    summary = "Delete a user",
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("If-Match" = Option<String>, Header, description = "ETag of the version being deleted"),
+    ),
+    responses(
+        (status = 200, description = "User deleted", body = ApiResult<Null>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 412, description = "User changed since it was read", body = ApiResult<Null>),
+        (status = 428, description = "If-Match is required", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn delete_user_by_id(
+    Extension(app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    Path(_id): Path<Uuid>,
+    IfMatch(_version): IfMatch,
+) -> Result<impl IntoResponse, RestApiError> {
+    app_ctx
+        .user_use_case
+        .delete_user(&_id, _version, Some(&_user_info.user_info.sub))
         .await
         .map_err(RestApiError::from_lib)?;
 
@@ -114,6 +283,18 @@ async fn delete_user(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    summary = "Get a user",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User found", body = ApiResult<ResponseUser>,
+            headers(("ETag" = String, description = "Current version of the user"))),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_dto))]
 async fn get_user_by_id(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     Path(_dto): Path<Uuid>,
@@ -126,6 +307,7 @@ async fn get_user_by_id(
 
     Ok((
         StatusCode::OK,
+        etag(result.version),
         Json(ApiResult::from(
             result,
             "User found".to_string(),
@@ -134,25 +316,60 @@ async fn get_user_by_id(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "",
+    summary = "List or export users",
+    params(RequestListQueryDto, RequestUserFilterDto, ExportQuery),
+    responses(
+        (status = 200, description = "Users found", content(
+            (ApiResult<Vec<ResponseUser>> = "application/json"),
+            (String = "text/csv"),
+            (String = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+            (String = "application/x-ndjson"),
+        )),
+    )
+)]
+#[instrument(skip_all)]
 async fn get_users(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
-) -> Result<impl IntoResponse, RestApiError> {
+    Export(_format): Export,
+    ValidatedQuery(_query): ValidatedQuery<RequestListQueryDto>,
+    ValidatedQuery(_filter): ValidatedQuery<RequestUserFilterDto>,
+) -> Result<Response, RestApiError> {
+    let query = _query.to_list_query(_filter.to_filters());
+    if let Some(format) = _format {
+        return export_response(format, "users", move |sender| async move {
+            _app_ctx.user_use_case.export_users(&query, &sender).await
+        })
+        .await;
+    }
     let result = _app_ctx
         .user_use_case
-        .get_users()
+        .get_users(&query)
         .await
         .map_err(RestApiError::from_lib)?;
 
     Ok((
         StatusCode::OK,
-        Json(ApiResult::from(
+        Json(ApiResult::from_page(
             result,
             "Users found".to_string(),
             StatusCode::OK.into(),
         )),
-    ))
+    )
+        .into_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "/info",
+    summary = "Get the signed in user",
+    responses(
+        (status = 200, description = "Token claims of the signed in user", body = ApiResult<AuthInfo>),
+    )
+)]
+#[instrument(skip_all)]
 async fn get_info(
     Extension(_app_ctx): Extension<Arc<AppCtx>>,
     Extension(_user_info): Extension<AuthInfo>,
@@ -166,3 +383,323 @@ async fn get_info(
         )),
     ))
 }
+
+#[utoipa::path(
+    post,
+    path = "/{id}/unlock",
+    summary = "Unlock a locked out user",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User unlocked", body = ApiResult<Null>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn unlock_user(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    _app_ctx
+        .user_use_case
+        .unlock_user(&_id)
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            Null,
+            "User unlocked successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/login-events",
+    summary = "List login attempts",
+    params(RequestLoginEventQueryDto),
+    responses(
+        (status = 200, description = "Login events found", body = ApiResult<Vec<ResponseLoginEvent>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_login_events(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    ValidatedQuery(_dto): ValidatedQuery<RequestLoginEventQueryDto>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .user_use_case
+        .get_login_events(&_dto.to_login_event_filter())
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Login events found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions",
+    summary = "List the signed in user's sessions",
+    responses(
+        (status = 200, description = "Sessions found", body = ApiResult<Vec<ResponseSession>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_own_sessions(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .session_use_case
+        .get_sessions(&_user_info.user_info.sub, Some(&_user_info.user_info.sid))
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Sessions found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions/{session_id}",
+    summary = "Sign out one session",
+    params(("session_id" = Uuid, Path, description = "Session id")),
+    responses(
+        (status = 200, description = "Session revoked", body = ApiResult<Null>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %session_id))]
+async fn revoke_own_session(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    Path(session_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    _app_ctx
+        .session_use_case
+        .revoke_session(&_user_info.user_info.sub, &session_id)
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            Null,
+            "Session revoked successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions",
+    summary = "Sign out everywhere",
+    responses(
+        (status = 200, description = "Sessions revoked", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all)]
+async fn revoke_own_sessions(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+) -> Result<impl IntoResponse, RestApiError> {
+    _app_ctx
+        .session_use_case
+        .revoke_all_sessions(&_user_info.user_info.sub)
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            Null,
+            "Sessions revoked successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/sessions",
+    summary = "List a user's sessions",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "Sessions found", body = ApiResult<Vec<ResponseSession>>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn get_user_sessions(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .session_use_case
+        .get_sessions(&_id, None)
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Sessions found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/sessions",
+    summary = "Sign a user out everywhere",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User logged out", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn revoke_user_sessions(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    _app_ctx
+        .session_use_case
+        .revoke_all_sessions(&_id)
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            Null,
+            "User logged out successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/deleted",
+    summary = "List deleted users",
+    responses(
+        (status = 200, description = "Deleted users found", body = ApiResult<Vec<DeletedRecord<ResponseUser>>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn get_deleted_users(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .user_use_case
+        .get_deleted_users()
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "Deleted users found".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/restore",
+    summary = "Restore a deleted user",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User restored", body = ApiResult<ResponseUser>),
+        (status = 404, description = "Not found", body = ApiResult<Null>),
+        (status = 409, description = "The user's role is deleted", body = ApiResult<Null>),
+    )
+)]
+#[instrument(skip_all, fields(id = %_id))]
+async fn restore_user(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    Path(_id): Path<Uuid>,
+) -> Result<impl IntoResponse, RestApiError> {
+    let result = _app_ctx
+        .user_use_case
+        .restore_user(&_id, Some(&_user_info.user_info.sub))
+        .await
+        .map_err(RestApiError::from_lib)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResult::from(
+            result,
+            "User restored successfully".to_string(),
+            StatusCode::OK.into(),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bulk",
+    summary = "Create, update and delete users in one request",
+    params(RequestBulkQueryDto),
+    request_body = [RequestBulkOperation<RequestCreateUserDto, RequestUpdateUserDto>],
+    responses(
+        (status = 200, description = "Every item succeeded", body = ApiResult<BulkReport<ResponseUser>>),
+        (status = 207, description = "Best effort, some items failed", body = ApiResult<BulkReport<ResponseUser>>),
+        (status = 422, description = "All or nothing, an item failed and nothing was written", body = ApiResult<BulkReport<ResponseUser>>),
+    )
+)]
+#[instrument(skip_all)]
+async fn bulk_users(
+    Extension(_app_ctx): Extension<Arc<AppCtx>>,
+    Extension(_user_info): Extension<AuthInfo>,
+    ValidatedQuery(_query): ValidatedQuery<RequestBulkQueryDto>,
+    payload: Result<
+        Json<Vec<RequestBulkOperation<RequestCreateUserDto, RequestUpdateUserDto>>>,
+        JsonRejection,
+    >,
+) -> Result<impl IntoResponse, RestApiError> {
+    let Json(_items) = payload?;
+    let batch = BulkBatch::parse(
+        &_items,
+        _query.mode(),
+        MAX_BULK_USERS,
+        RequestCreateUserDto::to_create_user,
+        RequestUpdateUserDto::to_update_user,
+    )?;
+    let result = match batch.should_run() {
+        true => Some(
+            _app_ctx
+                .user_use_case
+                .bulk_users(
+                    &batch.operations,
+                    batch.mode,
+                    Some(&_user_info.user_info.sub),
+                )
+                .await
+                .map_err(RestApiError::from_lib)?,
+        ),
+        false => None,
+    };
+
+    Ok(batch.into_response(result, |user| user.id))
+}
@@ -0,0 +1,249 @@
+use crate::util::error::{ErrorCode, FieldError, Problem, RestApiError};
+use crate::util::res::ApiResult;
+use axum::{http::StatusCode, Json};
+use lib::util::model::{BulkApplied, BulkMode, BulkOperation, BulkResult};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+pub const MAX_BULK_ITEMS: usize = 1000;
+// every user created or given a new password costs a bcrypt hash
+pub const MAX_BULK_USERS: usize = 100;
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestBulkQueryDto {
+    // `all_or_nothing` unless the caller asks for `best_effort`
+    pub mode: Option<BulkMode>,
+}
+
+impl RequestBulkQueryDto {
+    pub fn mode(&self) -> BulkMode {
+        self.mode.unwrap_or_default()
+    }
+}
+
+// one item of a bulk request body, `version` is checked like an If-Match header
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RequestBulkOperation<C, U> {
+    Create {
+        data: C,
+    },
+    Update {
+        id: Uuid,
+        version: Option<i32>,
+        data: U,
+    },
+    Delete {
+        id: Uuid,
+        version: Option<i32>,
+    },
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkItemError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl From<Problem> for BulkItemError {
+    fn from(problem: Problem) -> Self {
+        Self {
+            code: problem.code,
+            message: problem.detail,
+            errors: problem.errors,
+        }
+    }
+}
+
+// the outcome of the item at `index` of the request body
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkItemResult<T> {
+    pub index: usize,
+    pub status_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BulkItemError>,
+}
+
+impl<T> BulkItemResult<T> {
+    fn failed(index: usize, problem: Problem) -> Self {
+        Self {
+            index,
+            status_code: problem.status,
+            id: None,
+            data: None,
+            error: Some(problem.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkReport<T> {
+    pub mode: BulkMode,
+    // false when nothing was written
+    pub committed: bool,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult<T>>,
+}
+
+// a bulk request checked item by item, only valid items reach the repository
+#[derive(Debug)]
+pub struct BulkBatch<C, U> {
+    pub mode: BulkMode,
+    pub operations: Vec<BulkOperation<C, U>>,
+    // request body index of each operation
+    indices: Vec<usize>,
+    rejected: Vec<(usize, Problem)>,
+    total: usize,
+}
+
+impl<C, U> BulkBatch<C, U> {
+    pub fn parse<DC, DU>(
+        items: &[RequestBulkOperation<DC, DU>],
+        mode: BulkMode,
+        max_items: usize,
+        to_create: impl Fn(&DC) -> C,
+        to_update: impl Fn(&DU) -> U,
+    ) -> Result<Self, RestApiError>
+    where
+        DC: Validate,
+        DU: Validate,
+    {
+        if items.len() > max_items {
+            return Err(RestApiError::BadRequest(format!(
+                "Too many items, at most {max_items} can be sent at once"
+            )));
+        }
+        let mut batch = Self {
+            mode,
+            operations: Vec::with_capacity(items.len()),
+            indices: Vec::with_capacity(items.len()),
+            rejected: Vec::new(),
+            total: items.len(),
+        };
+        for (index, item) in items.iter().enumerate() {
+            let operation = match item {
+                RequestBulkOperation::Create { data } => data
+                    .validate()
+                    .map(|()| BulkOperation::Create(to_create(data))),
+                RequestBulkOperation::Update { id, version, data } => {
+                    data.validate().map(|()| BulkOperation::Update {
+                        id: *id,
+                        version: *version,
+                        record: to_update(data),
+                    })
+                }
+                RequestBulkOperation::Delete { id, version } => Ok(BulkOperation::Delete {
+                    id: *id,
+                    version: *version,
+                }),
+            };
+            match operation {
+                Ok(operation) => {
+                    batch.operations.push(operation);
+                    batch.indices.push(index);
+                }
+                Err(e) => batch
+                    .rejected
+                    .push((index, RestApiError::from(e).to_problem())),
+            }
+        }
+        Ok(batch)
+    }
+
+    // an all-or-nothing batch with an invalid item is refused without touching the database
+    pub fn should_run(&self) -> bool {
+        !self.operations.is_empty()
+            && (self.mode == BulkMode::BestEffort || self.rejected.is_empty())
+    }
+
+    pub fn into_response<T>(
+        self,
+        result: Option<BulkResult<T>>,
+        id_of: impl Fn(&T) -> Uuid,
+    ) -> (StatusCode, Json<ApiResult<BulkReport<T>>>)
+    where
+        T: Serialize,
+    {
+        let mut results: Vec<Option<BulkItemResult<T>>> = (0..self.total).map(|_| None).collect();
+        for (index, problem) in self.rejected {
+            results[index] = Some(BulkItemResult::failed(index, problem));
+        }
+        let committed = result.as_ref().is_some_and(|result| result.committed);
+        let outcomes = result.map(|result| result.outcomes).unwrap_or_default();
+        for (index, outcome) in self.indices.into_iter().zip(outcomes) {
+            let item = match outcome {
+                Ok(_) if !committed => continue,
+                Ok(applied) => {
+                    let (status, id, data) = match applied {
+                        BulkApplied::Created(data) => {
+                            (StatusCode::CREATED, id_of(&data), Some(data))
+                        }
+                        BulkApplied::Updated(data) => (StatusCode::OK, id_of(&data), Some(data)),
+                        BulkApplied::Deleted(id) => (StatusCode::OK, id, None),
+                    };
+                    BulkItemResult {
+                        index,
+                        status_code: status.as_u16(),
+                        id: Some(id),
+                        data,
+                        error: None,
+                    }
+                }
+                Err(e) => BulkItemResult::failed(index, RestApiError::from_lib(e).to_problem()),
+            };
+            results[index] = Some(item);
+        }
+        // valid items of a batch that was rolled back or never ran
+        let results: Vec<_> = results
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                item.unwrap_or_else(|| {
+                    BulkItemResult::failed(
+                        index,
+                        Problem::new(
+                            StatusCode::FAILED_DEPENDENCY,
+                            ErrorCode::NotApplied,
+                            "Not applied, another item of the batch failed".to_string(),
+                        ),
+                    )
+                })
+            })
+            .collect();
+
+        let failed = results.iter().filter(|item| item.error.is_some()).count();
+        let (status, message) = if failed == 0 {
+            (StatusCode::OK, "Bulk request completed")
+        } else if self.mode == BulkMode::BestEffort {
+            (StatusCode::MULTI_STATUS, "Bulk request partially completed")
+        } else {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Bulk request failed, nothing was written",
+            )
+        };
+        let report = BulkReport {
+            mode: self.mode,
+            committed: committed && results.len() > failed,
+            total: self.total,
+            succeeded: results.len() - failed,
+            failed,
+            results,
+        };
+        (
+            status,
+            Json(ApiResult::from(report, message.to_string(), status.into())),
+        )
+    }
+}
@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts},
+};
+use lib::user::model::ClientInfo;
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use crate::AppState;
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractClientInfo(pub ClientInfo);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ExtractClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(ClientInfo {
+            ip_address: client_ip(parts),
+            user_agent: parts
+                .headers
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string()),
+        }))
+    }
+}
+
+// X-Forwarded-For is only honored when the server is configured to sit behind a proxy,
+// otherwise any client could pick its own address
+pub fn client_ip(parts: &Parts) -> Option<String> {
+    let trust_proxy_headers = parts
+        .extensions
+        .get::<Arc<AppState>>()
+        .is_some_and(|state| state.config.server.trust_proxy_headers);
+    let forwarded = parts
+        .headers
+        .get("X-Forwarded-For")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    match forwarded {
+        Some(ip) if trust_proxy_headers => Some(ip),
+        _ => parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string()),
+    }
+}
@@ -1,10 +1,17 @@
 use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::{body::Body, http::Response, response::IntoResponse};
-use lib::util::error::{DatabaseError, LibError};
+use lib::util::error::{ConstraintError, DatabaseError, LibError};
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
+use crate::util::metrics::HTTP_ERRORS;
 use crate::util::res::{ApiResult, Null};
 
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
 #[derive(Error, Debug)]
 pub enum RestApiError {
     #[error("Bad request occured with message'{0}'")]
@@ -17,6 +24,22 @@ pub enum RestApiError {
     NotFound(String),
     #[error("Bad request occured with message'{0}'")]
     InternalServerError(String),
+    #[error("Too many requests occured with message'{0}'")]
+    TooManyRequests(String),
+    #[error("Conflict occured with message'{0}'")]
+    Conflict(String),
+    #[error("Precondition failed occured with message'{0}'")]
+    PreconditionFailed(String),
+    #[error("Precondition required occured with message'{0}'")]
+    PreconditionRequired(String),
+    #[error("Payload too large occured with message'{0}'")]
+    PayloadTooLarge(String),
+    #[error("Idempotency key in use occured with message'{0}'")]
+    IdempotencyKeyInUse(String),
+    #[error("Idempotency key reused occured with message'{0}'")]
+    IdempotencyKeyReused(String),
+    #[error(transparent)]
+    Lib(#[from] LibError),
     #[error(transparent)]
     ValidationError(#[from] validator::ValidationErrors),
     #[error(transparent)]
@@ -37,24 +60,141 @@ pub enum AuthError {
     Forbidden,
 }
 
+// machine readable error codes, clients match on these instead of the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    InvalidQuery,
+    InvalidBody,
+    InvalidInput,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    Conflict,
+    DuplicateValue,
+    InvalidReference,
+    ReferenceInUse,
+    MissingValue,
+    ConstraintViolation,
+    PreconditionFailed,
+    PreconditionRequired,
+    IdempotencyKeyInUse,
+    IdempotencyKeyReused,
+    NotApplied,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    RequestTimeout,
+    TooManyRequests,
+    DatabaseError,
+    StorageError,
+    UpstreamError,
+    TokenError,
+    InternalError,
+}
+
+impl ErrorCode {
+    // for error responses axum produces on its own, like unknown routes
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => ErrorCode::MethodNotAllowed,
+            StatusCode::CONFLICT => ErrorCode::Conflict,
+            StatusCode::PRECONDITION_FAILED => ErrorCode::PreconditionFailed,
+            StatusCode::PRECONDITION_REQUIRED => ErrorCode::PreconditionRequired,
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+            StatusCode::REQUEST_TIMEOUT => ErrorCode::RequestTimeout,
+            StatusCode::TOO_MANY_REQUESTS => ErrorCode::TooManyRequests,
+            status if status.is_client_error() => ErrorCode::BadRequest,
+            _ => ErrorCode::InternalError,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    // dotted path of the invalid field, missing for errors about the whole input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    // the failed rule, e.g. `required`, `email`, `range`
+    pub code: String,
+    pub message: String,
+}
+
+// the error member of the ApiResult envelope
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+// RFC 7807 problem details, also kept on error responses so ErrorLayer can render them again
+// once the request id and the accepted format are known
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    pub code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, code: ErrorCode, detail: String) -> Self {
+        Self {
+            // no problem type documents exist, `code` identifies the error
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail,
+            instance: None,
+            code,
+            request_id: None,
+            errors: Vec::new(),
+        }
+    }
+
+    // `application/problem+json` or the usual ApiResult envelope with an `error` member
+    pub fn to_response(&self, problem_json: bool) -> Response<Body> {
+        let (content_type, body) = if problem_json {
+            (PROBLEM_JSON, serde_json::to_string(self))
+        } else {
+            let mut result = ApiResult::from(Null, self.detail.clone(), self.status);
+            result.error = Some(ApiError {
+                code: self.code,
+                request_id: self.request_id.clone(),
+                errors: self.errors.clone(),
+            });
+            ("application/json", serde_json::to_string(&result))
+        };
+        let mut response = Response::builder()
+            .status(self.status)
+            .header(header::CONTENT_TYPE, HeaderValue::from_static(content_type))
+            .body(Body::new(body.expect("This serialize must be valid")))
+            .expect("Response must be valid");
+        response.extensions_mut().insert(self.clone());
+        response
+    }
+}
+
 impl RestApiError {
     pub fn from_lib(err: LibError) -> Self {
-        match err {
-            LibError::Database(err) => match err {
-                DatabaseError::NotFound(_) => Self::NotFound("Not Found".to_string()),
-                DatabaseError::Other(err) => Self::InternalServerError(err),
-            },
-            LibError::Http(err) => Self::InternalServerError(err),
-            LibError::Storage(err) => Self::InternalServerError(err),
-            LibError::SqlxError(err) => match err {
-                sqlx::Error::RowNotFound => Self::NotFound("Not Found".to_string()),
-                _ => Self::InternalServerError("Sqlx Error".to_string()),
-            },
-            LibError::NotFound(err) => Self::NotFound(err),
-            LibError::BcryptError(err) => Self::InternalServerError(err.to_string()),
-            LibError::Unauthorized(err) => Self::Unauthorized(err),
-            LibError::JwtError(err) => Self::InternalServerError(err.to_string()),
-        }
+        Self::Lib(err)
     }
     pub fn from_auth(err: AuthError) -> Self {
         match err {
@@ -64,32 +204,261 @@ impl RestApiError {
             AuthError::Forbidden => Self::Forbidden("Forbidden".to_string()),
         }
     }
-}
 
-impl IntoResponse for RestApiError {
-    fn into_response(self) -> Response<Body> {
+    // the variant name, the label of the error counter in the metrics
+    pub fn variant(&self) -> &'static str {
         match self {
-            RestApiError::BadRequest(msg) => create_response_json(400, msg),
-            RestApiError::Unauthorized(msg) => create_response_json(401, msg),
-            RestApiError::Forbidden(msg) => create_response_json(403, msg),
-            RestApiError::NotFound(msg) => create_response_json(404, msg),
-            RestApiError::InternalServerError(msg) => create_response_json(500, msg),
-            RestApiError::ValidationError(_) => create_response_json(
-                400,
-                format!("Input validation error: [{self}]").replace('\n', ", "),
+            RestApiError::BadRequest(_) => "BadRequest",
+            RestApiError::Unauthorized(_) => "Unauthorized",
+            RestApiError::Forbidden(_) => "Forbidden",
+            RestApiError::NotFound(_) => "NotFound",
+            RestApiError::InternalServerError(_) => "InternalServerError",
+            RestApiError::TooManyRequests(_) => "TooManyRequests",
+            RestApiError::Conflict(_) => "Conflict",
+            RestApiError::PreconditionFailed(_) => "PreconditionFailed",
+            RestApiError::PreconditionRequired(_) => "PreconditionRequired",
+            RestApiError::PayloadTooLarge(_) => "PayloadTooLarge",
+            RestApiError::IdempotencyKeyInUse(_) => "IdempotencyKeyInUse",
+            RestApiError::IdempotencyKeyReused(_) => "IdempotencyKeyReused",
+            RestApiError::Lib(_) => "Lib",
+            RestApiError::ValidationError(_) => "ValidationError",
+            RestApiError::AxumQueryRejection(_) => "AxumQueryRejection",
+            RestApiError::AxumJsonRejection(_) => "AxumJsonRejection",
+        }
+    }
+
+    pub fn to_problem(&self) -> Problem {
+        let (status, code, detail) = match self {
+            RestApiError::BadRequest(msg) => {
+                (StatusCode::BAD_REQUEST, ErrorCode::BadRequest, msg.clone())
+            }
+            RestApiError::Unauthorized(msg) => (
+                StatusCode::UNAUTHORIZED,
+                ErrorCode::Unauthorized,
+                msg.clone(),
+            ),
+            RestApiError::Forbidden(msg) => {
+                (StatusCode::FORBIDDEN, ErrorCode::Forbidden, msg.clone())
+            }
+            RestApiError::NotFound(msg) => {
+                (StatusCode::NOT_FOUND, ErrorCode::NotFound, msg.clone())
+            }
+            RestApiError::InternalServerError(msg) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::InternalError,
+                msg.clone(),
+            ),
+            RestApiError::TooManyRequests(msg) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::TooManyRequests,
+                msg.clone(),
+            ),
+            RestApiError::Conflict(msg) => (StatusCode::CONFLICT, ErrorCode::Conflict, msg.clone()),
+            RestApiError::PreconditionFailed(msg) => (
+                StatusCode::PRECONDITION_FAILED,
+                ErrorCode::PreconditionFailed,
+                msg.clone(),
+            ),
+            RestApiError::PreconditionRequired(msg) => (
+                StatusCode::PRECONDITION_REQUIRED,
+                ErrorCode::PreconditionRequired,
+                msg.clone(),
+            ),
+            RestApiError::PayloadTooLarge(msg) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                msg.clone(),
             ),
-            RestApiError::AxumJsonRejection(_) => create_response_json(400, self.to_string()),
-            RestApiError::AxumQueryRejection(e) => create_response_json(400, e.to_string()),
+            RestApiError::IdempotencyKeyInUse(msg) => (
+                StatusCode::CONFLICT,
+                ErrorCode::IdempotencyKeyInUse,
+                msg.clone(),
+            ),
+            RestApiError::IdempotencyKeyReused(msg) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::IdempotencyKeyReused,
+                msg.clone(),
+            ),
+            RestApiError::Lib(err) => return lib_problem(err),
+            RestApiError::ValidationError(errors) => {
+                let mut problem = Problem::new(
+                    StatusCode::BAD_REQUEST,
+                    ErrorCode::ValidationFailed,
+                    "Input validation failed".to_string(),
+                );
+                problem.errors = field_errors(None, errors);
+                return problem;
+            }
+            RestApiError::AxumJsonRejection(_) => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidBody,
+                self.to_string(),
+            ),
+            RestApiError::AxumQueryRejection(e) => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidQuery,
+                e.to_string(),
+            ),
+        };
+        Problem::new(status, code, detail)
+    }
+}
+
+// names the offending column so clients can point at the input field
+fn constraint_problem(err: &ConstraintError) -> Problem {
+    let (status, code, rule) = match err {
+        ConstraintError::Duplicate { .. } => {
+            (StatusCode::CONFLICT, ErrorCode::DuplicateValue, "unique")
         }
+        ConstraintError::InvalidReference { .. } => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::InvalidReference,
+            "foreign_key",
+        ),
+        ConstraintError::StillReferenced { .. } => (
+            StatusCode::CONFLICT,
+            ErrorCode::ReferenceInUse,
+            "foreign_key",
+        ),
+        ConstraintError::Missing { .. } => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::MissingValue,
+            "not_null",
+        ),
+        ConstraintError::Check { .. } => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::ConstraintViolation,
+            "check",
+        ),
+    };
+    let mut problem = Problem::new(status, code, err.to_string());
+    if let Some(field) = err.field() {
+        problem.errors = vec![FieldError {
+            field: Some(field.to_string()),
+            code: rule.to_string(),
+            message: err.to_string(),
+        }];
     }
+    problem
+}
+
+fn lib_problem(err: &LibError) -> Problem {
+    let (status, code, detail) = match err {
+        LibError::Database(err) => match err {
+            DatabaseError::NotFound(_) => (
+                StatusCode::NOT_FOUND,
+                ErrorCode::NotFound,
+                "Not Found".to_string(),
+            ),
+            DatabaseError::Other(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::DatabaseError,
+                err.clone(),
+            ),
+        },
+        LibError::Http(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::UpstreamError,
+            err.clone(),
+        ),
+        LibError::Storage(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::StorageError,
+            err.clone(),
+        ),
+        LibError::SqlxError(err) => match err {
+            sqlx::Error::RowNotFound => (
+                StatusCode::NOT_FOUND,
+                ErrorCode::NotFound,
+                "Not Found".to_string(),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::DatabaseError,
+                "Sqlx Error".to_string(),
+            ),
+        },
+        LibError::NotFound(err) => (StatusCode::NOT_FOUND, ErrorCode::NotFound, err.clone()),
+        LibError::BcryptError(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::InternalError,
+            err.to_string(),
+        ),
+        LibError::Unauthorized(err) => (
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::Unauthorized,
+            err.clone(),
+        ),
+        LibError::JwtError(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::TokenError,
+            err.to_string(),
+        ),
+        LibError::TooManyRequests(err) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::TooManyRequests,
+            err.clone(),
+        ),
+        LibError::Conflict(err) => (StatusCode::CONFLICT, ErrorCode::Conflict, err.clone()),
+        LibError::Constraint(err) => return constraint_problem(err),
+        LibError::PreconditionFailed(err) => (
+            StatusCode::PRECONDITION_FAILED,
+            ErrorCode::PreconditionFailed,
+            err.clone(),
+        ),
+        LibError::InvalidInput(err) => (
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidInput,
+            err.clone(),
+        ),
+    };
+    Problem::new(status, code, detail)
 }
 
-fn create_response_json(code: u16, msg: String) -> Response<Body> {
-    let response = Response::builder().header("Content-Type", "application/json");
-    let body = serde_json::to_string(&ApiResult::from(Null, msg, code))
-        .expect("This serialize must be valid");
-    response
-        .status(code)
-        .body(Body::new(body))
-        .expect("Response must be valid")
+// one entry per failed rule, nested structs and lists are addressed as `items[0].name`
+fn field_errors(prefix: Option<&str>, errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields: Vec<_> = errors.errors().iter().collect();
+    fields.sort_by_key(|(field, _)| **field);
+    let path = |field: &str| match (prefix, field) {
+        (prefix, "__all__") => prefix.map(str::to_string),
+        (Some(prefix), field) => Some(format!("{prefix}.{field}")),
+        (None, field) => Some(field.to_string()),
+    };
+    fields
+        .into_iter()
+        .flat_map(|(field, kind)| match kind {
+            ValidationErrorsKind::Field(errors) => errors
+                .iter()
+                .map(|error| FieldError {
+                    field: path(field),
+                    code: error.code.to_string(),
+                    message: error
+                        .message
+                        .as_ref()
+                        .map_or_else(|| error.code.to_string(), |message| message.to_string()),
+                })
+                .collect(),
+            ValidationErrorsKind::Struct(errors) => field_errors(path(field).as_deref(), errors),
+            ValidationErrorsKind::List(items) => items
+                .iter()
+                .flat_map(|(i, errors)| {
+                    let item = format!("{}[{i}]", path(field).unwrap_or_default());
+                    field_errors(Some(&item), errors)
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+impl IntoResponse for RestApiError {
+    fn into_response(self) -> Response<Body> {
+        let problem = self.to_problem();
+        // the response stays vague about server errors, the log keeps the cause, e.g. the sqlx error
+        match problem.status >= 500 {
+            true => tracing::error!(code = ?problem.code, error = %self, "request failed"),
+            false => tracing::debug!(code = ?problem.code, error = %self, "request rejected"),
+        }
+        metrics::counter!(HTTP_ERRORS, "variant" => self.variant()).increment(1);
+        problem.to_response(false)
+    }
 }
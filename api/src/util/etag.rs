@@ -0,0 +1,59 @@
+use crate::util::error::RestApiError;
+use crate::AppState;
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderName},
+};
+use std::sync::Arc;
+
+// the ETag of a versioned record, a strong validator holding its version
+pub fn etag(version: i32) -> [(HeaderName, String); 1] {
+    [(header::ETAG, format!("\"{version}\""))]
+}
+
+// the version named by `If-Match` on a PUT or DELETE, None when the client sent `*` or,
+// unless REQUIRE_IF_MATCH is set, no header at all
+#[derive(Debug, Clone, Copy)]
+pub struct IfMatch(pub Option<i32>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = RestApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(header::IF_MATCH) else {
+            let required = parts
+                .extensions
+                .get::<Arc<AppState>>()
+                .is_some_and(|state| state.config.server.require_if_match);
+            if required {
+                return Err(RestApiError::PreconditionRequired(
+                    "If-Match header is required".to_string(),
+                ));
+            }
+            return Ok(IfMatch(None));
+        };
+        let value = value
+            .to_str()
+            .map_err(|_| RestApiError::BadRequest("Invalid If-Match header".to_string()))?
+            .trim();
+        if value == "*" {
+            return Ok(IfMatch(None));
+        }
+        // weak or foreign tags can never match one of ours
+        value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .and_then(|version| version.parse().ok())
+            .map(|version| IfMatch(Some(version)))
+            .ok_or_else(|| {
+                RestApiError::PreconditionFailed(
+                    "If-Match does not match the current version".to_string(),
+                )
+            })
+    }
+}
@@ -0,0 +1,325 @@
+use crate::util::error::RestApiError;
+use crate::util::import::XLSX_CONTENT_TYPE;
+use crate::util::shutdown;
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, HeaderMap, Uri},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::stream::{self, Stream, StreamExt};
+use lib::util::error::LibError;
+use rust_xlsxwriter::Workbook;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Seek, SeekFrom};
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::task::JoinHandle;
+use tokio_util::io::ReaderStream;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+// rows buffered between the database and the response
+const EXPORT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => XLSX_CONTENT_TYPE,
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    // `json`, `csv`, `xlsx` or `ndjson`, the Accept header decides when missing
+    #[param(pattern = "^(json|csv|xlsx|ndjson)$")]
+    pub format: Option<String>,
+}
+
+// the export format asked for with `?format=` or the Accept header, None means the json listing
+#[derive(Debug, Clone, Copy)]
+pub struct Export(pub Option<ExportFormat>);
+
+impl Export {
+    // reads the format from the query string first, then from the Accept header
+    pub fn requested(uri: &Uri, headers: &HeaderMap) -> Result<Self, RestApiError> {
+        let Query(query) = Query::<ExportQuery>::try_from_uri(uri)?;
+        if let Some(format) = query.format {
+            return match format.as_str() {
+                "json" => Ok(Export(None)),
+                "csv" => Ok(Export(Some(ExportFormat::Csv))),
+                "xlsx" => Ok(Export(Some(ExportFormat::Xlsx))),
+                "ndjson" => Ok(Export(Some(ExportFormat::Ndjson))),
+                _ => Err(RestApiError::BadRequest(
+                    "Unsupported format, expected json, csv, xlsx or ndjson".to_string(),
+                )),
+            };
+        }
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        // the first media type we know wins
+        for media_type in accept.split(',') {
+            match media_type.split(';').next().unwrap_or_default().trim() {
+                "application/json" => return Ok(Export(None)),
+                "text/csv" => return Ok(Export(Some(ExportFormat::Csv))),
+                XLSX_CONTENT_TYPE => return Ok(Export(Some(ExportFormat::Xlsx))),
+                "application/x-ndjson" => return Ok(Export(Some(ExportFormat::Ndjson))),
+                _ => {}
+            }
+        }
+        Ok(Export(None))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Export
+where
+    S: Send + Sync,
+{
+    type Rejection = RestApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Export::requested(&parts.uri, &parts.headers)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ExportCell {
+    Text(String),
+    Number(f64),
+}
+
+impl ExportCell {
+    // spreadsheets open csv files by evaluating cells that look like formulas, a leading quote
+    // keeps `=HYPERLINK(...)` in a supplier name plain text. Numbers are written as they are.
+    pub fn into_csv(self) -> String {
+        match self {
+            ExportCell::Text(value) if value.starts_with(['=', '+', '-', '@', '\t', '\r']) => {
+                format!("'{value}")
+            }
+            cell => cell.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ExportCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportCell::Text(value) => f.write_str(value),
+            ExportCell::Number(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<&String> for ExportCell {
+    fn from(value: &String) -> Self {
+        ExportCell::Text(value.clone())
+    }
+}
+
+impl From<i32> for ExportCell {
+    fn from(value: i32) -> Self {
+        ExportCell::Number(value.into())
+    }
+}
+
+impl From<Uuid> for ExportCell {
+    fn from(value: Uuid) -> Self {
+        ExportCell::Text(value.to_string())
+    }
+}
+
+impl From<NaiveDate> for ExportCell {
+    fn from(value: NaiveDate) -> Self {
+        ExportCell::Text(value.to_string())
+    }
+}
+
+impl From<DateTime<Utc>> for ExportCell {
+    fn from(value: DateTime<Utc>) -> Self {
+        ExportCell::Text(value.to_rfc3339())
+    }
+}
+
+impl<T: Into<ExportCell>> From<Option<T>> for ExportCell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(ExportCell::Text(String::new()), Into::into)
+    }
+}
+
+// a record that can be written as a spreadsheet row, ndjson uses its serde form
+pub trait ExportRecord: Serialize + Send + 'static {
+    const HEADERS: &'static [&'static str];
+
+    fn to_row(&self) -> Vec<ExportCell>;
+}
+
+// runs `export` in its own task and streams what it sends in the requested format,
+// errors raised before the first row (like an unknown sort field) are returned as usual
+pub async fn export_response<R, F, Fut>(
+    format: ExportFormat,
+    file_name: &str,
+    export: F,
+) -> Result<Response, RestApiError>
+where
+    R: ExportRecord,
+    F: FnOnce(Sender<R>) -> Fut,
+    Fut: Future<Output = Result<(), LibError>> + Send + 'static,
+{
+    let (sender, mut receiver) = mpsc::channel(EXPORT_BUFFER);
+    let task = shutdown::spawn(export(sender));
+    let (first, task) = match receiver.recv().await {
+        Some(record) => (Some(record), Some(task)),
+        None => {
+            finish(task).await?;
+            (None, None)
+        }
+    };
+
+    let body = match format {
+        ExportFormat::Csv | ExportFormat::Ndjson => {
+            Body::from_stream(text_stream(format, first, receiver, task))
+        }
+        ExportFormat::Xlsx => {
+            let file = xlsx_file(first, receiver).await?;
+            if let Some(task) = task {
+                finish(task).await?;
+            }
+            Body::from_stream(ReaderStream::new(tokio::fs::File::from_std(file)))
+        }
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{file_name}.{}\"",
+                    format.extension()
+                ),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+async fn finish(task: JoinHandle<Result<(), LibError>>) -> Result<(), RestApiError> {
+    match task.await {
+        Ok(result) => result.map_err(RestApiError::from_lib),
+        Err(e) => Err(RestApiError::InternalServerError(e.to_string())),
+    }
+}
+
+fn records<R: Send>(first: Option<R>, receiver: Receiver<R>) -> impl Stream<Item = R> + Send {
+    stream::iter(first).chain(stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|record| (record, receiver))
+    }))
+}
+
+fn text_stream<R: ExportRecord>(
+    format: ExportFormat,
+    first: Option<R>,
+    receiver: Receiver<R>,
+    task: Option<JoinHandle<Result<(), LibError>>>,
+) -> impl Stream<Item = Result<Vec<u8>, io::Error>> + Send {
+    let header = (format == ExportFormat::Csv).then(|| {
+        csv_line(
+            R::HEADERS
+                .iter()
+                .map(|header| ExportCell::Text(header.to_string())),
+        )
+    });
+    let rows = records(first, receiver).map(move |record| match format {
+        ExportFormat::Csv => csv_line(record.to_row()),
+        _ => serde_json::to_vec(&record)
+            .map(|mut line| {
+                line.push(b'\n');
+                line
+            })
+            .map_err(io::Error::other),
+    });
+    // a failure after the first row can only abort the body
+    let end = stream::once(async move {
+        match task {
+            Some(task) => finish(task)
+                .await
+                .map_err(|e| io::Error::other(e.to_string())),
+            None => Ok(()),
+        }
+    })
+    .filter_map(|result| async move { result.err().map(Err) });
+    stream::iter(header).chain(rows).chain(end)
+}
+
+fn csv_line(cells: impl IntoIterator<Item = ExportCell>) -> Result<Vec<u8>, io::Error> {
+    let mut writer = csv::WriterBuilder::new().from_writer(Vec::new());
+    writer.write_record(cells.into_iter().map(ExportCell::into_csv))?;
+    writer.into_inner().map_err(|e| e.into_error())
+}
+
+// xlsx is a zip that can only be finished once every row is known, rows go to a temporary
+// file in constant memory mode and the finished workbook is streamed from disk
+async fn xlsx_file<R: ExportRecord>(
+    first: Option<R>,
+    mut receiver: Receiver<R>,
+) -> Result<std::fs::File, RestApiError> {
+    let written = tokio::task::spawn_blocking(move || -> Result<std::fs::File, String> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        for (col, header) in R::HEADERS.iter().enumerate() {
+            worksheet
+                .write_string(0, col as u16, *header)
+                .map_err(|e| e.to_string())?;
+        }
+        let mut row = 1;
+        let mut next = first;
+        while let Some(record) = next.take().or_else(|| receiver.blocking_recv()) {
+            // text is always written as a string cell, never as a formula
+            for (col, cell) in record.to_row().into_iter().enumerate() {
+                match cell {
+                    ExportCell::Text(value) => worksheet.write_string(row, col as u16, value),
+                    ExportCell::Number(value) => worksheet.write_number(row, col as u16, value),
+                }
+                .map_err(|e| e.to_string())?;
+            }
+            row += 1;
+        }
+        let mut file = tempfile::tempfile().map_err(|e| e.to_string())?;
+        workbook
+            .save_to_writer(&mut file)
+            .map_err(|e| e.to_string())?;
+        file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+        Ok(file)
+    })
+    .await;
+    match written {
+        Ok(Ok(file)) => Ok(file),
+        Ok(Err(e)) => Err(RestApiError::InternalServerError(e)),
+        Err(e) => Err(RestApiError::InternalServerError(e.to_string())),
+    }
+}
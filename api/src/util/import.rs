@@ -0,0 +1,262 @@
+use crate::util::error::RestApiError;
+use crate::util::res::ApiResult;
+use axum::{extract::Multipart, http::StatusCode, Json};
+use calamine::{Data, DataType, Reader, Xlsx};
+use csv::{StringRecord, Trim};
+use lib::util::model::{ImportReport, ImportRow, ImportRowError};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::io::Cursor;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationErrors};
+
+pub const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+const MAX_IMPORT_ROWS: usize = 10_000;
+pub const XLSX_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestImportQueryDto {
+    // nothing is written unless the caller asks for it with `dry_run=false`
+    pub dry_run: Option<bool>,
+}
+
+impl RequestImportQueryDto {
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(true)
+    }
+}
+
+// documents the multipart body read by `ImportFile::from_multipart`
+#[derive(ToSchema)]
+pub struct ImportFileUpload {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ImportFormat {
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let file_name = file_name.to_lowercase();
+        if file_name.ends_with(".xlsx") {
+            Some(ImportFormat::Xlsx)
+        } else if file_name.ends_with(".csv") {
+            Some(ImportFormat::Csv)
+        } else {
+            None
+        }
+    }
+}
+
+// an uploaded spreadsheet, headers are matched against the dto field names
+#[derive(Debug)]
+pub struct ImportFile {
+    headers: StringRecord,
+    // line number in the file and the cells of that line
+    rows: Vec<(usize, StringRecord)>,
+}
+
+impl ImportFile {
+    // reads the `file` field of a multipart upload, csv or xlsx by extension or content type
+    pub async fn from_multipart(mut multipart: Multipart) -> Result<Self, RestApiError> {
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| RestApiError::BadRequest(e.body_text()))?
+        {
+            if field.name() != Some("file") {
+                continue;
+            }
+            let file_name = field.file_name().unwrap_or_default().to_string();
+            let content_type = field.content_type().unwrap_or_default().to_string();
+            let bytes = field
+                .bytes()
+                .await
+                .map_err(|e| RestApiError::BadRequest(e.body_text()))?;
+            let format = match ImportFormat::from_file_name(&file_name) {
+                Some(format) => format,
+                None if content_type == XLSX_CONTENT_TYPE => ImportFormat::Xlsx,
+                None if content_type.starts_with("text/csv") => ImportFormat::Csv,
+                None => {
+                    return Err(RestApiError::BadRequest(
+                        "Unsupported file type, expected CSV or XLSX".to_string(),
+                    ))
+                }
+            };
+            return Self::from_bytes(format, &bytes);
+        }
+        Err(RestApiError::BadRequest("Missing file".to_string()))
+    }
+
+    pub fn from_bytes(format: ImportFormat, bytes: &[u8]) -> Result<Self, RestApiError> {
+        let file = match format {
+            ImportFormat::Csv => Self::from_csv(bytes)?,
+            ImportFormat::Xlsx => Self::from_xlsx(bytes)?,
+        };
+        if file.rows.len() > MAX_IMPORT_ROWS {
+            return Err(RestApiError::BadRequest(format!(
+                "Too many rows, at most {MAX_IMPORT_ROWS} can be imported at once"
+            )));
+        }
+        Ok(file)
+    }
+
+    fn from_csv(bytes: &[u8]) -> Result<Self, RestApiError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(Trim::All)
+            .flexible(true)
+            .from_reader(bytes);
+        let headers = normalize_headers(
+            reader
+                .headers()
+                .map_err(|e| RestApiError::BadRequest(format!("Invalid CSV: {e}")))?,
+        );
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record =
+                record.map_err(|e| RestApiError::BadRequest(format!("Invalid CSV: {e}")))?;
+            let line = record.position().map_or(0, |p| p.line() as usize);
+            if record.iter().any(|cell| !cell.is_empty()) {
+                rows.push((line, record));
+            }
+        }
+        Ok(Self { headers, rows })
+    }
+
+    fn from_xlsx(bytes: &[u8]) -> Result<Self, RestApiError> {
+        let invalid =
+            |e: calamine::XlsxError| RestApiError::BadRequest(format!("Invalid XLSX: {e}"));
+        let mut workbook = Xlsx::new(Cursor::new(bytes)).map_err(invalid)?;
+        let range = workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| RestApiError::BadRequest("XLSX has no worksheet".to_string()))?
+            .map_err(invalid)?;
+        let first_line = range.start().map_or(0, |(row, _)| row as usize) + 1;
+        let mut lines = range.rows();
+        let headers = normalize_headers(&lines.next().map(to_record).unwrap_or_default());
+        let rows = lines
+            .enumerate()
+            .map(|(i, cells)| (first_line + i + 1, to_record(cells)))
+            .filter(|(_, record)| record.iter().any(|cell| !cell.is_empty()))
+            .collect();
+        Ok(Self { headers, rows })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    // runs every row through the dto's deserialization and validation rules
+    pub fn parse<T, R>(
+        &self,
+        to_record: impl Fn(&T) -> R,
+    ) -> (Vec<ImportRow<R>>, Vec<ImportRowError>)
+    where
+        T: DeserializeOwned + Validate,
+    {
+        let mut rows = Vec::new();
+        let mut errors = Vec::new();
+        for (line, record) in &self.rows {
+            match record.deserialize::<T>(Some(&self.headers)) {
+                Ok(dto) => match dto.validate() {
+                    Ok(()) => rows.push(ImportRow {
+                        row: *line,
+                        record: to_record(&dto),
+                    }),
+                    Err(e) => errors.extend(validation_errors(*line, &e)),
+                },
+                Err(e) => errors.push(deserialize_error(*line, &self.headers, &e)),
+            }
+        }
+        (rows, errors)
+    }
+}
+
+// merges the row errors found while parsing into the repository report
+pub fn import_response(
+    mut report: ImportReport,
+    total: usize,
+    errors: Vec<ImportRowError>,
+    dry_run: bool,
+) -> (StatusCode, Json<ApiResult<ImportReport>>) {
+    report.total = total;
+    report.dry_run = dry_run;
+    report.errors.extend(errors);
+    report.errors.sort_by_key(|error| error.row);
+    let (status, message) = if !report.errors.is_empty() {
+        (StatusCode::UNPROCESSABLE_ENTITY, "Import has invalid rows")
+    } else if dry_run {
+        (StatusCode::OK, "Import is valid")
+    } else {
+        (StatusCode::CREATED, "Import completed")
+    };
+    (
+        status,
+        Json(ApiResult::from(report, message.to_string(), status.into())),
+    )
+}
+
+fn normalize_headers(headers: &StringRecord) -> StringRecord {
+    headers
+        .iter()
+        .map(|header| header.trim().to_lowercase().replace([' ', '-'], "_"))
+        .collect()
+}
+
+fn to_record(cells: &[Data]) -> StringRecord {
+    cells
+        .iter()
+        .map(|cell| match cell {
+            Data::DateTime(_) | Data::DateTimeIso(_) => cell
+                .as_date()
+                .map(|date| date.to_string())
+                .unwrap_or_else(|| cell.to_string()),
+            _ => cell.to_string().trim().to_string(),
+        })
+        .collect()
+}
+
+fn deserialize_error(line: usize, headers: &StringRecord, err: &csv::Error) -> ImportRowError {
+    match err.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => ImportRowError {
+            row: line,
+            field: err
+                .field()
+                .and_then(|i| headers.get(i as usize))
+                .map(str::to_string),
+            message: err.kind().to_string(),
+        },
+        _ => ImportRowError {
+            row: line,
+            field: None,
+            message: err.to_string(),
+        },
+    }
+}
+
+fn validation_errors(line: usize, errors: &ValidationErrors) -> Vec<ImportRowError> {
+    errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors.iter().map(move |error| ImportRowError {
+                row: line,
+                field: (field != "__all__").then(|| field.to_string()),
+                message: error
+                    .message
+                    .as_ref()
+                    .map_or_else(|| error.code.to_string(), |message| message.to_string()),
+            })
+        })
+        .collect()
+}
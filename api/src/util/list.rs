@@ -0,0 +1,52 @@
+use lib::util::model::{ListQuery, SortDirection};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+static RE_SORT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z_]{1,32}$").unwrap());
+
+// accepted by every list route, entity specific filters come from a second query dto
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestListQueryDto {
+    #[validate(range(min = 1, message = "invalid"))]
+    pub page: Option<i64>,
+    #[validate(range(min = 1, max = 500, message = "invalid"))]
+    pub limit: Option<i64>,
+    #[validate(length(min = 1, max = 512, message = "invalid"))]
+    pub cursor: Option<String>,
+    #[validate(regex(path = *RE_SORT, message = "invalid"))]
+    pub sort: Option<String>,
+    pub order: Option<SortDirection>,
+    #[validate(length(max = 100, message = "invalid"))]
+    pub q: Option<String>,
+}
+
+impl RequestListQueryDto {
+    pub fn to_list_query(&self, filters: Vec<(String, String)>) -> ListQuery {
+        let default = ListQuery::default();
+        ListQuery {
+            page: self.page.unwrap_or(default.page),
+            limit: self.limit.unwrap_or(default.limit),
+            cursor: self.cursor.clone(),
+            sort: self.sort.clone(),
+            direction: self.order.unwrap_or_default(),
+            filters,
+            search: self.q.clone(),
+        }
+    }
+}
+
+// collects the filters that were given, keyed by the name the repository knows them by
+pub fn filters<const N: usize>(fields: [(&str, &Option<String>); N]) -> Vec<(String, String)> {
+    fields
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value
+                .as_ref()
+                .map(|value| (name.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+use axum::http::HeaderMap;
+use config::{LogConfig, LogFormat, OtlpConfig};
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry::{global, Context};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+// keeps the span exporter running, spans still buffered are sent on `shutdown`
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("spans could not be exported: {e}");
+            }
+        }
+    }
+}
+
+// logs go to stdout, as lines of text or one json object per line for log collectors.
+// With `otlp` the same spans are exported to an OpenTelemetry collector.
+pub fn init_logging(log: &LogConfig, otlp: Option<&OtlpConfig>) -> Telemetry {
+    // the level was checked when the config was loaded
+    let filter = EnvFilter::try_new(&log.level).unwrap_or_else(|_| EnvFilter::new("info"));
+    let (text, json) = match log.format {
+        LogFormat::Text => (Some(tracing_subscriber::fmt::layer()), None),
+        // every line carries the fields of the request span, like the request id
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false),
+            ),
+        ),
+    };
+    let provider = otlp.and_then(|otlp| match tracer_provider(otlp) {
+        Ok(provider) => Some(provider),
+        Err(e) => {
+            eprintln!("spans are not exported, the OTLP exporter failed to start: {e}");
+            None
+        }
+    });
+    let otel = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("api")));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(text)
+        .with(json)
+        .with(otel)
+        .init();
+    Telemetry { provider }
+}
+
+fn tracer_provider(
+    otlp: &OtlpConfig,
+) -> Result<SdkTracerProvider, opentelemetry_otlp::ExporterBuildError> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", otlp.endpoint))
+        .build()?;
+    // incoming `traceparent` headers continue the caller's trace
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(otlp.service_name.clone())
+                .build(),
+        )
+        .build())
+}
+
+// the trace context a caller sent along, empty unless OTLP export is enabled
+pub fn remote_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
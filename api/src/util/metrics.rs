@@ -0,0 +1,69 @@
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use once_cell::sync::Lazy;
+
+pub const HTTP_REQUESTS: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION: &str = "http_request_duration_seconds";
+pub const HTTP_ERRORS: &str = "http_errors_total";
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+pub const DB_POOL_MAX_CONNECTIONS: &str = "db_pool_max_connections";
+pub const DB_POOL_ACQUIRE_SECONDS: &str = "db_pool_acquire_seconds";
+pub const STOCK_VALUE: &str = "inventory_stock_value";
+pub const STOCK_MATERIALS: &str = "inventory_materials";
+pub const LOW_STOCK_MATERIALS: &str = "inventory_low_stock_materials";
+
+// from a fast lookup to a slow export
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+// the recorder behind the `metrics` macros, installed once per process and shared by every app
+static HANDLE: Lazy<PrometheusHandle> = Lazy::new(|| {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION.to_string()),
+            LATENCY_BUCKETS,
+        )
+        .expect("Latency buckets must not be empty")
+        .install_recorder()
+        .expect("Metrics recorder must be installed once");
+    describe();
+    handle
+});
+
+pub fn metrics_handle() -> &'static PrometheusHandle {
+    &HANDLE
+}
+
+fn describe() {
+    metrics::describe_counter!(
+        HTTP_REQUESTS,
+        "Requests served, by method, route and status"
+    );
+    metrics::describe_histogram!(
+        HTTP_REQUEST_DURATION,
+        metrics::Unit::Seconds,
+        "Time to answer a request, by method and route"
+    );
+    metrics::describe_counter!(HTTP_ERRORS, "Error responses, by RestApiError variant");
+    metrics::describe_gauge!(DB_POOL_CONNECTIONS, "Open Postgres connections");
+    metrics::describe_gauge!(
+        DB_POOL_IDLE_CONNECTIONS,
+        "Open Postgres connections not in use"
+    );
+    metrics::describe_gauge!(DB_POOL_MAX_CONNECTIONS, "Largest size the pool may grow to");
+    metrics::describe_gauge!(
+        DB_POOL_ACQUIRE_SECONDS,
+        metrics::Unit::Seconds,
+        "Time the last scrape waited for a pooled connection"
+    );
+    metrics::describe_gauge!(
+        STOCK_VALUE,
+        "Price times quantity of all materials in stock"
+    );
+    metrics::describe_gauge!(STOCK_MATERIALS, "Materials on record");
+    metrics::describe_gauge!(
+        LOW_STOCK_MATERIALS,
+        "Materials at or below the low stock threshold"
+    );
+}
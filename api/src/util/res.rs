@@ -1,10 +1,13 @@
+use crate::util::error::ApiError;
 use axum::body::Body;
+use lib::util::model::Page;
 use serde::Serialize;
+use utoipa::ToSchema;
 
-#[derive(Clone, Copy, Debug, Serialize)]
+#[derive(Clone, Copy, Debug, Serialize, ToSchema)]
 pub struct Null;
 
-#[derive(Clone, Copy, Debug, Serialize)]
+#[derive(Clone, Copy, Debug, Serialize, ToSchema)]
 pub struct Result<T>
 where
     T: Serialize,
@@ -21,14 +24,29 @@ where
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, ToSchema)]
 pub struct ApiResult<T = Null>
 where
     T: Serialize,
 {
+    // inlined, named generic schemas of nested generics (`Result<Vec<T>>`) would collide
+    #[schema(inline)]
     pub rslt: Result<T>,
     pub status_message: String,
     pub status_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ListMeta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiError>,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ListMeta {
+    pub total: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<i64>,
+    pub limit: i64,
+    pub next_cursor: Option<String>,
 }
 
 impl<T> From<ApiResult<T>> for Body
@@ -50,6 +68,28 @@ where
             rslt: Result::from_data(data),
             status_message,
             status_code,
+            meta: None,
+            error: None,
+        }
+    }
+}
+
+impl<T> ApiResult<Vec<T>>
+where
+    T: Serialize,
+{
+    pub fn from_page(page: Page<T>, status_message: String, status_code: u16) -> Self {
+        Self {
+            rslt: Result::from_data(page.items),
+            status_message,
+            status_code,
+            meta: Some(ListMeta {
+                total: page.total,
+                page: page.page,
+                limit: page.limit,
+                next_cursor: page.next_cursor,
+            }),
+            error: None,
         }
     }
 }
@@ -0,0 +1,90 @@
+use axum::Router;
+use once_cell::sync::Lazy;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+use tokio_util::task::TaskTracker;
+
+// cancelled once SIGTERM or Ctrl-C arrives, readiness turns false from then on
+static SHUTDOWN: Lazy<CancellationToken> = Lazy::new(CancellationToken::new);
+// work that outlives the handler which started it, like an export still being streamed
+static TASKS: Lazy<TaskTracker> = Lazy::new(TaskTracker::new);
+
+// spawns `task` so a shutdown waits for it to finish
+pub fn spawn<F>(task: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    TASKS.spawn(task)
+}
+
+pub fn is_shutting_down() -> bool {
+    SHUTDOWN.is_cancelled()
+}
+
+// resolves when the shutdown started, for `axum::serve(...).with_graceful_shutdown`
+pub fn shutting_down() -> WaitForCancellationFutureOwned {
+    SHUTDOWN.clone().cancelled_owned()
+}
+
+// waits for SIGTERM (what docker and kubernetes send) or Ctrl-C and starts the shutdown
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Ctrl-C handler must be installed");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("SIGTERM handler must be installed")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutdown started, draining requests");
+    SHUTDOWN.cancel();
+}
+
+// waits for the spawned tasks, no new ones are expected once the server stopped
+pub async fn drain_tasks() {
+    TASKS.close();
+    TASKS.wait().await;
+}
+
+// serves `app` until `signal` resolves, then refuses new connections while running requests
+// and spawned tasks together get `grace` to finish
+pub async fn serve<F>(listener: TcpListener, app: Router, signal: F, grace: Duration)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let stop = CancellationToken::new();
+    let serve = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(stop.clone().cancelled_owned());
+    let mut server = tokio::spawn(async move {
+        serve.await.expect("Axum must be able to start");
+        drain_tasks().await;
+    });
+    tokio::select! {
+        result = &mut server => result.expect("Axum must keep serving until shutdown"),
+        _ = signal => {
+            stop.cancel();
+            if tokio::time::timeout(grace, &mut server).await.is_err() {
+                tracing::warn!("requests or tasks still running after {grace:?}, stopping anyway");
+            }
+        }
+    }
+}
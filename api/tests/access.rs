@@ -0,0 +1,197 @@
+mod common;
+
+use api::rest::openapi::ApiDoc;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::TestApp;
+use std::collections::BTreeSet;
+use utoipa::OpenApi;
+
+const ID: &str = "00000000-0000-4000-8000-000000000000";
+
+const ADMIN: &[&str] = &["Admin"];
+const ANYONE: &[&str] = &["Admin", "User"];
+
+// every route behind AuthLayer as (method, OpenAPI path, roles its role_check lets through)
+const PROTECTED: &[(&str, &str, &[&str])] = &[
+    ("get", "/api/users", ADMIN),
+    ("post", "/api/users", ANYONE),
+    ("put", "/api/users", ANYONE),
+    ("delete", "/api/users", ANYONE),
+    ("get", "/api/users/info", ANYONE),
+    ("get", "/api/users/sessions", ANYONE),
+    ("delete", "/api/users/sessions", ANYONE),
+    ("delete", "/api/users/sessions/{session_id}", ANYONE),
+    ("get", "/api/users/login-events", ADMIN),
+    ("post", "/api/users/bulk", ADMIN),
+    ("get", "/api/users/deleted", ADMIN),
+    ("post", "/api/users/{id}/restore", ADMIN),
+    ("get", "/api/users/{id}", ADMIN),
+    ("put", "/api/users/{id}", ADMIN),
+    ("delete", "/api/users/{id}", ADMIN),
+    ("post", "/api/users/{id}/unlock", ADMIN),
+    ("get", "/api/users/{id}/sessions", ADMIN),
+    ("delete", "/api/users/{id}/sessions", ADMIN),
+    ("post", "/api/roles", ADMIN),
+    ("get", "/api/roles", ADMIN),
+    ("get", "/api/roles/deleted", ADMIN),
+    ("post", "/api/roles/{id}/restore", ADMIN),
+    ("get", "/api/roles/{id}", ADMIN),
+    ("put", "/api/roles/{id}", ADMIN),
+    ("delete", "/api/roles/{id}", ADMIN),
+    ("get", "/api/suppliers", ANYONE),
+    ("post", "/api/suppliers", ADMIN),
+    ("post", "/api/suppliers/import", ADMIN),
+    ("post", "/api/suppliers/bulk", ADMIN),
+    ("get", "/api/suppliers/deleted", ADMIN),
+    ("post", "/api/suppliers/{id}/restore", ADMIN),
+    ("get", "/api/suppliers/{id}", ANYONE),
+    ("put", "/api/suppliers/{id}", ADMIN),
+    ("delete", "/api/suppliers/{id}", ADMIN),
+    ("get", "/api/materials", ANYONE),
+    ("get", "/api/materials/groups", ANYONE),
+    ("post", "/api/materials/groups", ADMIN),
+    ("post", "/api/materials/import", ADMIN),
+    ("post", "/api/materials/groups/bulk", ADMIN),
+    ("get", "/api/materials/groups/deleted", ADMIN),
+    ("post", "/api/materials/groups/{id}/restore", ADMIN),
+    ("get", "/api/materials/groups/{id}", ANYONE),
+    ("put", "/api/materials/groups/{id}", ADMIN),
+    ("delete", "/api/materials/groups/{id}", ADMIN),
+    ("get", "/api/materials/groups/sub/{group_name}", ANYONE),
+    ("get", "/api/audit-logs", ADMIN),
+    ("get", "/api/search", ANYONE),
+];
+
+fn uri(path: &str) -> String {
+    path.replace("{id}", ID)
+        .replace("{session_id}", ID)
+        .replace("{group_name}", "Metals")
+}
+
+async fn call(app: &TestApp, method: &str, path: &str, authorization: Option<&str>) -> StatusCode {
+    let mut request = Request::builder()
+        .method(Method::from_bytes(method.to_uppercase().as_bytes()).unwrap())
+        .uri(uri(path));
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    app.send(request.body(Body::empty()).unwrap()).await.status
+}
+
+// operations of the spec that do not make authentication optional with an empty `security(())` entry
+fn documented_protected() -> BTreeSet<(String, String)> {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let mut protected = BTreeSet::new();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            let optional = operation["security"]
+                .as_array()
+                .is_some_and(|security| security.contains(&serde_json::json!({})));
+            if !optional {
+                protected.insert((method.clone(), path.clone()));
+            }
+        }
+    }
+    protected
+}
+
+#[test]
+fn every_protected_route_is_checked() {
+    let checked: BTreeSet<(String, String)> = PROTECTED
+        .iter()
+        .map(|(method, path, _)| (method.to_string(), path.to_string()))
+        .collect();
+    assert_eq!(
+        checked.len(),
+        PROTECTED.len(),
+        "PROTECTED lists a route twice"
+    );
+    assert_eq!(checked, documented_protected());
+}
+
+#[tokio::test]
+async fn protected_routes_refuse_missing_credentials() {
+    let app = TestApp::new().await;
+    for (method, path, _) in PROTECTED {
+        assert_eq!(
+            call(&app, method, path, None).await,
+            StatusCode::UNAUTHORIZED,
+            "{method} {path}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn protected_routes_refuse_invalid_credentials() {
+    let app = TestApp::new().await;
+    let user = app.login_as_user().await;
+    let invalid = [
+        "Bearer not-a-token".to_string(),
+        // the token is only read after the Bearer scheme
+        user.token.clone(),
+        format!("Basic {}", user.token),
+        format!("Bearer {}x", user.token),
+    ];
+    for (method, path, _) in PROTECTED {
+        for authorization in &invalid {
+            assert_eq!(
+                call(&app, method, path, Some(authorization)).await,
+                StatusCode::UNAUTHORIZED,
+                "{method} {path} with {authorization}"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn role_check_refuses_users_on_admin_routes() {
+    let app = TestApp::new().await;
+    for (method, path, roles) in PROTECTED {
+        // some routes end the session or the account, every route gets a fresh login
+        let user = app.login_as_user().await;
+        let bearer = format!("Bearer {}", user.token);
+        let status = call(&app, method, path, Some(&bearer)).await;
+        if !roles.contains(&"User") {
+            assert_eq!(status, StatusCode::FORBIDDEN, "{method} {path}");
+        } else {
+            assert!(
+                status != StatusCode::FORBIDDEN && status != StatusCode::UNAUTHORIZED,
+                "{method} {path} answered {status}"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn role_check_lets_admins_through() {
+    let app = TestApp::new().await;
+    for (method, path, _) in PROTECTED {
+        // some routes end the session or the account, every route gets a fresh login
+        let admin = app.login_as_admin().await;
+        let bearer = format!("Bearer {}", admin.token);
+        let status = call(&app, method, path, Some(&bearer)).await;
+        assert!(
+            status != StatusCode::FORBIDDEN && status != StatusCode::UNAUTHORIZED,
+            "{method} {path} answered {status}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn public_routes_need_no_credentials() {
+    let app = TestApp::new().await;
+    assert_eq!(app.get("/api", None).await.status, StatusCode::OK);
+    assert_eq!(
+        app.get("/.well-known/jwks.json", None).await.status,
+        StatusCode::OK
+    );
+    let response = app
+        .post(
+            "/api/auth/login",
+            None,
+            serde_json::json!({ "email": "nobody@example.com", "password": "Passw0rd" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
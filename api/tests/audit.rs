@@ -0,0 +1,46 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn changes_are_audited_with_their_actor() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+    let role = app
+        .post("/api/roles", token, json!({ "name": "Buyer" }))
+        .await
+        .data();
+    let id = role["id"].as_str().unwrap();
+    app.put(
+        &format!("/api/roles/{id}"),
+        token,
+        json!({ "name": "Purchaser" }),
+    )
+    .await;
+
+    let logs = app
+        .get(&format!("/api/audit-logs?entity_id={id}"), token)
+        .await;
+    assert_eq!(logs.status, StatusCode::OK, "{}", logs.text());
+    let logs = logs.data();
+    let actions: Vec<&str> = logs
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|log| log["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, vec!["update", "create"]);
+    assert_eq!(logs[0]["actor_id"], admin.id.to_string());
+    assert_eq!(logs[0]["actor_name"], admin.name);
+    assert_eq!(logs[0]["entity_type"], "role");
+    assert_eq!(logs[0]["before"]["name"], "Buyer");
+    assert_eq!(logs[0]["after"]["name"], "Purchaser");
+
+    let creates = app
+        .get("/api/audit-logs?entity_type=role&action=create", token)
+        .await;
+    assert_eq!(creates.data().as_array().unwrap().len(), 1);
+}
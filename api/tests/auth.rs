@@ -0,0 +1,108 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{TestApp, PASSWORD};
+use serde_json::json;
+
+#[tokio::test]
+async fn root_answers_ok() {
+    let app = TestApp::new().await;
+    let response = app.get("/api", None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!("OK"));
+}
+
+#[tokio::test]
+async fn registered_users_log_in() {
+    let app = TestApp::new().await;
+    let response = app
+        .post(
+            "/api/auth/register",
+            None,
+            json!({
+                "name": "carol",
+                "email": "carol@example.com",
+                "password": PASSWORD,
+                "address": "Main Street 2",
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.text());
+    assert_eq!(response.data()["email"], "carol@example.com");
+
+    let token = app.login("carol@example.com").await;
+    let info = app.get("/api/users/info", Some(&token)).await;
+    assert_eq!(info.status, StatusCode::OK);
+    assert_eq!(info.data()["user_info"]["name"], "carol");
+    assert_eq!(info.data()["user_info"]["roles"], "User");
+}
+
+#[tokio::test]
+async fn register_refuses_invalid_and_taken_accounts() {
+    let app = TestApp::new().await;
+    let user = app.login_as_user().await;
+
+    let invalid = app
+        .post(
+            "/api/auth/register",
+            None,
+            json!({ "name": "x", "email": "not-an-email", "password": PASSWORD }),
+        )
+        .await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+
+    let taken = app
+        .post(
+            "/api/auth/register",
+            None,
+            json!({ "name": "someone_else", "email": user.email, "password": PASSWORD }),
+        )
+        .await;
+    assert_eq!(taken.status, StatusCode::CONFLICT, "{}", taken.text());
+}
+
+#[tokio::test]
+async fn login_refuses_wrong_passwords() {
+    let app = TestApp::new().await;
+    let user = app.login_as_user().await;
+    let response = app
+        .post(
+            "/api/auth/login",
+            None,
+            json!({ "email": user.email, "password": "Wrong_password" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert!(response.data().is_null());
+}
+
+#[tokio::test]
+async fn jwks_publishes_no_secret_keys() {
+    let app = TestApp::new().await;
+    let response = app.get("/.well-known/jwks.json", None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    // an HS256 secret must never be published, there is nothing to verify with
+    assert_eq!(response.json(), json!({ "keys": [] }));
+}
+
+#[tokio::test]
+async fn oidc_routes_are_refused_while_oidc_is_disabled() {
+    let app = TestApp::new().await;
+    let login = app.get("/api/auth/oidc/login", None).await;
+    assert_eq!(login.status, StatusCode::NOT_FOUND, "{}", login.text());
+
+    let callback = app
+        .get("/api/auth/oidc/callback?code=abc&state=def", None)
+        .await;
+    assert_eq!(
+        callback.status,
+        StatusCode::NOT_FOUND,
+        "{}",
+        callback.text()
+    );
+
+    let refused = app
+        .get("/api/auth/oidc/callback?error=access_denied", None)
+        .await;
+    assert_eq!(refused.status, StatusCode::UNAUTHORIZED);
+}
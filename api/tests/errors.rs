@@ -0,0 +1,146 @@
+use api::rest::middleware::error::ErrorLayer;
+use api::rest::middleware::request_id::RequestIdLayer;
+use api::util::error::RestApiError;
+use axum::body::{to_bytes, Body};
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use serde_json::Value;
+use tower::ServiceExt;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+struct Item {
+    #[validate(length(min = 1, message = "must not be empty"))]
+    name: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct Order {
+    #[validate(range(min = 1))]
+    quantity: i32,
+    #[validate(nested)]
+    items: Vec<Item>,
+}
+
+async fn invalid() -> Result<(), RestApiError> {
+    Order {
+        quantity: 0,
+        items: vec![
+            Item {
+                name: "ok".to_string(),
+            },
+            Item {
+                name: String::new(),
+            },
+        ],
+    }
+    .validate()?;
+    Ok(())
+}
+
+async fn rate_limited() -> Result<(), RestApiError> {
+    Err(RestApiError::TooManyRequests(
+        "too many requests".to_string(),
+    ))
+}
+
+fn app(problem_json: bool) -> Router {
+    Router::new()
+        .route("/invalid", get(invalid))
+        .route("/limited", get(rate_limited))
+        .layer(ErrorLayer::new(problem_json))
+        .layer(RequestIdLayer)
+}
+
+async fn call(app: Router, request: Request<Body>) -> (Response<()>, Value) {
+    let response = app.oneshot(request).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.unwrap();
+    (
+        Response::from_parts(parts, ()),
+        serde_json::from_slice(&body).unwrap(),
+    )
+}
+
+fn get_request(path: &str) -> axum::http::request::Builder {
+    Request::builder().uri(path)
+}
+
+#[tokio::test]
+async fn validation_errors_name_each_field() {
+    let request = get_request("/invalid")
+        .header("x-request-id", "req-1")
+        .body(Body::empty())
+        .unwrap();
+    let (response, body) = call(app(false), request).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()["x-request-id"], "req-1");
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    assert_eq!(body["status_code"], 400);
+    assert_eq!(body["error"]["code"], "VALIDATION_FAILED");
+    assert_eq!(body["error"]["request_id"], "req-1");
+    assert_eq!(
+        body["error"]["errors"],
+        serde_json::json!([
+            {"field": "items[1].name", "code": "length", "message": "must not be empty"},
+            {"field": "quantity", "code": "range", "message": "range"},
+        ])
+    );
+}
+
+#[tokio::test]
+async fn problem_json_is_served_when_accepted() {
+    let request = get_request("/invalid")
+        .header(header::ACCEPT, "application/problem+json")
+        .body(Body::empty())
+        .unwrap();
+    let (response, body) = call(app(false), request).await;
+
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    assert_eq!(body["type"], "about:blank");
+    assert_eq!(body["title"], "Bad Request");
+    assert_eq!(body["status"], 400);
+    assert_eq!(body["instance"], "/invalid");
+    assert_eq!(body["code"], "VALIDATION_FAILED");
+    // a generated id, the same one echoed in the header
+    assert_eq!(
+        body["request_id"],
+        response.headers()["x-request-id"].to_str().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn axum_errors_get_a_code() {
+    let request = get_request("/missing").body(Body::empty()).unwrap();
+    let (response, body) = call(app(true), request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "application/problem+json"
+    );
+    assert_eq!(body["code"], "NOT_FOUND");
+    assert_eq!(body["instance"], "/missing");
+}
+
+#[tokio::test]
+async fn unsafe_request_ids_are_replaced() {
+    let request = get_request("/limited")
+        .header("x-request-id", "not a safe id")
+        .body(Body::empty())
+        .unwrap();
+    let (response, body) = call(app(false), request).await;
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let id = response.headers()["x-request-id"].to_str().unwrap();
+    assert_ne!(id, "not a safe id");
+    assert_eq!(body["error"]["code"], "TOO_MANY_REQUESTS");
+    assert_eq!(body["error"]["request_id"], id);
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+
+#[tokio::test]
+async fn probes_answer_without_a_token() {
+    let app = TestApp::new().await;
+
+    let live = app.get("/health/live", None).await;
+    assert_eq!(live.status, StatusCode::OK, "{}", live.text());
+    assert_eq!(live.data()["status"], "ok");
+
+    let ready = app.get("/health/ready", None).await;
+    assert_eq!(ready.status, StatusCode::OK, "{}", ready.text());
+    assert_eq!(ready.data()["status"], "ok");
+}
+
+#[tokio::test]
+async fn not_ready_while_migrations_are_pending() {
+    let app = TestApp::new().await;
+    let mut conn = app.connection().await;
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT max(version) FROM _sqlx_migrations)",
+    )
+    .execute(&mut conn)
+    .await
+    .unwrap();
+
+    let ready = app.get("/health/ready", None).await;
+    assert_eq!(ready.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(ready.data()["status"], "unavailable");
+    let reason = ready.data()["reason"].as_str().unwrap().to_string();
+    assert!(reason.contains("pending migrations"), "{reason}");
+
+    // the process itself is fine, it must not be restarted for this
+    let live = app.get("/health/live", None).await;
+    assert_eq!(live.status, StatusCode::OK);
+}
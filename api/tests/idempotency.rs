@@ -0,0 +1,177 @@
+mod common;
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderName, Method, Request, StatusCode};
+use common::{TestApp, TestResponse, TestUser};
+use lib::idempotency::model::IdempotentRequest;
+use serde_json::{json, Value};
+use sqlx::Connection;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+fn supplier(name: &str) -> Value {
+    json!({
+        "name": name,
+        "email": "sales@example.com",
+        "phone": "555",
+        "address": "Harbour 7",
+    })
+}
+
+async fn create(app: &TestApp, user: &TestUser, key: &str, body: Value) -> TestResponse {
+    app.request(
+        Method::POST,
+        "/api/suppliers",
+        Some(&user.token),
+        &[(KEY, key)],
+        Some(body),
+    )
+    .await
+}
+
+async fn suppliers(app: &TestApp) -> i64 {
+    let mut conn = app.connection().await;
+    sqlx::query_scalar("SELECT count(*) FROM supplier")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap()
+}
+
+async fn keys(app: &TestApp) -> i64 {
+    let mut conn = app.connection().await;
+    sqlx::query_scalar("SELECT count(*) FROM idempotency_keys")
+        .fetch_one(&mut conn)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn retries_get_the_stored_response() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+
+    let created = create(&app, &admin, "create-acme", supplier("Acme")).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+    assert!(!created.headers.contains_key("idempotency-replayed"));
+
+    let replayed = create(&app, &admin, "create-acme", supplier("Acme")).await;
+    assert_eq!(replayed.status, StatusCode::CREATED);
+    assert_eq!(replayed.headers["idempotency-replayed"], "true");
+    assert_eq!(replayed.body, created.body);
+    assert_eq!(suppliers(&app).await, 1);
+
+    // keys belong to the user who sent them
+    let other = app.login_as_admin().await;
+    let created = create(&app, &other, "create-acme", supplier("Globex")).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+    assert_eq!(suppliers(&app).await, 2);
+}
+
+#[tokio::test]
+async fn keys_cannot_be_reused_for_another_request() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let created = create(&app, &admin, "create-acme", supplier("Acme")).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+
+    let reused = create(&app, &admin, "create-acme", supplier("Globex")).await;
+    assert_eq!(reused.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(reused.json()["error"]["code"], "IDEMPOTENCY_KEY_REUSED");
+    assert_eq!(suppliers(&app).await, 1);
+}
+
+#[tokio::test]
+async fn keys_in_progress_are_refused() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    // the same request is still running somewhere else
+    let body = supplier("Acme");
+    let running = IdempotentRequest::new(
+        admin.id,
+        "create-acme",
+        "POST",
+        "/api/suppliers",
+        body.to_string().as_bytes(),
+    );
+    let mut conn = app.connection().await;
+    sqlx::query(
+        "INSERT INTO idempotency_keys (user_id, key, method, path, fingerprint) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(running.user_id)
+    .bind(&running.key)
+    .bind(&running.method)
+    .bind(&running.path)
+    .bind(&running.fingerprint)
+    .execute(&mut conn)
+    .await
+    .unwrap();
+
+    let refused = create(&app, &admin, "create-acme", body).await;
+    assert_eq!(refused.status, StatusCode::CONFLICT, "{}", refused.text());
+    assert_eq!(refused.json()["error"]["code"], "IDEMPOTENCY_KEY_IN_USE");
+    assert_eq!(suppliers(&app).await, 0);
+}
+
+#[tokio::test]
+async fn failed_requests_free_their_key() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+
+    let invalid = create(&app, &admin, "create-acme", json!({ "name": "Acme" })).await;
+    assert_eq!(
+        invalid.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        invalid.text()
+    );
+    assert_eq!(keys(&app).await, 0);
+
+    // the retry runs again instead of replaying the error
+    let created = create(&app, &admin, "create-acme", supplier("Acme")).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+    assert!(!created.headers.contains_key("idempotency-replayed"));
+}
+
+#[tokio::test]
+async fn abandoned_requests_free_their_key() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    // holds the insert until the client gave up
+    let mut lock = app.connection().await;
+    let mut tx = lock.begin().await.unwrap();
+    sqlx::query("LOCK TABLE supplier IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/suppliers")
+        .header(header::AUTHORIZATION, format!("Bearer {}", admin.token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(KEY, "create-acme")
+        .extension(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))))
+        .body(Body::from(supplier("Acme").to_string()))
+        .unwrap();
+    let client = tokio::spawn(app.router.clone().oneshot(request));
+    while keys(&app).await == 0 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    client.abort();
+    let _ = client.await;
+    tx.rollback().await.unwrap();
+
+    for _ in 0..100 {
+        if keys(&app).await == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(keys(&app).await, 0);
+    let created = create(&app, &admin, "create-acme", supplier("Acme")).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+}
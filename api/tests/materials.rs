@@ -0,0 +1,201 @@
+mod common;
+
+use axum::http::{header, Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn admins_manage_material_groups() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+
+    let created = app
+        .post(
+            "/api/materials/groups",
+            token,
+            json!({ "name": "Metals", "sub_group": "Steel" }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+    let id = created.data()["id"].as_str().unwrap().to_string();
+
+    let updated = app
+        .request(
+            Method::PUT,
+            &format!("/api/materials/groups/{id}"),
+            token,
+            &[(header::IF_MATCH, "\"1\"")],
+            Some(json!({ "sub_group": "Stainless steel" })),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.text());
+    assert_eq!(updated.data()["sub_group_name"], "Stainless steel");
+
+    let deleted = app
+        .delete(&format!("/api/materials/groups/{id}"), token)
+        .await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    assert_eq!(
+        app.get(&format!("/api/materials/groups/{id}"), token)
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+    let trash = app.get("/api/materials/groups/deleted", token).await;
+    assert_eq!(trash.status, StatusCode::OK);
+    assert_eq!(trash.data()[0]["id"], id);
+
+    let restored = app
+        .post(
+            &format!("/api/materials/groups/{id}/restore"),
+            token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(restored.status, StatusCode::OK, "{}", restored.text());
+}
+
+#[tokio::test]
+async fn users_read_material_groups() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let user = app.login_as_user().await;
+    for (name, sub_group) in [("Metals", "Steel"), ("Metals", "Copper"), ("Wood", "Oak")] {
+        app.post(
+            "/api/materials/groups",
+            Some(&admin.token),
+            json!({ "name": name, "sub_group": sub_group }),
+        )
+        .await;
+    }
+
+    let list = app
+        .get("/api/materials/groups?sort=name", Some(&user.token))
+        .await;
+    assert_eq!(list.status, StatusCode::OK);
+    assert_eq!(list.json()["meta"]["total"], 3);
+
+    let id = list.data()[2]["id"].as_str().unwrap().to_string();
+    let found = app
+        .get(&format!("/api/materials/groups/{id}"), Some(&user.token))
+        .await;
+    assert_eq!(found.status, StatusCode::OK);
+    assert_eq!(found.data()["name"], "Wood");
+
+    let metals = app
+        .get("/api/materials/groups/sub/Metals", Some(&user.token))
+        .await;
+    assert_eq!(metals.status, StatusCode::OK);
+    let mut sub_groups: Vec<String> = metals
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|group| group["sub_group_name"].as_str().unwrap().to_string())
+        .collect();
+    sub_groups.sort();
+    assert_eq!(sub_groups, vec!["Copper", "Steel"]);
+}
+
+#[tokio::test]
+async fn admins_import_materials_users_list_them() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let user = app.login_as_user().await;
+    let supplier = app
+        .post(
+            "/api/suppliers",
+            Some(&admin.token),
+            json!({
+                "name": "Acme",
+                "email": "acme@example.com",
+                "phone": "555",
+                "address": "Harbour 7",
+            }),
+        )
+        .await
+        .data();
+    let group = app
+        .post(
+            "/api/materials/groups",
+            Some(&admin.token),
+            json!({ "name": "Metals", "sub_group": "Steel" }),
+        )
+        .await
+        .data();
+    let file = format!(
+        "name,price,description,quantity,mfg_date,exp_date,supplier_id,group_id\n\
+         Steel beam,120,Hot rolled,10,2026-01-01,2036-01-01,{},{}\n\
+         Steel rod,15,Cold drawn,200,2026-02-01,2036-02-01,{},{}\n",
+        supplier["id"].as_str().unwrap(),
+        group["id"].as_str().unwrap(),
+        supplier["id"].as_str().unwrap(),
+        "00000000-0000-4000-8000-000000000000",
+    );
+
+    let refused = app
+        .upload_csv("/api/materials/import?dry_run=false", &admin.token, &file)
+        .await;
+    assert_eq!(refused.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(refused.data()["errors"][0]["field"], "group_id");
+
+    let file = file.replace(
+        "00000000-0000-4000-8000-000000000000",
+        group["id"].as_str().unwrap(),
+    );
+    let imported = app
+        .upload_csv("/api/materials/import?dry_run=false", &admin.token, &file)
+        .await;
+    assert_eq!(imported.status, StatusCode::CREATED, "{}", imported.text());
+    assert_eq!(imported.data()["imported"], 2);
+
+    let list = app.get("/api/materials?sort=name", Some(&user.token)).await;
+    assert_eq!(list.status, StatusCode::OK);
+    assert_eq!(list.data()[0]["name"], "Steel beam");
+    assert_eq!(list.data()[0]["supplier_name"], "Acme");
+    assert_eq!(list.data()[0]["group_name"], "Metals");
+    assert_eq!(list.json()["meta"]["total"], 2);
+}
+
+#[tokio::test]
+async fn admins_change_material_groups_in_bulk() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+
+    let report = app
+        .post(
+            "/api/materials/groups/bulk",
+            token,
+            json!([
+                { "op": "create", "data": { "name": "Metals", "sub_group": "Steel" } },
+                { "op": "create", "data": { "name": "Wood", "sub_group": "Oak" } },
+            ]),
+        )
+        .await;
+    assert_eq!(report.status, StatusCode::OK, "{}", report.text());
+    let id = report.data()["results"][0]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // an invalid item refuses the whole all-or-nothing batch
+    let report = app
+        .post(
+            "/api/materials/groups/bulk",
+            token,
+            json!([
+                { "op": "delete", "id": id },
+                { "op": "create", "data": { "name": "", "sub_group": "Oak" } },
+            ]),
+        )
+        .await;
+    assert_eq!(report.data()["committed"], false, "{}", report.text());
+    assert_eq!(
+        app.get(&format!("/api/materials/groups/{id}"), token)
+            .await
+            .status,
+        StatusCode::OK
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn metrics_count_requests_by_route_and_errors_by_variant() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let missing = app
+        .get(
+            &format!("/api/materials/groups/{}", Uuid::new_v4()),
+            Some(&admin.token),
+        )
+        .await;
+    assert_eq!(missing.status, StatusCode::NOT_FOUND);
+
+    let metrics = app.get("/metrics", None).await;
+    assert_eq!(metrics.status, StatusCode::OK);
+    let text = metrics.text();
+    // the route pattern, not the id of the request
+    assert!(
+        text.contains(
+            r#"http_requests_total{method="GET",route="/api/materials/groups/:id",status="404"} 1"#
+        ),
+        "{text}"
+    );
+    assert!(
+        text.contains(r#"http_request_duration_seconds_bucket{method="GET",route="/api/materials/groups/:id","#),
+        "{text}"
+    );
+    assert!(
+        text.contains(r#"http_errors_total{variant="Lib"}"#),
+        "{text}"
+    );
+    for gauge in [
+        "db_pool_connections ",
+        "db_pool_idle_connections ",
+        "db_pool_max_connections ",
+        "db_pool_acquire_seconds ",
+        "inventory_stock_value ",
+        "inventory_materials ",
+        "inventory_low_stock_materials ",
+    ] {
+        assert!(text.contains(gauge), "{gauge} missing from {text}");
+    }
+}
+
+#[tokio::test]
+async fn metrics_can_require_a_token() {
+    let app = TestApp::with_config(|config| {
+        config.server.metrics_token = Some("scrape-secret".to_string())
+    })
+    .await;
+
+    assert_eq!(
+        app.get("/metrics", None).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    // a login token is not the metrics token
+    let admin = app.login_as_admin().await;
+    assert_eq!(
+        app.get("/metrics", Some(&admin.token)).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    let metrics = app.get("/metrics", Some("scrape-secret")).await;
+    assert_eq!(metrics.status, StatusCode::OK);
+    assert!(metrics.text().contains("inventory_stock_value "));
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use common::{TestApp, TestResponse};
+use config::{Keys, OidcConfig};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{encode, Algorithm, Header};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use url::Url;
+
+const CLIENT_ID: &str = "inventory";
+const ADMIN_GROUP: &str = "inventory-admins";
+
+// an identity provider on a local port: discovery, its key set and a token endpoint that
+// signs an id token for whoever asks with the current `signer`
+struct MockIdp {
+    issuer: String,
+    published: Mutex<JwkSet>,
+    signer: Mutex<(Header, Keys)>,
+    nonce: Mutex<String>,
+    jwks_fetches: AtomicUsize,
+}
+
+impl MockIdp {
+    async fn start(keys: Keys) -> Arc<Self> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let idp = Arc::new(Self {
+            issuer: format!("http://{}", listener.local_addr().unwrap()),
+            published: Mutex::new(keys.jwks()),
+            signer: Mutex::new((keys.header(), keys)),
+            nonce: Mutex::new(String::new()),
+            jwks_fetches: AtomicUsize::new(0),
+        });
+        let router = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(idp.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        idp
+    }
+
+    // publishes `keys` and signs the next tokens with them
+    fn rotate(&self, keys: Keys) {
+        *self.published.lock().unwrap() = keys.jwks();
+        *self.signer.lock().unwrap() = (keys.header(), keys);
+    }
+
+    fn jwks_fetches(&self) -> usize {
+        self.jwks_fetches.load(Ordering::SeqCst)
+    }
+
+    fn config(&self) -> OidcConfig {
+        OidcConfig {
+            issuer_url: self.issuer.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: None,
+            redirect_uri: "http://localhost/api/auth/oidc/callback".to_string(),
+            scopes: "openid email".to_string(),
+            groups_claim: "groups".to_string(),
+            role_mapping: vec![(ADMIN_GROUP.to_string(), "Admin".to_string())],
+            default_role: None,
+        }
+    }
+}
+
+async fn discovery(State(idp): State<Arc<MockIdp>>) -> Json<Value> {
+    Json(json!({
+        "issuer": idp.issuer,
+        "authorization_endpoint": format!("{}/authorize", idp.issuer),
+        "token_endpoint": format!("{}/token", idp.issuer),
+        "jwks_uri": format!("{}/jwks", idp.issuer),
+    }))
+}
+
+async fn jwks(State(idp): State<Arc<MockIdp>>) -> Json<JwkSet> {
+    idp.jwks_fetches.fetch_add(1, Ordering::SeqCst);
+    Json(idp.published.lock().unwrap().clone())
+}
+
+async fn token(State(idp): State<Arc<MockIdp>>) -> Json<Value> {
+    let now = chrono::Utc::now().timestamp();
+    let claims = json!({
+        "iss": idp.issuer,
+        "aud": CLIENT_ID,
+        "sub": "idp-user-1",
+        "email": "sso.user@example.com",
+        "email_verified": true,
+        "preferred_username": "sso.user",
+        "groups": [ADMIN_GROUP],
+        "nonce": *idp.nonce.lock().unwrap(),
+        "iat": now,
+        "exp": now + 300,
+    });
+    let (header, keys) = &*idp.signer.lock().unwrap();
+    let id_token = encode(header, &claims, &keys.encoding).unwrap();
+    Json(json!({ "id_token": id_token, "token_type": "Bearer" }))
+}
+
+// signing keys under `kid` from the config fixtures, throwaway keys of no real provider
+fn idp_keys(kid: &str, public: &str, private: &str) -> Keys {
+    let fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/../config/fixtures/keys");
+    let dir = TempDir::new().unwrap();
+    for (file, fixture) in [("public", public), ("private", private)] {
+        std::fs::copy(
+            format!("{fixtures}/{fixture}"),
+            dir.path().join(format!("{kid}.{file}.pem")),
+        )
+        .unwrap();
+    }
+    Keys::from_dir(dir.path(), kid).unwrap()
+}
+
+fn rsa_keys() -> Keys {
+    idp_keys("idp-rsa", "rsa-spki.public.pem", "rsa.private.pem")
+}
+
+fn ed25519_keys() -> Keys {
+    idp_keys("idp-ed25519", "ed25519.public.pem", "ed25519.private.pem")
+}
+
+async fn start(keys: Keys) -> (TestApp, Arc<MockIdp>) {
+    let idp = MockIdp::start(keys).await;
+    let oidc = idp.config();
+    let app = TestApp::with_config(|config| config.oidc = Some(oidc)).await;
+    (app, idp)
+}
+
+// the browser round trip: login redirects to the provider, which sends the user back with a code
+async fn sso_login(app: &TestApp, idp: &MockIdp) -> TestResponse {
+    let redirect = app.get("/api/auth/oidc/login", None).await;
+    assert_eq!(
+        redirect.status,
+        StatusCode::SEE_OTHER,
+        "{}",
+        redirect.text()
+    );
+    let location = redirect.headers[header::LOCATION].to_str().unwrap();
+    let url = Url::parse(location).unwrap();
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.to_string())
+            .unwrap()
+    };
+    *idp.nonce.lock().unwrap() = param("nonce");
+    app.get(
+        &format!(
+            "/api/auth/oidc/callback?code=test-code&state={}",
+            param("state")
+        ),
+        None,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn sso_login_creates_the_user_with_the_mapped_role() {
+    let (app, idp) = start(rsa_keys()).await;
+
+    let login = sso_login(&app, &idp).await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.text());
+    assert!(login.data()["token"].is_string());
+
+    let mut conn = app.connection().await;
+    let role: String = sqlx::query_scalar(
+        "SELECT r.name FROM users u JOIN roles r ON r.id = u.role_id WHERE u.email = $1",
+    )
+    .bind("sso.user@example.com")
+    .fetch_one(&mut conn)
+    .await
+    .unwrap();
+    assert_eq!(role, "Admin");
+
+    // the key set is kept between logins
+    let again = sso_login(&app, &idp).await;
+    assert_eq!(again.status, StatusCode::CREATED, "{}", again.text());
+    assert_eq!(idp.jwks_fetches(), 1);
+}
+
+#[tokio::test]
+async fn id_tokens_cannot_pick_their_algorithm() {
+    let keys = rsa_keys();
+    let (app, idp) = start(keys.clone()).await;
+    // the provider's RSA key is published for RS256, the same key over RS384 must not verify
+    let mut header = keys.header();
+    header.alg = Algorithm::RS384;
+    *idp.signer.lock().unwrap() = (header, keys);
+
+    let login = sso_login(&app, &idp).await;
+    assert_eq!(login.status, StatusCode::UNAUTHORIZED, "{}", login.text());
+}
+
+#[tokio::test]
+async fn rotated_provider_keys_are_fetched_again() {
+    let (app, idp) = start(rsa_keys()).await;
+    let login = sso_login(&app, &idp).await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.text());
+    assert_eq!(idp.jwks_fetches(), 1);
+
+    idp.rotate(ed25519_keys());
+    let login = sso_login(&app, &idp).await;
+    assert_eq!(login.status, StatusCode::CREATED, "{}", login.text());
+    assert_eq!(idp.jwks_fetches(), 2);
+}
+
+#[tokio::test]
+async fn unpublished_keys_are_rejected() {
+    let (app, idp) = start(rsa_keys()).await;
+    // signs with a key the provider never published
+    let keys = ed25519_keys();
+    *idp.signer.lock().unwrap() = (keys.header(), keys);
+
+    let login = sso_login(&app, &idp).await;
+    assert_eq!(login.status, StatusCode::UNAUTHORIZED, "{}", login.text());
+}
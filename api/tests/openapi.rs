@@ -0,0 +1,148 @@
+use api::rest::openapi::ApiDoc;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use utoipa::OpenApi;
+
+// handler module, router function and the prefix build_app mounts it under
+const MOUNTS: &[(&str, &str, &str)] = &[
+    ("auth", "auth_handler", "/api/auth"),
+    ("auth", "well_known_handler", "/.well-known"),
+    ("user", "user_handler", "/api/users"),
+    ("role", "role_handler", "/api/roles"),
+    ("material", "material_handler", "/api/materials"),
+    ("supplier", "supplier_handler", "/api/suppliers"),
+    ("audit", "audit_handler", "/api/audit-logs"),
+    ("search", "search_handler", "/api/search"),
+    ("health", "api_root_handler", "/api"),
+    ("health", "health_handler", "/health"),
+    ("metrics", "metrics_handler", "/metrics"),
+];
+// routers build_app mounts that are not part of the document
+const UNDOCUMENTED: &[&str] = &["openapi_handler"];
+
+fn read(path: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap()
+}
+
+// `.route(...)` calls of the routers in the handler modules as (method, full path)
+fn routes() -> BTreeSet<(String, String)> {
+    let router_fn = Regex::new(r"fn (\w+)\(\) -> Router \{").unwrap();
+    let path = Regex::new(r#"^\s*"([^"]*)""#).unwrap();
+    let method = Regex::new(r"\b(get|post|put|delete|patch)\(").unwrap();
+    let param = Regex::new(r":(\w+)").unwrap();
+    let mut routes = BTreeSet::new();
+    for module in MOUNTS
+        .iter()
+        .map(|(module, _, _)| *module)
+        .collect::<BTreeSet<_>>()
+    {
+        let source = read(&format!("src/rest/{module}/handler.rs"));
+        for router in router_fn.captures_iter(&source) {
+            let name = router.get(1).unwrap().as_str();
+            // private routers such as `user_handler_admin` are merged into `user_handler`
+            let (_, _, prefix) = MOUNTS
+                .iter()
+                .filter(|(m, handler, _)| *m == module && name.starts_with(handler))
+                .max_by_key(|(_, handler, _)| handler.len())
+                .unwrap_or_else(|| panic!("router `{name}` is not in MOUNTS"));
+            let start = router.get(0).unwrap().end();
+            let body = &source[start..start + source[start..].find("\n}\n").unwrap()];
+            for (i, _) in body.match_indices(".route(") {
+                let args = balanced(&body[i + ".route(".len()..]);
+                let sub_path = &path.captures(args).unwrap()[1];
+                let sub_path = param.replace_all(sub_path, "{$1}");
+                let full = match sub_path.as_ref() {
+                    "/" => prefix.to_string(),
+                    sub_path => format!("{prefix}{sub_path}"),
+                };
+                for m in method.captures_iter(args) {
+                    routes.insert((m[1].to_string(), full.clone()));
+                }
+            }
+        }
+    }
+    routes
+}
+
+// the text up to the parenthesis closing an already opened one
+fn balanced(text: &str) -> &str {
+    let mut depth = 1;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[..i];
+                }
+            }
+            _ => {}
+        }
+    }
+    panic!("unbalanced parentheses");
+}
+
+fn documented() -> BTreeSet<(String, String)> {
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .filter(|key| ["get", "post", "put", "delete", "patch"].contains(&key.as_str()))
+                .map(|method| (method.clone(), path.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[test]
+fn every_route_is_documented() {
+    let missing: Vec<_> = routes().difference(&documented()).cloned().collect();
+    assert!(
+        missing.is_empty(),
+        "routes missing from the OpenAPI spec: {missing:?}"
+    );
+}
+
+#[test]
+fn every_documented_path_is_routed() {
+    let stale: Vec<_> = documented().difference(&routes()).cloned().collect();
+    assert!(
+        stale.is_empty(),
+        "documented paths without a route: {stale:?}"
+    );
+}
+
+#[test]
+fn mounts_match_build_app() {
+    // protected routers are wrapped in `protected(...)` by build_app
+    let nest = Regex::new(r#"\.nest\(\s*"([^"]+)",\s*(?:protected\()?(\w+_handler)\(\)"#).unwrap();
+    // a merged router keeps the paths of the one it is merged into
+    let merge = Regex::new(r"\.merge\((\w+_handler)\(\)\)").unwrap();
+    let app = read("src/app.rs");
+    let mounted: Vec<_> = nest
+        .captures_iter(&app)
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .chain(
+            merge
+                .captures_iter(&app)
+                .map(|c| (String::new(), c[1].to_string())),
+        )
+        .filter(|(_, handler)| !UNDOCUMENTED.contains(&handler.as_str()))
+        .collect();
+    assert_eq!(mounted.len(), MOUNTS.len(), "build_app mounts {mounted:?}");
+    for (path, handler) in mounted {
+        assert!(
+            MOUNTS
+                .iter()
+                .any(|(_, h, prefix)| *h == handler && prefix.ends_with(&path)),
+            "`{handler}` mounted at `{path}` is not in MOUNTS"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use axum::http::{header, StatusCode};
+use common::TestApp;
+use config::RateLimit;
+use serde_json::json;
+
+const TWO_PER_MINUTE: RateLimit = RateLimit {
+    burst: 2,
+    per_minute: 1,
+};
+
+fn retry_after(response: &common::TestResponse) -> u64 {
+    response.headers[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn clients_over_the_limit_are_told_when_to_retry() {
+    let app = TestApp::with_config(|config| config.rate_limits.auth = TWO_PER_MINUTE).await;
+    let login = json!({ "email": "nobody@example.com", "password": "Wrong_password" });
+
+    for _ in 0..2 {
+        let response = app.post("/api/auth/login", None, login.clone()).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    }
+    let limited = app.post("/api/auth/login", None, login).await;
+    assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(limited.json()["error"]["code"], "TOO_MANY_REQUESTS");
+    // one request is refilled per minute
+    let wait = retry_after(&limited);
+    assert!((1..=60).contains(&wait), "{wait}");
+}
+
+#[tokio::test]
+async fn signed_in_users_are_limited_each_on_their_own() {
+    let app = TestApp::with_config(|config| config.rate_limits.users = TWO_PER_MINUTE).await;
+    let first = app.login_as_user().await;
+    let second = app.login_as_user().await;
+
+    for _ in 0..2 {
+        let info = app.get("/api/users/info", Some(&first.token)).await;
+        assert_eq!(info.status, StatusCode::OK);
+    }
+    let limited = app.get("/api/users/info", Some(&first.token)).await;
+    assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after(&limited) > 0);
+
+    // both come from the same address
+    let info = app.get("/api/users/info", Some(&second.token)).await;
+    assert_eq!(info.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn limited_requests_are_answered_before_the_session_is_checked() {
+    let app = TestApp::with_config(|config| config.rate_limits.users = TWO_PER_MINUTE).await;
+    let user = app.login_as_user().await;
+    for _ in 0..2 {
+        app.get("/api/users/info", Some(&user.token)).await;
+    }
+
+    let mut conn = app.connection().await;
+    sqlx::query("UPDATE sessions SET revoked_at = now() WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    let limited = app.get("/api/users/info", Some(&user.token)).await;
+    assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use axum::http::{header, Method, StatusCode};
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn admins_manage_roles() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+
+    let created = app
+        .post("/api/roles", token, json!({ "name": "Buyer" }))
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+    let id = created.data()["id"].as_str().unwrap().to_string();
+
+    let duplicate = app
+        .post("/api/roles", token, json!({ "name": "Buyer" }))
+        .await;
+    assert_eq!(duplicate.status, StatusCode::CONFLICT);
+
+    let list = app.get("/api/roles?sort=name", token).await;
+    assert_eq!(list.status, StatusCode::OK);
+    let names: Vec<String> = list
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|role| role["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["Admin", "Buyer", "User"]);
+
+    let found = app.get(&format!("/api/roles/{id}"), token).await;
+    assert_eq!(found.status, StatusCode::OK);
+    assert_eq!(found.headers[header::ETAG], "\"1\"");
+
+    let updated = app
+        .request(
+            Method::PUT,
+            &format!("/api/roles/{id}"),
+            token,
+            &[(header::IF_MATCH, "\"1\"")],
+            Some(json!({ "name": "Purchaser" })),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.text());
+    assert_eq!(updated.data()["name"], "Purchaser");
+
+    let deleted = app
+        .request(
+            Method::DELETE,
+            &format!("/api/roles/{id}"),
+            token,
+            &[(header::IF_MATCH, "\"1\"")],
+            None,
+        )
+        .await;
+    assert_eq!(deleted.status, StatusCode::PRECONDITION_FAILED);
+    let deleted = app.delete(&format!("/api/roles/{id}"), token).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    assert_eq!(
+        app.get(&format!("/api/roles/{id}"), token).await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    let trash = app.get("/api/roles/deleted", token).await;
+    assert_eq!(trash.status, StatusCode::OK);
+    assert_eq!(trash.data()[0]["id"], id);
+
+    let restored = app
+        .post(&format!("/api/roles/{id}/restore"), token, json!({}))
+        .await;
+    assert_eq!(restored.status, StatusCode::OK, "{}", restored.text());
+    assert_eq!(restored.data()["name"], "Purchaser");
+}
+
+#[tokio::test]
+async fn roles_in_use_are_not_deleted() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+
+    let roles = app.get("/api/roles?name=User", token).await.data();
+    let user_role = roles[0]["id"].as_str().unwrap().to_string();
+    app.create_user("User").await;
+
+    let deleted = app.delete(&format!("/api/roles/{user_role}"), token).await;
+    assert_eq!(deleted.status, StatusCode::CONFLICT, "{}", deleted.text());
+}
+
+#[tokio::test]
+async fn deleted_roles_release_their_name() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+    let created = app
+        .post("/api/roles", token, json!({ "name": "Buyer" }))
+        .await;
+    let id = created.data()["id"].as_str().unwrap().to_string();
+    app.delete(&format!("/api/roles/{id}"), token).await;
+
+    let again = app
+        .post("/api/roles", token, json!({ "name": "Buyer" }))
+        .await;
+    assert_eq!(again.status, StatusCode::CREATED, "{}", again.text());
+
+    let restored = app
+        .post(&format!("/api/roles/{id}/restore"), token, json!({}))
+        .await;
+    assert_eq!(restored.status, StatusCode::CONFLICT, "{}", restored.text());
+}
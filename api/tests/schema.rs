@@ -0,0 +1,79 @@
+mod common;
+
+use common::TestSchema;
+use lib::util::postgres::{check_schema, SchemaError, MIGRATOR};
+
+fn database_url() -> String {
+    common::test_config().database.url
+}
+
+#[tokio::test]
+async fn an_unmigrated_schema_is_behind() {
+    let schema = TestSchema::new(&database_url()).await;
+
+    match check_schema(&schema.pool).await {
+        Err(SchemaError::Behind(pending)) => {
+            let up = MIGRATOR
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+                .count();
+            assert_eq!(pending.len(), up);
+        }
+        other => panic!("expected a schema behind, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn a_migrated_schema_is_up_to_date() {
+    let schema = TestSchema::new(&database_url()).await;
+    MIGRATOR.run(&schema.pool).await.unwrap();
+
+    check_schema(&schema.pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn a_partly_migrated_schema_names_the_missing_migrations() {
+    let schema = TestSchema::new(&database_url()).await;
+    MIGRATOR.run(&schema.pool).await.unwrap();
+    let (version, description): (i64, String) = sqlx::query_as(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT max(version) FROM _sqlx_migrations) \
+         RETURNING version, description",
+    )
+    .fetch_one(&schema.pool)
+    .await
+    .unwrap();
+
+    match check_schema(&schema.pool).await {
+        Err(SchemaError::Behind(pending)) => {
+            assert_eq!(pending, vec![format!("{version} {description}")])
+        }
+        other => panic!("expected a schema behind, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn edited_and_failed_migrations_are_refused() {
+    let schema = TestSchema::new(&database_url()).await;
+    MIGRATOR.run(&schema.pool).await.unwrap();
+    let first = MIGRATOR.iter().next().unwrap().version;
+
+    sqlx::query("UPDATE _sqlx_migrations SET checksum = '\\x00' WHERE version = $1")
+        .bind(first)
+        .execute(&schema.pool)
+        .await
+        .unwrap();
+    assert!(matches!(
+        check_schema(&schema.pool).await,
+        Err(SchemaError::Modified(version)) if version == first
+    ));
+
+    sqlx::query("UPDATE _sqlx_migrations SET success = false WHERE version = $1")
+        .bind(first)
+        .execute(&schema.pool)
+        .await
+        .unwrap();
+    assert!(matches!(
+        check_schema(&schema.pool).await,
+        Err(SchemaError::Dirty(version)) if version == first
+    ));
+}
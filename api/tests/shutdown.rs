@@ -0,0 +1,73 @@
+// a test binary of its own, the drain closes the process wide task tracker
+mod common;
+
+use api::util::shutdown;
+use common::TestApp;
+use sqlx::Connection;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn running_requests_finish_during_shutdown() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (stop, signal) = oneshot::channel::<()>();
+    let server = tokio::spawn(shutdown::serve(
+        listener,
+        app.router.clone(),
+        async {
+            let _ = signal.await;
+        },
+        Duration::from_secs(10),
+    ));
+
+    // holds the listing until the shutdown started
+    let mut lock = app.connection().await;
+    let mut tx = lock.begin().await.unwrap();
+    sqlx::query("LOCK TABLE supplier IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    let mut client = TcpStream::connect(address).await.unwrap();
+    let request = format!(
+        "GET /api/suppliers HTTP/1.1\r\nHost: {address}\r\nAuthorization: Bearer {}\r\nConnection: close\r\n\r\n",
+        admin.token
+    );
+    client.write_all(request.as_bytes()).await.unwrap();
+    let mut check = app.connection().await;
+    while sqlx::query_scalar::<_, i64>("SELECT count(*) FROM pg_locks WHERE NOT granted")
+        .fetch_one(&mut check)
+        .await
+        .unwrap()
+        == 0
+    {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    stop.send(()).unwrap();
+    // new connections are refused once the shutdown started
+    let mut refused = false;
+    for _ in 0..100 {
+        if TcpStream::connect(address).await.is_err() {
+            refused = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(refused, "the listener must close on shutdown");
+    assert!(!server.is_finished());
+
+    tx.rollback().await.unwrap();
+    let mut response = String::new();
+    client.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    tokio::time::timeout(Duration::from_secs(10), server)
+        .await
+        .expect("the server must stop once the request finished")
+        .unwrap();
+}
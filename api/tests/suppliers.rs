@@ -0,0 +1,176 @@
+mod common;
+
+use axum::http::{header, Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+
+fn supplier(name: &str) -> Value {
+    json!({
+        "name": name,
+        "email": format!("{}@example.com", name.to_lowercase()),
+        "phone": "+1 555 0100",
+        "address": "Harbour 7",
+    })
+}
+
+#[tokio::test]
+async fn admins_manage_suppliers() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+
+    let created = app.post("/api/suppliers", token, supplier("Acme")).await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+    let id = created.data()["id"].as_str().unwrap().to_string();
+
+    let invalid = app
+        .post(
+            "/api/suppliers",
+            token,
+            json!({ "name": "A", "email": "no", "phone": "", "address": "" }),
+        )
+        .await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+
+    let updated = app
+        .request(
+            Method::PUT,
+            &format!("/api/suppliers/{id}"),
+            token,
+            &[(header::IF_MATCH, "\"1\"")],
+            Some(json!({ "phone": "+1 555 0199" })),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.text());
+    assert_eq!(updated.data()["phone"], "+1 555 0199");
+    assert_eq!(updated.headers[header::ETAG], "\"2\"");
+
+    let deleted = app.delete(&format!("/api/suppliers/{id}"), token).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    assert_eq!(
+        app.get(&format!("/api/suppliers/{id}"), token).await.status,
+        StatusCode::NOT_FOUND
+    );
+    let trash = app.get("/api/suppliers/deleted", token).await;
+    assert_eq!(trash.status, StatusCode::OK);
+    assert_eq!(trash.data()[0]["id"], id);
+
+    let restored = app
+        .post(&format!("/api/suppliers/{id}/restore"), token, json!({}))
+        .await;
+    assert_eq!(restored.status, StatusCode::OK, "{}", restored.text());
+}
+
+#[tokio::test]
+async fn users_read_suppliers() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let user = app.login_as_user().await;
+    for name in ["Acme", "Globex", "Initech"] {
+        app.post("/api/suppliers", Some(&admin.token), supplier(name))
+            .await;
+    }
+
+    let page = app
+        .get(
+            "/api/suppliers?sort=name&order=desc&limit=2",
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(page.status, StatusCode::OK);
+    assert_eq!(page.data()[0]["name"], "Initech");
+    assert_eq!(page.data().as_array().unwrap().len(), 2);
+    assert_eq!(page.json()["meta"]["total"], 3);
+
+    let far = app
+        .get(
+            &format!("/api/suppliers?page={}&limit=500", i64::MAX),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(far.status, StatusCode::BAD_REQUEST, "{}", far.text());
+
+    let id = page.data()[1]["id"].as_str().unwrap().to_string();
+    let found = app
+        .get(&format!("/api/suppliers/{id}"), Some(&user.token))
+        .await;
+    assert_eq!(found.status, StatusCode::OK);
+    assert_eq!(found.data()["name"], "Globex");
+    assert_eq!(found.headers[header::ETAG], "\"1\"");
+
+    let csv = app
+        .get("/api/suppliers?format=csv&sort=name", Some(&user.token))
+        .await;
+    assert_eq!(csv.status, StatusCode::OK);
+    assert!(csv.headers[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/csv"));
+    assert_eq!(csv.text().lines().count(), 4, "{}", csv.text());
+}
+
+#[tokio::test]
+async fn admins_import_suppliers() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let file = "name,email,phone,address\n\
+                Acme,acme@example.com,555,Harbour 7\n\
+                X,not-an-email,555,Harbour 8\n\
+                Globex,globex@example.com,555,Harbour 9\n";
+
+    let invalid = app
+        .upload_csv("/api/suppliers/import", &admin.token, file)
+        .await;
+    assert_eq!(invalid.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let errors = invalid.data()["errors"].clone();
+    assert_eq!(errors.as_array().unwrap().len(), 2);
+    assert_eq!(errors[0]["row"], 3);
+
+    let valid = "name,email,phone,address\n\
+                 Acme,acme@example.com,555,Harbour 7\n\
+                 Globex,globex@example.com,555,Harbour 9\n";
+    let dry_run = app
+        .upload_csv("/api/suppliers/import", &admin.token, valid)
+        .await;
+    assert_eq!(dry_run.status, StatusCode::OK, "{}", dry_run.text());
+    assert_eq!(dry_run.data()["dry_run"], true);
+    assert_eq!(dry_run.data()["imported"], 0);
+
+    let imported = app
+        .upload_csv("/api/suppliers/import?dry_run=false", &admin.token, valid)
+        .await;
+    assert_eq!(imported.status, StatusCode::CREATED, "{}", imported.text());
+    assert_eq!(imported.data()["imported"], 2);
+    let list = app.get("/api/suppliers", Some(&admin.token)).await;
+    assert_eq!(list.json()["meta"]["total"], 2);
+}
+
+#[tokio::test]
+async fn admins_change_suppliers_in_bulk() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+    let existing = app.post("/api/suppliers", token, supplier("Acme")).await;
+    let id = existing.data()["id"].as_str().unwrap().to_string();
+
+    let report = app
+        .post(
+            "/api/suppliers/bulk?mode=best_effort",
+            token,
+            json!([
+                { "op": "create", "data": supplier("Globex") },
+                { "op": "update", "id": id, "version": 5, "data": { "phone": "1" } },
+                { "op": "delete", "id": id },
+            ]),
+        )
+        .await;
+    assert_eq!(report.status, StatusCode::MULTI_STATUS, "{}", report.text());
+    let report = report.data();
+    assert_eq!(report["committed"], true);
+    assert_eq!(report["succeeded"], 2);
+    assert_eq!(report["results"][1]["status_code"], 412);
+    assert_eq!(
+        app.get(&format!("/api/suppliers/{id}"), token).await.status,
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use axum::http::{header, Method, StatusCode};
+use common::TestApp;
+use sqlx::{Connection, PgConnection, Postgres, Transaction};
+use std::time::Duration;
+
+async fn lock_suppliers(connection: &mut PgConnection) -> Transaction<'_, Postgres> {
+    let mut tx = connection.begin().await.unwrap();
+    sqlx::query("LOCK TABLE supplier IN ACCESS EXCLUSIVE MODE")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    tx
+}
+
+#[tokio::test]
+async fn slow_requests_time_out() {
+    let app = TestApp::with_config(|config| config.server.request_timeout_seconds = 1).await;
+    let admin = app.login_as_admin().await;
+    let mut connection = app.connection().await;
+    let lock = lock_suppliers(&mut connection).await;
+
+    let response = app.get("/api/suppliers", Some(&admin.token)).await;
+    lock.rollback().await.unwrap();
+    assert_eq!(response.status, StatusCode::REQUEST_TIMEOUT);
+    assert_eq!(response.json()["error"]["code"], "REQUEST_TIMEOUT");
+
+    let response = app.get("/api/suppliers", Some(&admin.token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+}
+
+#[tokio::test]
+async fn exports_are_not_timed_out() {
+    let app = TestApp::with_config(|config| config.server.request_timeout_seconds = 1).await;
+    let admin = app.login_as_admin().await;
+    let mut connection = app.connection().await;
+    let lock = lock_suppliers(&mut connection).await;
+
+    let token = admin.token.clone();
+    let accept = [(header::ACCEPT, "text/csv")];
+    let xlsx = app.get("/api/suppliers?format=xlsx", Some(&token));
+    let csv = app.request(Method::GET, "/api/suppliers", Some(&token), &accept, None);
+    let release = async {
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        lock.rollback().await.unwrap();
+    };
+    let (xlsx, csv, ()) = tokio::join!(xlsx, csv, release);
+    assert_eq!(xlsx.status, StatusCode::OK, "{}", xlsx.text());
+    assert_eq!(csv.status, StatusCode::OK, "{}", csv.text());
+}
@@ -0,0 +1,371 @@
+mod common;
+
+use axum::http::{header, Method, StatusCode};
+use chrono::{DateTime, Utc};
+use common::{TestApp, PASSWORD};
+use serde_json::{json, Value};
+
+fn ids(list: &Value) -> Vec<String> {
+    list.as_array()
+        .expect("a list")
+        .iter()
+        .map(|item| item["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn users_manage_their_own_account() {
+    let app = TestApp::new().await;
+    let user = app.login_as_user().await;
+    let token = user.token.as_str();
+
+    let info = app.get("/api/users/info", Some(token)).await;
+    assert_eq!(info.status, StatusCode::OK);
+    assert_eq!(info.data()["user_info"]["sub"], user.id.to_string());
+
+    let updated = app
+        .put(
+            "/api/users",
+            Some(token),
+            json!({ "address": "Other Street 3" }),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.text());
+    assert_eq!(updated.data()["address"], "Other Street 3");
+    assert_eq!(updated.data()["role_name"], "User");
+
+    let deleted = app.delete("/api/users", Some(token)).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    // a deleted account can neither use its token nor log in again
+    assert_eq!(
+        app.get("/api/users/info", Some(token)).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    let login = app
+        .post(
+            "/api/auth/login",
+            None,
+            json!({ "email": user.email, "password": PASSWORD }),
+        )
+        .await;
+    assert_eq!(login.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn users_revoke_their_own_sessions() {
+    let app = TestApp::new().await;
+    let user = app.login_as_user().await;
+    let second = app.login(&user.email).await;
+    let third = app.login(&user.email).await;
+
+    let sessions = app.get("/api/users/sessions", Some(&user.token)).await;
+    assert_eq!(sessions.status, StatusCode::OK);
+    let sessions = sessions.data();
+    assert_eq!(sessions.as_array().unwrap().len(), 3);
+    let current: Vec<&Value> = sessions
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|session| session["current"] == true)
+        .collect();
+    assert_eq!(current.len(), 1);
+
+    // revoking the second login's session from the third one
+    let second_info = app.get("/api/users/info", Some(&second)).await.data();
+    let second_sid = second_info["user_info"]["sid"].as_str().unwrap();
+    let revoked = app
+        .delete(&format!("/api/users/sessions/{second_sid}"), Some(&third))
+        .await;
+    assert_eq!(revoked.status, StatusCode::OK);
+    assert_eq!(
+        app.get("/api/users/info", Some(&second)).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let revoked = app.delete("/api/users/sessions", Some(&third)).await;
+    assert_eq!(revoked.status, StatusCode::OK);
+    for token in [&user.token, &third] {
+        assert_eq!(
+            app.get("/api/users/info", Some(token)).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
+
+async fn last_active(conn: &mut sqlx::PgConnection, user_id: uuid::Uuid) -> DateTime<Utc> {
+    sqlx::query_scalar("SELECT last_active_at FROM sessions WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(conn)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn session_activity_is_written_at_most_once_a_minute() {
+    let app = TestApp::new().await;
+    let user = app.login_as_user().await;
+    let mut conn = app.connection().await;
+    let before = last_active(&mut conn, user.id).await;
+    let info = app.get("/api/users/info", Some(&user.token)).await;
+    assert_eq!(info.status, StatusCode::OK);
+    assert_eq!(last_active(&mut conn, user.id).await, before);
+
+    sqlx::query(
+        "UPDATE sessions SET last_active_at = now() - interval '2 minutes' WHERE user_id = $1",
+    )
+    .bind(user.id)
+    .execute(&mut conn)
+    .await
+    .unwrap();
+    let stale = last_active(&mut conn, user.id).await;
+    let info = app.get("/api/users/info", Some(&user.token)).await;
+    assert_eq!(info.status, StatusCode::OK);
+    assert!(last_active(&mut conn, user.id).await > stale);
+}
+
+#[tokio::test]
+async fn admins_manage_users() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+
+    let created = app
+        .post(
+            "/api/users",
+            token,
+            json!({
+                "name": "dave",
+                "email": "dave@example.com",
+                "password": PASSWORD,
+                "address": "Dock Road 4",
+                "role": "User",
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED, "{}", created.text());
+    let id = created.data()["id"].as_str().unwrap().to_string();
+
+    let list = app.get("/api/users?q=dave", token).await;
+    assert_eq!(list.status, StatusCode::OK);
+    assert_eq!(ids(&list.data()), vec![id.clone()]);
+    assert_eq!(list.json()["meta"]["total"], 1);
+
+    let found = app.get(&format!("/api/users/{id}"), token).await;
+    assert_eq!(found.status, StatusCode::OK);
+    assert_eq!(found.headers[header::ETAG], "\"1\"");
+
+    let stale = app
+        .request(
+            Method::PUT,
+            &format!("/api/users/{id}"),
+            token,
+            &[(header::IF_MATCH, "\"7\"")],
+            Some(json!({ "role": "Admin" })),
+        )
+        .await;
+    assert_eq!(stale.status, StatusCode::PRECONDITION_FAILED);
+    let updated = app
+        .request(
+            Method::PUT,
+            &format!("/api/users/{id}"),
+            token,
+            &[(header::IF_MATCH, "\"1\"")],
+            Some(json!({ "role": "Admin" })),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK, "{}", updated.text());
+    assert_eq!(updated.data()["role_name"], "Admin");
+    assert_eq!(updated.headers[header::ETAG], "\"2\"");
+
+    let deleted = app.delete(&format!("/api/users/{id}"), token).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    assert_eq!(
+        app.get(&format!("/api/users/{id}"), token).await.status,
+        StatusCode::NOT_FOUND
+    );
+    let trash = app.get("/api/users/deleted", token).await;
+    assert_eq!(trash.status, StatusCode::OK);
+    assert_eq!(ids(&trash.data()), vec![id.clone()]);
+    assert_eq!(trash.data()[0]["deleted_by"], admin.id.to_string());
+
+    let restored = app
+        .post(&format!("/api/users/{id}/restore"), token, json!({}))
+        .await;
+    assert_eq!(restored.status, StatusCode::OK, "{}", restored.text());
+    assert_eq!(
+        app.get(&format!("/api/users/{id}"), token).await.status,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn admins_manage_sessions_and_lockouts() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+    let user = app.login_as_user().await;
+
+    let sessions = app
+        .get(&format!("/api/users/{}/sessions", user.id), token)
+        .await;
+    assert_eq!(sessions.status, StatusCode::OK);
+    assert_eq!(sessions.data().as_array().unwrap().len(), 1);
+    let revoked = app
+        .delete(&format!("/api/users/{}/sessions", user.id), token)
+        .await;
+    assert_eq!(revoked.status, StatusCode::OK);
+    assert_eq!(
+        app.get("/api/users/info", Some(&user.token)).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+
+    for _ in 0..app.config.login_policy.max_attempts {
+        app.post(
+            "/api/auth/login",
+            None,
+            json!({ "email": user.email, "password": "Wrong_password" }),
+        )
+        .await;
+    }
+    let locked = app
+        .post(
+            "/api/auth/login",
+            None,
+            json!({ "email": user.email, "password": PASSWORD }),
+        )
+        .await;
+    assert_eq!(locked.status, StatusCode::UNAUTHORIZED, "{}", locked.text());
+    // a locked account answers exactly like an email nobody registered
+    let unknown = app
+        .post(
+            "/api/auth/login",
+            None,
+            json!({ "email": "nobody@example.com", "password": PASSWORD }),
+        )
+        .await;
+    assert_eq!(unknown.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(unknown.json()["error"], {
+        let mut error = locked.json()["error"].clone();
+        error["request_id"] = unknown.json()["error"]["request_id"].clone();
+        error
+    });
+    assert_eq!(
+        unknown.json()["status_message"],
+        locked.json()["status_message"]
+    );
+
+    let events = app
+        .get(
+            &format!("/api/users/login-events?user_id={}", user.id),
+            token,
+        )
+        .await;
+    assert_eq!(events.status, StatusCode::OK);
+    let failures = events
+        .data()
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|event| event["success"] == false)
+        .count();
+    assert!(failures >= app.config.login_policy.max_attempts as usize);
+
+    let unlocked = app
+        .post(&format!("/api/users/{}/unlock", user.id), token, json!({}))
+        .await;
+    assert_eq!(unlocked.status, StatusCode::OK);
+    app.login(&user.email).await;
+}
+
+#[tokio::test]
+async fn admins_change_users_in_bulk() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+    let user = app.login_as_user().await;
+
+    let report = app
+        .post(
+            "/api/users/bulk",
+            token,
+            json!([
+                { "op": "create", "data": {
+                    "name": "erin", "email": "erin@example.com", "password": PASSWORD } },
+                { "op": "update", "id": user.id, "version": 1,
+                  "data": { "address": "Bulk Lane 5" } },
+            ]),
+        )
+        .await;
+    assert_eq!(report.status, StatusCode::OK, "{}", report.text());
+    assert_eq!(report.data()["committed"], true);
+    assert_eq!(report.data()["succeeded"], 2);
+
+    // all or nothing: one failing item keeps the others from being written
+    let report = app
+        .post(
+            "/api/users/bulk",
+            token,
+            json!([
+                { "op": "update", "id": user.id, "data": { "address": "Never Written 6" } },
+                { "op": "delete", "id": "00000000-0000-4000-8000-000000000000" },
+            ]),
+        )
+        .await;
+    assert_eq!(report.data()["committed"], false, "{}", report.text());
+    let found = app.get(&format!("/api/users/{}", user.id), token).await;
+    assert_eq!(found.data()["address"], "Bulk Lane 5");
+}
+
+#[tokio::test]
+async fn user_batches_are_kept_small() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    // each create hashes a password
+    let items: Vec<Value> = (0..101)
+        .map(|i| {
+            json!({ "op": "create", "data": {
+                "name": format!("bulk{i}"), "email": format!("bulk{i}@example.com"),
+                "password": PASSWORD } })
+        })
+        .collect();
+    let report = app
+        .post("/api/users/bulk", Some(&admin.token), json!(items))
+        .await;
+    assert_eq!(report.status, StatusCode::BAD_REQUEST, "{}", report.text());
+}
+
+#[tokio::test]
+async fn deleted_users_release_their_email() {
+    let app = TestApp::new().await;
+    let admin = app.login_as_admin().await;
+    let token = Some(admin.token.as_str());
+    let (id, _, email) = app.create_user("User").await;
+    let deleted = app.delete(&format!("/api/users/{id}"), token).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+
+    let registered = app
+        .post(
+            "/api/auth/register",
+            None,
+            json!({
+                "name": "newcomer",
+                "email": email,
+                "password": PASSWORD,
+                "address": "Dock Road 4",
+            }),
+        )
+        .await;
+    assert_eq!(
+        registered.status,
+        StatusCode::CREATED,
+        "{}",
+        registered.text()
+    );
+
+    // the new account keeps the email, the old one cannot come back with it
+    let restored = app
+        .post(&format!("/api/users/{id}/restore"), token, json!({}))
+        .await;
+    assert_eq!(restored.status, StatusCode::CONFLICT, "{}", restored.text());
+    assert_eq!(restored.json()["error"]["errors"][0]["field"], "email");
+}
@@ -0,0 +1,407 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use simple_asn1::ASN1Block;
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+const MIN_SECRET_LENGTH: usize = 32;
+const RSA_OID: [u64; 7] = [1, 2, 840, 113549, 1, 1, 1];
+const ED25519_OID: [u64; 4] = [1, 3, 101, 112];
+
+#[derive(Error, Debug)]
+pub enum KeyError {
+    #[error("auth.jwt_secret (JWT_SECRET) or auth.jwt_keys_dir (JWT_KEYS_DIR) must be set")]
+    Missing,
+    #[error("auth.jwt_secret (JWT_SECRET) must be at least {MIN_SECRET_LENGTH} bytes long")]
+    WeakSecret,
+    #[error("auth.jwt_signing_kid (JWT_SIGNING_KID) must be set when a key directory is used")]
+    MissingSigningKid,
+    #[error("no public key found for signing kid '{0}'")]
+    UnknownSigningKid(String),
+    #[error("key file '{0}' could not be read: {1}")]
+    Io(String, std::io::Error),
+    #[error("key file '{0}' is not a supported RSA or Ed25519 PEM key")]
+    InvalidKey(String),
+    #[error("private key of kid '{0}' does not match its public key")]
+    KeyMismatch(String),
+}
+
+#[derive(Clone)]
+pub struct VerificationKey {
+    pub kid: Option<String>,
+    pub algorithm: Algorithm,
+    pub decoding: DecodingKey,
+    // only asymmetric keys are published
+    pub jwk: Option<Jwk>,
+}
+
+#[derive(Clone)]
+pub struct Keys {
+    pub algorithm: Algorithm,
+    pub kid: Option<String>,
+    pub encoding: EncodingKey,
+    pub decoding: DecodingKey,
+    verification_keys: Vec<VerificationKey>,
+}
+
+impl Keys {
+    pub fn new(secret: &[u8]) -> Self {
+        let decoding = DecodingKey::from_secret(secret);
+        Self {
+            algorithm: Algorithm::HS256,
+            kid: None,
+            encoding: EncodingKey::from_secret(secret),
+            decoding: decoding.clone(),
+            verification_keys: vec![VerificationKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                decoding,
+                jwk: None,
+            }],
+        }
+    }
+
+    // the key directory holds `{kid}.public.pem` files for every key accepted for verification
+    // and `{kid}.private.pem` for the key named by `signing_kid`. Rotating means adding a new
+    // pair, switching the signing kid and removing the old public key once its tokens expired.
+    // A key directory takes precedence over a secret.
+    pub fn from_settings(
+        secret: Option<&str>,
+        dir: Option<&Path>,
+        signing_kid: Option<&str>,
+    ) -> Result<Self, KeyError> {
+        if let Some(dir) = dir {
+            let signing_kid = signing_kid.ok_or(KeyError::MissingSigningKid)?;
+            return Self::from_dir(dir, signing_kid);
+        }
+        let secret = secret.ok_or(KeyError::Missing)?;
+        if secret.len() < MIN_SECRET_LENGTH {
+            return Err(KeyError::WeakSecret);
+        }
+        Ok(Self::new(secret.as_bytes()))
+    }
+
+    pub fn from_dir(dir: &Path, signing_kid: &str) -> Result<Self, KeyError> {
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| KeyError::Io(dir.display().to_string(), e))?;
+        let mut verification_keys = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| KeyError::Io(dir.display().to_string(), e))?
+                .path();
+            let file_name = path.file_name().and_then(|name| name.to_str());
+            if let Some(kid) = file_name.and_then(|name| name.strip_suffix(".public.pem")) {
+                verification_keys.push(load_public_key(&path, kid)?);
+            }
+        }
+        verification_keys.sort_by(|a, b| a.kid.cmp(&b.kid));
+
+        let signing_key = verification_keys
+            .iter()
+            .find(|key| key.kid.as_deref() == Some(signing_kid))
+            .ok_or_else(|| KeyError::UnknownSigningKid(signing_kid.to_string()))?;
+        let private_path = dir.join(format!("{signing_kid}.private.pem"));
+        let private_pem = read_file(&private_path)?;
+        let encoding = match signing_key.algorithm {
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(&private_pem),
+            _ => EncodingKey::from_rsa_pem(&private_pem),
+        }
+        .map_err(|_| KeyError::InvalidKey(private_path.display().to_string()))?;
+
+        let keys = Self {
+            algorithm: signing_key.algorithm,
+            kid: Some(signing_kid.to_string()),
+            encoding,
+            decoding: signing_key.decoding.clone(),
+            verification_keys,
+        };
+        keys.check_signing_key()?;
+        Ok(keys)
+    }
+
+    pub fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+        header.kid = self.kid.clone();
+        header
+    }
+
+    pub fn find(&self, kid: Option<&str>) -> Option<&VerificationKey> {
+        self.verification_keys
+            .iter()
+            .find(|key| key.kid.as_deref() == kid)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .verification_keys
+                .iter()
+                .filter_map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+
+    fn check_signing_key(&self) -> Result<(), KeyError> {
+        let kid = self.kid.clone().unwrap_or_default();
+        let claims = HashMap::from([("sub", "key-check")]);
+        let token = encode(&self.header(), &claims, &self.encoding)
+            .map_err(|_| KeyError::KeyMismatch(kid.clone()))?;
+        let mut validation = Validation::new(self.algorithm);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        decode::<HashMap<String, String>>(&token, &self.decoding, &validation)
+            .map(|_| ())
+            .map_err(|_| KeyError::KeyMismatch(kid))
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, KeyError> {
+    std::fs::read(path).map_err(|e| KeyError::Io(path.display().to_string(), e))
+}
+
+fn load_public_key(path: &Path, kid: &str) -> Result<VerificationKey, KeyError> {
+    let invalid = || KeyError::InvalidKey(path.display().to_string());
+    let pem = pem::parse(read_file(path)?).map_err(|_| invalid())?;
+    let blocks = simple_asn1::from_der(pem.contents()).map_err(|_| invalid())?;
+
+    let (algorithm, params) = match (pem.tag(), blocks.as_slice()) {
+        ("RSA PUBLIC KEY", [rsa_key]) => (Algorithm::RS256, rsa_parameters(rsa_key)),
+        ("PUBLIC KEY", [ASN1Block::Sequence(_, spki)]) => match spki.as_slice() {
+            [ASN1Block::Sequence(_, algorithm), ASN1Block::BitString(_, _, key)] => {
+                let oid = match algorithm.first() {
+                    Some(ASN1Block::ObjectIdentifier(_, oid)) => {
+                        oid.as_vec::<u64>().map_err(|_| invalid())?
+                    }
+                    _ => return Err(invalid()),
+                };
+                if oid == RSA_OID {
+                    let rsa_key = simple_asn1::from_der(key).map_err(|_| invalid())?;
+                    (Algorithm::RS256, rsa_key.first().and_then(rsa_parameters))
+                } else if oid == ED25519_OID {
+                    (
+                        Algorithm::EdDSA,
+                        Some(AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                            key_type: OctetKeyPairType::OctetKeyPair,
+                            curve: EllipticCurve::Ed25519,
+                            x: URL_SAFE_NO_PAD.encode(key),
+                        })),
+                    )
+                } else {
+                    return Err(invalid());
+                }
+            }
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+    let params = params.ok_or_else(invalid)?;
+
+    let decoding = match &params {
+        AlgorithmParameters::RSA(rsa) => DecodingKey::from_rsa_components(&rsa.n, &rsa.e),
+        AlgorithmParameters::OctetKeyPair(ed) => DecodingKey::from_ed_components(&ed.x),
+        _ => return Err(invalid()),
+    }
+    .map_err(|_| invalid())?;
+
+    Ok(VerificationKey {
+        kid: Some(kid.to_string()),
+        algorithm,
+        decoding,
+        jwk: Some(Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(match algorithm {
+                    Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+                    _ => KeyAlgorithm::RS256,
+                }),
+                key_id: Some(kid.to_string()),
+                ..Default::default()
+            },
+            algorithm: params,
+        }),
+    })
+}
+
+fn rsa_parameters(block: &ASN1Block) -> Option<AlgorithmParameters> {
+    match block {
+        ASN1Block::Sequence(_, values) => match values.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => {
+                Some(AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(n.to_biguint()?.to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(e.to_biguint()?.to_bytes_be()),
+                }))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// keys stay out of logs, which key signs is enough to tell configurations apart
+impl std::fmt::Debug for Keys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keys")
+            .field("algorithm", &self.algorithm)
+            .field("kid", &self.kid)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // throwaway keys made with openssl for these tests only, never use them to sign real tokens
+    const RSA_PRIVATE: &str = include_str!("../fixtures/keys/rsa.private.pem");
+    const RSA_PKCS1_PUBLIC: &str = include_str!("../fixtures/keys/rsa-pkcs1.public.pem");
+    const RSA_SPKI_PUBLIC: &str = include_str!("../fixtures/keys/rsa-spki.public.pem");
+    const OTHER_RSA_PRIVATE: &str = include_str!("../fixtures/keys/other-rsa.private.pem");
+    const ED25519_PRIVATE: &str = include_str!("../fixtures/keys/ed25519.private.pem");
+    const ED25519_PUBLIC: &str = include_str!("../fixtures/keys/ed25519.public.pem");
+    const EC_P256_PUBLIC: &str = include_str!("../fixtures/keys/ec-p256.public.pem");
+
+    // a key directory holding `files` as (file name, contents)
+    fn key_dir(files: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in files {
+            std::fs::write(dir.path().join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    fn signs_and_verifies(keys: &Keys) {
+        let claims = HashMap::from([("sub", "alice")]);
+        let token = encode(&keys.header(), &claims, &keys.encoding).unwrap();
+        let header = jsonwebtoken::decode_header(&token).unwrap();
+        let key = keys.find(header.kid.as_deref()).unwrap();
+        let mut validation = Validation::new(key.algorithm);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        let decoded = decode::<HashMap<String, String>>(&token, &key.decoding, &validation);
+        assert_eq!(decoded.unwrap().claims["sub"], "alice");
+    }
+
+    #[test]
+    fn rsa_pkcs1_public_keys_load() {
+        let dir = key_dir(&[
+            ("a.public.pem", RSA_PKCS1_PUBLIC),
+            ("a.private.pem", RSA_PRIVATE),
+        ]);
+        let keys = Keys::from_dir(dir.path(), "a").unwrap();
+        assert_eq!(keys.algorithm, Algorithm::RS256);
+        assert_eq!(keys.kid.as_deref(), Some("a"));
+        signs_and_verifies(&keys);
+
+        let jwks = keys.jwks();
+        assert_eq!(jwks.keys.len(), 1);
+        assert_eq!(jwks.keys[0].common.key_id.as_deref(), Some("a"));
+        match &jwks.keys[0].algorithm {
+            // 65537
+            AlgorithmParameters::RSA(rsa) => assert_eq!(rsa.e, "AQAB"),
+            other => panic!("expected RSA parameters, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn spki_rsa_public_keys_match_pkcs1() {
+        let pkcs1 = key_dir(&[
+            ("a.public.pem", RSA_PKCS1_PUBLIC),
+            ("a.private.pem", RSA_PRIVATE),
+        ]);
+        let spki = key_dir(&[
+            ("a.public.pem", RSA_SPKI_PUBLIC),
+            ("a.private.pem", RSA_PRIVATE),
+        ]);
+        let spki_keys = Keys::from_dir(spki.path(), "a").unwrap();
+        signs_and_verifies(&spki_keys);
+        // both encodings of the same key publish the same modulus
+        let pkcs1_jwks = Keys::from_dir(pkcs1.path(), "a").unwrap().jwks();
+        assert_eq!(
+            spki_keys.jwks().keys[0].algorithm,
+            pkcs1_jwks.keys[0].algorithm
+        );
+    }
+
+    #[test]
+    fn ed25519_keys_load() {
+        let dir = key_dir(&[
+            ("ed.public.pem", ED25519_PUBLIC),
+            ("ed.private.pem", ED25519_PRIVATE),
+        ]);
+        let keys = Keys::from_dir(dir.path(), "ed").unwrap();
+        assert_eq!(keys.algorithm, Algorithm::EdDSA);
+        signs_and_verifies(&keys);
+        assert!(matches!(
+            keys.jwks().keys[0].algorithm,
+            AlgorithmParameters::OctetKeyPair(_)
+        ));
+    }
+
+    #[test]
+    fn older_public_keys_still_verify() {
+        let dir = key_dir(&[
+            ("new.public.pem", ED25519_PUBLIC),
+            ("new.private.pem", ED25519_PRIVATE),
+            ("old.public.pem", RSA_SPKI_PUBLIC),
+        ]);
+        let keys = Keys::from_dir(dir.path(), "new").unwrap();
+        assert_eq!(keys.jwks().keys.len(), 2);
+        assert_eq!(keys.find(Some("old")).unwrap().algorithm, Algorithm::RS256);
+    }
+
+    #[test]
+    fn ec_keys_are_rejected() {
+        let dir = key_dir(&[
+            ("ec.public.pem", EC_P256_PUBLIC),
+            ("ed.public.pem", ED25519_PUBLIC),
+            ("ed.private.pem", ED25519_PRIVATE),
+        ]);
+        let err = Keys::from_dir(dir.path(), "ed").err().unwrap();
+        assert!(matches!(err, KeyError::InvalidKey(path) if path.ends_with("ec.public.pem")));
+    }
+
+    #[test]
+    fn mismatched_pairs_are_rejected() {
+        let dir = key_dir(&[
+            ("a.public.pem", RSA_SPKI_PUBLIC),
+            ("a.private.pem", OTHER_RSA_PRIVATE),
+        ]);
+        let err = Keys::from_dir(dir.path(), "a").err().unwrap();
+        assert!(matches!(err, KeyError::KeyMismatch(kid) if kid == "a"));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for garbage in [
+            "not a key",
+            "-----BEGIN PUBLIC KEY-----\nbm90IGRlcg==\n-----END PUBLIC KEY-----\n",
+            "-----BEGIN CERTIFICATE-----\nbm90IGRlcg==\n-----END CERTIFICATE-----\n",
+        ] {
+            let dir = key_dir(&[("a.public.pem", garbage), ("a.private.pem", RSA_PRIVATE)]);
+            let err = Keys::from_dir(dir.path(), "a").err().unwrap();
+            assert!(matches!(err, KeyError::InvalidKey(_)), "{garbage}: {err}");
+        }
+
+        let dir = key_dir(&[
+            ("a.public.pem", RSA_SPKI_PUBLIC),
+            ("a.private.pem", "not a key"),
+        ]);
+        let err = Keys::from_dir(dir.path(), "a").err().unwrap();
+        assert!(matches!(err, KeyError::InvalidKey(path) if path.ends_with("a.private.pem")));
+    }
+
+    #[test]
+    fn the_signing_kid_needs_a_public_key() {
+        let dir = key_dir(&[("a.public.pem", RSA_SPKI_PUBLIC)]);
+        let err = Keys::from_dir(dir.path(), "b").err().unwrap();
+        assert!(matches!(err, KeyError::UnknownSigningKid(kid) if kid == "b"));
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+// settings are looked up in the environment first, then in the config file by their dotted path.
+// Problems are collected instead of returned so startup reports all of them at once.
+pub(crate) struct Source {
+    file: Table,
+    used: HashSet<String>,
+    pub(crate) errors: Vec<String>,
+}
+
+impl Source {
+    pub(crate) fn new(file: Table) -> Self {
+        Self {
+            file,
+            used: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub(crate) fn value<T: FromStr>(&mut self, key: &str, env: &str) -> Option<T> {
+        let raw = self.raw(key, env)?;
+        match raw.parse::<T>() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.errors
+                    .push(format!("{key} ({env}): invalid value '{raw}'"));
+                None
+            }
+        }
+    }
+
+    pub(crate) fn get<T: FromStr>(&mut self, key: &str, env: &str, default: T) -> T {
+        self.value(key, env).unwrap_or(default)
+    }
+
+    // a comma separated env var or an array of strings in the file
+    pub(crate) fn list(&mut self, key: &str, env: &str) -> Option<Vec<String>> {
+        self.used.insert(key.to_string());
+        if let Ok(value) = std::env::var(env) {
+            return Some(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(String::from)
+                    .collect(),
+            );
+        }
+        match self.lookup(key)? {
+            Value::Array(items) => {
+                let items: Option<Vec<String>> = items
+                    .iter()
+                    .map(|item| item.as_str().map(String::from))
+                    .collect();
+                if items.is_none() {
+                    self.errors
+                        .push(format!("{key} ({env}): expected an array of strings"));
+                }
+                items
+            }
+            _ => {
+                self.errors
+                    .push(format!("{key} ({env}): expected an array of strings"));
+                None
+            }
+        }
+    }
+
+    pub(crate) fn check(&mut self, valid: bool, message: impl Into<String>) {
+        if !valid {
+            self.errors.push(message.into());
+        }
+    }
+
+    // settings in the file nothing asked for are most likely typos
+    pub(crate) fn unknown_keys(&self) -> Vec<String> {
+        let mut unknown = Vec::new();
+        collect_unknown(&self.file, "", &self.used, &mut unknown);
+        unknown
+    }
+
+    fn raw(&mut self, key: &str, env: &str) -> Option<String> {
+        self.used.insert(key.to_string());
+        if let Ok(value) = std::env::var(env) {
+            return Some(value);
+        }
+        match self.lookup(key)? {
+            Value::String(value) => Some(value.clone()),
+            Value::Integer(value) => Some(value.to_string()),
+            Value::Float(value) => Some(value.to_string()),
+            Value::Boolean(value) => Some(value.to_string()),
+            _ => {
+                self.errors.push(format!("{key} ({env}): expected a value"));
+                None
+            }
+        }
+    }
+
+    fn lookup(&self, key: &str) -> Option<&Value> {
+        let (tables, name) = key.rsplit_once('.').unwrap_or(("", key));
+        let mut table = &self.file;
+        for part in tables.split('.').filter(|part| !part.is_empty()) {
+            table = table.get(part)?.as_table()?;
+        }
+        table.get(name)
+    }
+}
+
+fn collect_unknown(table: &Table, prefix: &str, used: &HashSet<String>, unknown: &mut Vec<String>) {
+    for (name, value) in table {
+        let key = format!("{prefix}{name}");
+        match value {
+            Value::Table(table) if !used.contains(&key) => {
+                collect_unknown(table, &format!("{key}."), used, unknown)
+            }
+            _ if !used.contains(&key) => unknown.push(key),
+            _ => {}
+        }
+    }
+}
+
+// the file named by CONFIG_FILE, or config.toml in the working directory when it exists
+pub(crate) fn config_file() -> Option<PathBuf> {
+    match std::env::var("CONFIG_FILE") {
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(_) => Some(PathBuf::from("config.toml")).filter(|path| path.exists()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(file: &str) -> Source {
+        Source::new(file.parse::<Table>().unwrap())
+    }
+
+    #[test]
+    fn file_values_fall_back_to_defaults() {
+        let mut source = source("[server]\nport = 8080\nproblem_json = true\n");
+        assert_eq!(
+            source.get("server.port", "SOURCE_TEST_UNSET_PORT", 3000),
+            8080
+        );
+        assert!(source.get("server.problem_json", "SOURCE_TEST_UNSET_PROBLEM", false));
+        assert_eq!(
+            source.get("server.missing", "SOURCE_TEST_UNSET_MISSING", 7),
+            7
+        );
+        assert!(source.errors.is_empty());
+        assert!(source.unknown_keys().is_empty());
+    }
+
+    #[test]
+    fn env_overrides_the_file() {
+        std::env::set_var("SOURCE_TEST_POOL_SIZE", "25");
+        std::env::set_var(
+            "SOURCE_TEST_ORIGINS",
+            "https://a.example, https://b.example",
+        );
+        let mut source = source(
+            "[database]\npool_size = 5\n[cors]\nallowed_origins = [\"https://c.example\"]\n",
+        );
+        assert_eq!(
+            source.get("database.pool_size", "SOURCE_TEST_POOL_SIZE", 10),
+            25
+        );
+        assert_eq!(
+            source.list("cors.allowed_origins", "SOURCE_TEST_ORIGINS"),
+            Some(vec![
+                "https://a.example".to_string(),
+                "https://b.example".to_string()
+            ])
+        );
+        // overridden settings still count as known
+        assert!(source.unknown_keys().is_empty());
+    }
+
+    #[test]
+    fn problems_are_collected() {
+        let mut source =
+            source("[server]\nport = \"eighty\"\nprot = 80\n[cors]\nallowed_origins = \"*\"\n");
+        assert_eq!(
+            source.get("server.port", "SOURCE_TEST_UNSET_PORT", 3000),
+            3000
+        );
+        assert_eq!(
+            source.list("cors.allowed_origins", "SOURCE_TEST_UNSET_ORIGINS"),
+            None
+        );
+        assert_eq!(
+            source.errors,
+            vec![
+                "server.port (SOURCE_TEST_UNSET_PORT): invalid value 'eighty'",
+                "cors.allowed_origins (SOURCE_TEST_UNSET_ORIGINS): expected an array of strings",
+            ]
+        );
+        assert_eq!(source.unknown_keys(), vec!["server.prot"]);
+    }
+}
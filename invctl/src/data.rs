@@ -0,0 +1,263 @@
+use crate::error::CliError;
+use api::rest::material::dto::RequestCreateMaterialDto;
+use api::rest::supplier::dto::RequestCreateSupplierDto;
+use api::util::export::{ExportCell, ExportRecord};
+use api::util::import::{ImportFile, ImportFormat};
+use clap::{Args, ValueEnum};
+use lib::app_ctx::AppCtx;
+use lib::util::error::LibError;
+use lib::util::model::{ImportReport, ListQuery};
+use rust_xlsxwriter::{Workbook, Worksheet};
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use tokio::sync::mpsc::{self, Sender};
+
+// rows buffered between the database and the file
+const EXPORT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ImportEntity {
+    Suppliers,
+    Materials,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    entity: ImportEntity,
+    /// A .csv or .xlsx file, the header row names the columns like for the api import
+    file: PathBuf,
+    /// Only check the rows, write nothing
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportEntity {
+    Users,
+    Suppliers,
+    MaterialGroups,
+    Materials,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Xlsx,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    entity: ExportEntity,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+    format: ExportFormat,
+    /// Written to stdout when missing, xlsx needs a file
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+pub async fn import(args: ImportArgs, app_ctx: &AppCtx) -> Result<(), CliError> {
+    let file_name = args.file.to_string_lossy().to_string();
+    let format = ImportFormat::from_file_name(&file_name)
+        .ok_or_else(|| CliError::Invalid("Unsupported file type, expected .csv or .xlsx".into()))?;
+    let file = ImportFile::from_bytes(format, &std::fs::read(&args.file)?)?;
+    // like the api, a file with invalid rows is checked but never written
+    let (mut report, errors) = match args.entity {
+        ImportEntity::Suppliers => {
+            let (rows, errors) = file.parse(RequestCreateSupplierDto::to_create_supplier);
+            let report = app_ctx
+                .supplier_use_case
+                .import_suppliers(&rows, args.dry_run || !errors.is_empty(), None)
+                .await?;
+            (report, errors)
+        }
+        ImportEntity::Materials => {
+            let (rows, errors) = file.parse(RequestCreateMaterialDto::to_create_material);
+            let report = app_ctx
+                .material_use_case
+                .import_materials(&rows, args.dry_run || !errors.is_empty(), None)
+                .await?;
+            (report, errors)
+        }
+    };
+    report.errors.extend(errors);
+    report.errors.sort_by_key(|error| error.row);
+    print_import_report(&report, file.len());
+    let mut invalid_rows: Vec<usize> = report.errors.iter().map(|error| error.row).collect();
+    invalid_rows.dedup();
+    match invalid_rows.is_empty() {
+        true => Ok(()),
+        false => Err(CliError::Invalid(format!(
+            "{} invalid rows, nothing was imported",
+            invalid_rows.len()
+        ))),
+    }
+}
+
+fn print_import_report(report: &ImportReport, total: usize) {
+    for error in &report.errors {
+        match &error.field {
+            Some(field) => println!("row {}: {field}: {}", error.row, error.message),
+            None => println!("row {}: {}", error.row, error.message),
+        }
+    }
+    println!("{total} rows read, {} imported", report.imported);
+}
+
+pub async fn export(args: ExportArgs, app_ctx: &AppCtx) -> Result<(), CliError> {
+    let query = ListQuery::default();
+    let exported = match args.entity {
+        ExportEntity::Users => {
+            write_export(&args, |sender| async move {
+                app_ctx.user_use_case.export_users(&query, &sender).await
+            })
+            .await?
+        }
+        ExportEntity::Suppliers => {
+            write_export(&args, |sender| async move {
+                app_ctx
+                    .supplier_use_case
+                    .export_suppliers(&query, &sender)
+                    .await
+            })
+            .await?
+        }
+        ExportEntity::MaterialGroups => {
+            write_export(&args, |sender| async move {
+                app_ctx
+                    .material_use_case
+                    .export_material_groups(&query, &sender)
+                    .await
+            })
+            .await?
+        }
+        ExportEntity::Materials => {
+            write_export(&args, |sender| async move {
+                app_ctx
+                    .material_use_case
+                    .export_materials(&query, &sender)
+                    .await
+            })
+            .await?
+        }
+    };
+    eprintln!("{exported} rows exported");
+    Ok(())
+}
+
+// runs `export` while writing what it sends, the records are the ones the api exports
+async fn write_export<R, F, Fut>(args: &ExportArgs, export: F) -> Result<usize, CliError>
+where
+    R: ExportRecord,
+    F: FnOnce(Sender<R>) -> Fut,
+    Fut: Future<Output = Result<(), LibError>>,
+{
+    let mut writer = RecordWriter::new::<R>(args.format, args.output.as_ref())?;
+    let (sender, mut receiver) = mpsc::channel(EXPORT_BUFFER);
+    // the receiver is dropped when writing fails, which ends the export
+    let written = async move {
+        let mut count = 0;
+        while let Some(record) = receiver.recv().await {
+            writer.write(&record)?;
+            count += 1;
+        }
+        writer.finish()?;
+        Ok::<usize, CliError>(count)
+    };
+    let (exported, written) = tokio::join!(export(sender), written);
+    let count = written?;
+    exported?;
+    Ok(count)
+}
+
+enum RecordWriter {
+    Csv(Box<csv::Writer<Box<dyn Write>>>),
+    Ndjson(Box<dyn Write>),
+    Xlsx {
+        worksheet: Box<Worksheet>,
+        row: u32,
+        path: PathBuf,
+    },
+}
+
+impl RecordWriter {
+    // starts the file with the header row, an export without rows still has one
+    fn new<R: ExportRecord>(
+        format: ExportFormat,
+        output: Option<&PathBuf>,
+    ) -> Result<Self, CliError> {
+        let out = || -> Result<Box<dyn Write>, CliError> {
+            Ok(match output {
+                Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(BufWriter::new(io::stdout().lock())),
+            })
+        };
+        Ok(match format {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(out()?);
+                writer.write_record(R::HEADERS).map_err(io::Error::from)?;
+                RecordWriter::Csv(Box::new(writer))
+            }
+            ExportFormat::Ndjson => RecordWriter::Ndjson(out()?),
+            ExportFormat::Xlsx => {
+                let path = output
+                    .cloned()
+                    .ok_or_else(|| CliError::Invalid("xlsx exports need --output".into()))?;
+                let mut worksheet = Box::new(Worksheet::new());
+                for (col, header) in R::HEADERS.iter().enumerate() {
+                    worksheet
+                        .write_string(0, col as u16, *header)
+                        .map_err(io::Error::other)?;
+                }
+                RecordWriter::Xlsx {
+                    worksheet,
+                    row: 1,
+                    path,
+                }
+            }
+        })
+    }
+
+    fn write<R: ExportRecord>(&mut self, record: &R) -> Result<(), CliError> {
+        match self {
+            RecordWriter::Csv(writer) => {
+                let cells = record.to_row().into_iter().map(ExportCell::into_csv);
+                writer.write_record(cells).map_err(io::Error::from)?;
+            }
+            RecordWriter::Ndjson(writer) => {
+                serde_json::to_writer(&mut *writer, record).map_err(io::Error::from)?;
+                writer.write_all(b"\n")?;
+            }
+            RecordWriter::Xlsx { worksheet, row, .. } => {
+                for (col, cell) in record.to_row().into_iter().enumerate() {
+                    match cell {
+                        ExportCell::Text(value) => worksheet.write_string(*row, col as u16, value),
+                        ExportCell::Number(value) => {
+                            worksheet.write_number(*row, col as u16, value)
+                        }
+                    }
+                    .map_err(io::Error::other)?;
+                }
+                *row += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), CliError> {
+        match self {
+            RecordWriter::Csv(mut writer) => writer.flush()?,
+            RecordWriter::Ndjson(mut writer) => writer.flush()?,
+            RecordWriter::Xlsx {
+                worksheet, path, ..
+            } => {
+                let mut workbook = Workbook::new();
+                workbook.push_worksheet(*worksheet);
+                workbook.save(path).map_err(io::Error::other)?;
+            }
+        }
+        Ok(())
+    }
+}
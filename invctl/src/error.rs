@@ -0,0 +1,46 @@
+use api::util::error::RestApiError;
+use config::ConfigError;
+use lib::util::error::LibError;
+use lib::util::postgres::SchemaError;
+use sqlx::migrate::MigrateError;
+use thiserror::Error;
+use validator::ValidationErrors;
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Lib(#[from] LibError),
+    #[error(transparent)]
+    Api(#[from] RestApiError),
+    #[error(transparent)]
+    Schema(#[from] SchemaError),
+    #[error(transparent)]
+    Migrate(#[from] MigrateError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+// one `field: message` per failed rule, the same rules the api applies
+impl From<ValidationErrors> for CliError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields: Vec<String> = errors
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| {
+                    let message = error
+                        .message
+                        .as_ref()
+                        .map_or_else(|| error.code.to_string(), |message| message.to_string());
+                    format!("{field}: {message}")
+                })
+            })
+            .collect();
+        fields.sort();
+        CliError::Invalid(fields.join(", "))
+    }
+}
@@ -0,0 +1,78 @@
+use crate::data::{ExportArgs, ImportArgs};
+use crate::error::CliError;
+use crate::migrate::MigrateCommand;
+use crate::report::ReportCommand;
+use crate::role::RoleCommand;
+use crate::user::UserCommand;
+use api::app::build_app_ctx;
+use clap::{Parser, Subcommand};
+use config::Config;
+use lib::util::postgres::{check_schema, get_connection_pool};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+mod data;
+mod error;
+mod migrate;
+mod report;
+mod role;
+mod user;
+
+/// Operational tasks for the inventory backend, run against the database of DATABASE_URL
+#[derive(Debug, Parser)]
+#[command(name = "invctl", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Create users, reset passwords and change roles
+    #[command(subcommand)]
+    User(UserCommand),
+    /// List, create, rename and delete roles
+    #[command(subcommand)]
+    Role(RoleCommand),
+    /// Apply the migrations built into invctl or check the schema
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Import suppliers or materials from a CSV or XLSX file
+    Import(ImportArgs),
+    /// Export users, suppliers, material groups or materials
+    Export(ExportArgs),
+    /// Print reports
+    #[command(subcommand)]
+    Report(ReportCommand),
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<(), CliError> {
+    let config = Config::load()?;
+    let pool = Arc::new(get_connection_pool(config.clone()));
+    if let Command::Migrate(command) = command {
+        return migrate::run(command, &pool).await;
+    }
+    // like the server, nothing is written to a schema this build does not match
+    check_schema(&pool).await?;
+    let app_ctx = build_app_ctx(&config, pool).await;
+    match command {
+        Command::User(command) => user::run(command, &app_ctx).await,
+        Command::Role(command) => role::run(command, &app_ctx).await,
+        Command::Import(args) => data::import(args, &app_ctx).await,
+        Command::Export(args) => data::export(args, &app_ctx).await,
+        Command::Report(command) => report::run(command, &app_ctx, &config).await,
+        Command::Migrate(_) => unreachable!("handled before the schema check"),
+    }
+}
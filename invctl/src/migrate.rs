@@ -0,0 +1,21 @@
+use crate::error::CliError;
+use clap::Subcommand;
+use lib::util::postgres::{check_schema, MIGRATOR};
+use sqlx::{Pool, Postgres};
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply pending migrations
+    Run,
+    /// Check that every migration is applied unchanged, fails otherwise
+    Status,
+}
+
+pub async fn run(command: MigrateCommand, pool: &Pool<Postgres>) -> Result<(), CliError> {
+    if let MigrateCommand::Run = command {
+        MIGRATOR.run(pool).await?;
+    }
+    check_schema(pool).await?;
+    println!("Database schema is up to date");
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use crate::error::CliError;
+use clap::Subcommand;
+use config::Config;
+use lib::app_ctx::AppCtx;
+use lib::material::model::StockReport;
+
+#[derive(Debug, Subcommand)]
+pub enum ReportCommand {
+    /// Stock held per material group and the materials running low
+    Stock {
+        /// Quantity at or below which a material is low, LOW_STOCK_THRESHOLD by default
+        #[arg(long)]
+        low_stock: Option<i32>,
+        /// Print the report as json
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub async fn run(
+    command: ReportCommand,
+    app_ctx: &AppCtx,
+    config: &Config,
+) -> Result<(), CliError> {
+    match command {
+        ReportCommand::Stock { low_stock, json } => {
+            let report = app_ctx
+                .material_use_case
+                .get_stock_report(low_stock.unwrap_or(config.low_stock_threshold))
+                .await?;
+            match json {
+                true => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).map_err(std::io::Error::from)?
+                ),
+                false => print_stock_report(&report),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_stock_report(report: &StockReport) {
+    let groups: Vec<Vec<String>> = report
+        .groups
+        .iter()
+        .map(|group| {
+            vec![
+                group.group_name.clone().unwrap_or("(no group)".to_string()),
+                group.materials.to_string(),
+                group.quantity.to_string(),
+                group.value.to_string(),
+            ]
+        })
+        .chain([vec![
+            "Total".to_string(),
+            report.materials.to_string(),
+            report.quantity.to_string(),
+            report.value.to_string(),
+        ]])
+        .collect();
+    print_table(&["GROUP", "MATERIALS", "QUANTITY", "VALUE"], &groups);
+
+    println!();
+    println!(
+        "{} materials at or below {} in stock",
+        report.low_stock.len(),
+        report.low_stock_threshold
+    );
+    let low_stock: Vec<Vec<String>> = report
+        .low_stock
+        .iter()
+        .map(|material| {
+            vec![
+                material.name.clone(),
+                material.quantity.to_string(),
+                material.group_name.clone().unwrap_or_default(),
+                material.supplier_name.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    if !low_stock.is_empty() {
+        print_table(&["MATERIAL", "QUANTITY", "GROUP", "SUPPLIER"], &low_stock);
+    }
+}
+
+// left aligned columns as wide as their widest cell
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(col, header)| {
+            rows.iter()
+                .map(|row| row[col].chars().count())
+                .chain([header.len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(headers.to_vec());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::error::CliError;
+use crate::report::print_table;
+use api::rest::role::dto::RequestRoleDto;
+use clap::Subcommand;
+use lib::app_ctx::AppCtx;
+use lib::util::error::LibError;
+use lib::util::model::ListQuery;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Subcommand)]
+pub enum RoleCommand {
+    /// List roles
+    List,
+    /// Create a role
+    Create { name: String },
+    /// Rename a role, users keep it under the new name
+    Rename { name: String, new_name: String },
+    /// Delete a role no user has anymore
+    Delete { name: String },
+}
+
+pub async fn run(command: RoleCommand, app_ctx: &AppCtx) -> Result<(), CliError> {
+    match command {
+        RoleCommand::List => {
+            let query = ListQuery {
+                limit: 500,
+                sort: Some("name".to_string()),
+                ..ListQuery::default()
+            };
+            let roles = app_ctx.role_use_case.get_roles(&query).await?;
+            let rows: Vec<Vec<String>> = roles
+                .items
+                .iter()
+                .map(|role| vec![role.id.to_string(), role.name.clone()])
+                .collect();
+            print_table(&["ID", "NAME"], &rows);
+        }
+        RoleCommand::Create { name } => {
+            let dto = RequestRoleDto { name };
+            dto.validate()?;
+            let role = app_ctx
+                .role_use_case
+                .create_role(&dto.to_request_role(), None)
+                .await?;
+            println!("Created role {} ({})", role.name, role.id);
+        }
+        RoleCommand::Rename { name, new_name } => {
+            let id = find_role(app_ctx, &name).await?;
+            let dto = RequestRoleDto { name: new_name };
+            dto.validate()?;
+            let role = app_ctx
+                .role_use_case
+                .update_role(&id, &dto.to_request_role(), None, None)
+                .await?;
+            println!("Renamed role {name} to {}", role.name);
+        }
+        RoleCommand::Delete { name } => {
+            let id = find_role(app_ctx, &name).await?;
+            app_ctx.role_use_case.delete_role(&id, None, None).await?;
+            println!("Deleted role {name}");
+        }
+    }
+    Ok(())
+}
+
+async fn find_role(app_ctx: &AppCtx, name: &str) -> Result<Uuid, CliError> {
+    match app_ctx.role_use_case.find_role(name).await {
+        Err(LibError::SqlxError(sqlx::Error::RowNotFound)) => {
+            Err(CliError::Invalid(format!("No role named {name}")))
+        }
+        result => Ok(result?),
+    }
+}
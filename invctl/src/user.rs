@@ -0,0 +1,186 @@
+use crate::error::CliError;
+use crate::report::print_table;
+use api::rest::user::dto::{RequestCreateUserDto, RequestUpdateUserDto};
+use clap::Subcommand;
+use lib::app_ctx::AppCtx;
+use lib::role::entity::Role;
+use lib::user::model::{CreateUser, ResponseUser, UpdateUser};
+use lib::util::error::LibError;
+use lib::util::model::ListQuery;
+use validator::Validate;
+
+// passwords never go on the command line where shell history and `ps` would keep them
+const PASSWORD_ENV: &str = "INVCTL_PASSWORD";
+
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// Create a user, the password is read from INVCTL_PASSWORD or prompted for
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        /// Admin, User or a role created with `invctl role create`
+        #[arg(long, value_parser = parse_role)]
+        role: String,
+        #[arg(long)]
+        address: Option<String>,
+    },
+    /// Set a new password, sign the user out everywhere and lift a lockout
+    ResetPassword {
+        #[arg(long)]
+        email: String,
+    },
+    /// Change the role of a user
+    SetRole {
+        #[arg(long)]
+        email: String,
+        /// Admin, User or a role created with `invctl role create`
+        #[arg(long, value_parser = parse_role)]
+        role: String,
+    },
+    /// List users
+    List {
+        /// Only users with this role
+        #[arg(long)]
+        role: Option<String>,
+    },
+}
+
+pub async fn run(command: UserCommand, app_ctx: &AppCtx) -> Result<(), CliError> {
+    match command {
+        UserCommand::Create {
+            name,
+            email,
+            role,
+            address,
+        } => {
+            let role = find_role(app_ctx, &role).await?;
+            let dto = RequestCreateUserDto {
+                name: Some(name),
+                email: Some(email),
+                password: Some(read_password()?),
+                address,
+                role: None,
+            };
+            dto.validate()?;
+            let user = CreateUser {
+                role,
+                ..dto.to_create_user()
+            };
+            let user = app_ctx.user_use_case.create_user(&user, None).await?;
+            println!("Created {} {} ({})", user.role_name, user.email, user.id);
+        }
+        UserCommand::ResetPassword { email } => {
+            let user = find_user(app_ctx, &email).await?;
+            let password = read_password()?;
+            let dto = RequestUpdateUserDto {
+                password: Some(password.clone()),
+                ..update_dto()
+            };
+            dto.validate()?;
+            let revoked = app_ctx
+                .user_use_case
+                .reset_password(&user.id, &password)
+                .await?;
+            println!("Password of {email} reset, {revoked} sessions revoked");
+        }
+        UserCommand::SetRole { email, role } => {
+            let user = find_user(app_ctx, &email).await?;
+            let changes = UpdateUser {
+                role: Some(find_role(app_ctx, &role).await?),
+                ..update_dto().to_update_user()
+            };
+            let user = app_ctx
+                .user_use_case
+                .update_user(&user.id, &changes, None, None)
+                .await?;
+            println!("{} is now {}", user.email, user.role_name);
+        }
+        UserCommand::List { role } => {
+            let mut query = ListQuery {
+                limit: 500,
+                sort: Some("email".to_string()),
+                filters: role
+                    .map(|role| ("role".to_string(), role))
+                    .into_iter()
+                    .collect(),
+                ..ListQuery::default()
+            };
+            let mut users = Vec::new();
+            loop {
+                let page = app_ctx.user_use_case.get_users(&query).await?;
+                users.extend(page.items);
+                match page.next_cursor {
+                    Some(cursor) => query.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            let rows: Vec<Vec<String>> = users
+                .iter()
+                .map(|user| {
+                    vec![
+                        user.id.to_string(),
+                        user.name.clone(),
+                        user.email.clone(),
+                        user.role_name.clone(),
+                    ]
+                })
+                .collect();
+            print_table(&["ID", "NAME", "EMAIL", "ROLE"], &rows);
+        }
+    }
+    Ok(())
+}
+
+// whether the role exists is only known once connected, see `find_role`
+fn parse_role(role: &str) -> Result<String, String> {
+    match role.trim() {
+        "" => Err("expected a role name".to_string()),
+        role => Ok(Role::named(role).to_str()),
+    }
+}
+
+async fn find_role(app_ctx: &AppCtx, name: &str) -> Result<Role, CliError> {
+    match app_ctx.role_use_case.find_role(name).await {
+        Ok(_) => Ok(Role::named(name)),
+        Err(LibError::SqlxError(sqlx::Error::RowNotFound)) => Err(CliError::Invalid(format!(
+            "No role named {name}, see `invctl role list`"
+        ))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn update_dto() -> RequestUpdateUserDto {
+    RequestUpdateUserDto {
+        name: None,
+        email: None,
+        password: None,
+        address: None,
+        role: None,
+    }
+}
+
+async fn find_user(app_ctx: &AppCtx, email: &str) -> Result<ResponseUser, CliError> {
+    let query = ListQuery {
+        limit: 1,
+        filters: vec![("email".to_string(), email.trim().to_string())],
+        ..ListQuery::default()
+    };
+    let page = app_ctx.user_use_case.get_users(&query).await?;
+    page.items
+        .into_iter()
+        .next()
+        .ok_or_else(|| CliError::Invalid(format!("No user with email {email}")))
+}
+
+fn read_password() -> Result<String, CliError> {
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password("Password: ")?;
+    if rpassword::prompt_password("Repeat password: ")? != password {
+        return Err(CliError::Invalid("Passwords do not match".to_string()));
+    }
+    Ok(password)
+}
@@ -1,9 +1,19 @@
+use crate::audit::repository::AuditLogRepository;
+use crate::audit::use_case::AuditLogUseCase;
 use crate::auth::auth::Auth;
 use crate::auth::use_case::AuthUseCase;
+use crate::idempotency::repository::IdempotencyRepository;
+use crate::idempotency::use_case::IdempotencyUseCase;
 use crate::material::repository::MaterialRepository;
 use crate::material::use_case::MaterialUseCase;
+use crate::oidc::repository::OidcRepository;
+use crate::oidc::use_case::OidcUseCase;
 use crate::role::repository::RoleRepository;
 use crate::role::use_case::RoleUseCase;
+use crate::search::repository::SearchRepository;
+use crate::search::use_case::SearchUseCase;
+use crate::session::repository::SessionRepository;
+use crate::session::use_case::SessionUseCase;
 use crate::supplier::repository::SupplierRepository;
 use crate::supplier::use_case::SupplierUseCase;
 use crate::user::repository::UserRepository;
@@ -16,27 +26,127 @@ pub struct AppCtx {
     pub supplier_use_case: SupplierUseCase,
     pub material_use_case: MaterialUseCase,
     pub auth_use_case: AuthUseCase,
+    pub oidc_use_case: OidcUseCase,
+    pub session_use_case: SessionUseCase,
+    pub audit_log_use_case: AuditLogUseCase,
+    pub search_use_case: SearchUseCase,
+    pub idempotency_use_case: IdempotencyUseCase,
 }
 
 impl AppCtx {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         user_approval_repository: Box<dyn UserRepository>,
         role_approval_repository: Box<dyn RoleRepository>,
         supplier_repository: Box<dyn SupplierRepository>,
         material_repository: Box<dyn MaterialRepository>,
         auth_approval_repository: Box<Auth>,
+        oidc_repository: Box<dyn OidcRepository>,
+        session_repository: Box<dyn SessionRepository>,
+        audit_log_repository: Box<dyn AuditLogRepository>,
+        search_repository: Box<dyn SearchRepository>,
+        idempotency_repository: Box<dyn IdempotencyRepository>,
     ) -> AppCtx {
         let user_use_case = UserUseCase::new(user_approval_repository);
         let role_use_case = RoleUseCase::new(role_approval_repository);
         let supplier_use_case = SupplierUseCase::new(supplier_repository);
         let material_use_case = MaterialUseCase::new(material_repository);
         let auth_use_case = AuthUseCase::new(auth_approval_repository);
+        let oidc_use_case = OidcUseCase::new(oidc_repository);
+        let session_use_case = SessionUseCase::new(session_repository);
+        let audit_log_use_case = AuditLogUseCase::new(audit_log_repository);
+        let search_use_case = SearchUseCase::new(search_repository);
+        let idempotency_use_case = IdempotencyUseCase::new(idempotency_repository);
         AppCtx {
             user_use_case,
             role_use_case,
             supplier_use_case,
             material_use_case,
             auth_use_case,
+            oidc_use_case,
+            session_use_case,
+            audit_log_use_case,
+            search_use_case,
+            idempotency_use_case,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::audit::PgAuditLogRepository;
+    use crate::idempotency::idempotency::PgIdempotencyRepository;
+    use crate::material::memory::MemoryMaterialRepository;
+    use crate::oidc::oidc::PgOidcRepository;
+    use crate::role::entity::Role;
+    use crate::role::memory::MemoryRoleRepository;
+    use crate::search::search::PgSearchRepository;
+    use crate::session::session::PgSessionRepository;
+    use crate::supplier::memory::MemorySupplierRepository;
+    use crate::user::memory::MemoryUserRepository;
+    use crate::user::model::{ClientInfo, CreateUser};
+    use crate::util::memory::MemoryDb;
+    use config::{AuthConfig, Keys, LoginPolicy};
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn memory_repositories_wire_into_the_context() {
+        // never connected, the repositories without a memory counterpart are not used here
+        let pool = Arc::new(
+            PgPoolOptions::new()
+                .connect_lazy("postgres://localhost/unused")
+                .unwrap(),
+        );
+        let db = Arc::new(MemoryDb::new());
+        let app_ctx = AppCtx::new(
+            Box::new(MemoryUserRepository::new(
+                db.clone(),
+                LoginPolicy::default(),
+                Keys::new(&[7; 32]),
+            )),
+            Box::new(MemoryRoleRepository::new(db.clone())),
+            Box::new(MemorySupplierRepository::new(db.clone())),
+            Box::new(MemoryMaterialRepository::new(db)),
+            Box::new(Auth::new(pool.clone(), Keys::new(&[7; 32])).await),
+            Box::new(
+                PgOidcRepository::new(
+                    pool.clone(),
+                    None,
+                    AuthConfig {
+                        token_ttl_seconds: AuthConfig::DEFAULT_TOKEN_TTL_SECONDS,
+                        bcrypt_cost: AuthConfig::DEFAULT_BCRYPT_COST,
+                        keys: Keys::new(&[7; 32]),
+                    },
+                )
+                .await,
+            ),
+            Box::new(PgSessionRepository::new(pool.clone()).await),
+            Box::new(PgAuditLogRepository::new(pool.clone()).await),
+            Box::new(PgSearchRepository::new(pool.clone()).await),
+            Box::new(PgIdempotencyRepository::new(pool, 60).await),
+        )
+        .await;
+
+        let user = CreateUser {
+            name: "alice".to_string(),
+            email: "alice@example.com".to_string(),
+            password: "Passw0rd".to_string(),
+            address: String::new(),
+            role: Role::Admin,
+        };
+        let created = app_ctx
+            .user_use_case
+            .create_user(&user, None)
+            .await
+            .unwrap();
+        let admin = app_ctx.role_use_case.find_role("Admin").await.unwrap();
+        assert_eq!(created.role_id, admin);
+        assert!(app_ctx
+            .user_use_case
+            .login("alice@example.com", "Passw0rd", &ClientInfo::default())
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::audit::model::{AuditEntry, AuditLogFilter, ResponseAuditLog};
+use crate::audit::repository::AuditLogRepository;
+use crate::util::error::LibError;
+use async_trait::async_trait;
+use sqlx::postgres::{PgConnection, Postgres};
+use sqlx::Pool;
+use std::sync::Arc;
+use tracing::instrument;
+
+#[derive(Debug)]
+pub struct PgAuditLogRepository {
+    db_connect: Arc<Pool<Postgres>>,
+}
+
+impl PgAuditLogRepository {
+    pub async fn new(db_connect: Arc<Pool<Postgres>>) -> Self {
+        Self { db_connect }
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for PgAuditLogRepository {
+    #[instrument(skip_all)]
+    async fn get_audit_logs(
+        &self,
+        filter: &AuditLogFilter,
+    ) -> Result<Vec<ResponseAuditLog>, LibError> {
+        let query = sqlx::query_as!(
+            ResponseAuditLog,
+            r#"
+            SELECT a.id, a.actor_id, u.name as "actor_name?", a.entity_type, a.entity_id,
+                a.action, a.before, a.after, a.created_at
+            FROM audit_log as a
+            LEFT JOIN users as u
+            ON a.actor_id = u.id
+            WHERE ($1::uuid IS NULL OR a.actor_id = $1)
+              AND ($2::text IS NULL OR a.entity_type = $2)
+              AND ($3::uuid IS NULL OR a.entity_id = $3)
+              AND ($4::text IS NULL OR a.action = $4)
+              AND ($5::timestamptz IS NULL OR a.created_at >= $5)
+              AND ($6::timestamptz IS NULL OR a.created_at < $6)
+            ORDER BY a.created_at DESC
+            LIMIT $7;
+            "#,
+            filter.actor_id,
+            filter.entity_type,
+            filter.entity_id,
+            filter.action,
+            filter.from,
+            filter.to,
+            filter.limit
+        )
+        .fetch_all(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(query)
+    }
+}
+
+impl PgAuditLogRepository {
+    // called inside the transaction of the change, so a change is never stored without its entry
+    pub async fn record(db_connect: &mut PgConnection, entry: &AuditEntry) -> Result<(), LibError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (actor_id, entity_type, entity_id, action, before, after)
+            VALUES ($1, $2, $3, $4, $5, $6);
+            "#,
+            entry.actor_id,
+            entry.entity_type.to_str(),
+            entry.entity_id,
+            entry.action.to_str(),
+            entry.before,
+            entry.after
+        )
+        .execute(db_connect)
+        .await?;
+        Ok(())
+    }
+}
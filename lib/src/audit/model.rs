@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// columns every write touches, so they would show up in every diff
+const IGNORED_FIELDS: [&str; 2] = ["created_at", "updated_at"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEntityType {
+    User,
+    Role,
+    Supplier,
+    MaterialGroup,
+    Material,
+}
+
+impl AuditEntityType {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            AuditEntityType::User => "user",
+            AuditEntityType::Role => "role",
+            AuditEntityType::Supplier => "supplier",
+            AuditEntityType::MaterialGroup => "material_group",
+            AuditEntityType::Material => "material",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+impl AuditAction {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Restore => "restore",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub actor_id: Option<Uuid>,
+    pub entity_type: AuditEntityType,
+    pub entity_id: Uuid,
+    pub action: AuditAction,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditEntry {
+    pub fn created<T: Serialize>(
+        actor_id: Option<&Uuid>,
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+        after: &T,
+    ) -> Self {
+        Self {
+            actor_id: actor_id.copied(),
+            entity_type,
+            entity_id,
+            action: AuditAction::Create,
+            before: None,
+            after: Some(to_fields(after)),
+        }
+    }
+
+    // only the fields that changed are kept on both sides
+    pub fn updated<T: Serialize>(
+        actor_id: Option<&Uuid>,
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+        before: &T,
+        after: &T,
+    ) -> Self {
+        let mut before = to_fields(before);
+        let mut after = to_fields(after);
+        if let (Value::Object(before), Value::Object(after)) = (&mut before, &mut after) {
+            let unchanged: Vec<String> = before
+                .iter()
+                .filter(|(key, value)| after.get(*key) == Some(*value))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in unchanged {
+                before.remove(&key);
+                after.remove(&key);
+            }
+        }
+        Self {
+            actor_id: actor_id.copied(),
+            entity_type,
+            entity_id,
+            action: AuditAction::Update,
+            before: Some(before),
+            after: Some(after),
+        }
+    }
+
+    pub fn deleted<T: Serialize>(
+        actor_id: Option<&Uuid>,
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+        before: &T,
+    ) -> Self {
+        Self {
+            actor_id: actor_id.copied(),
+            entity_type,
+            entity_id,
+            action: AuditAction::Delete,
+            before: Some(to_fields(before)),
+            after: None,
+        }
+    }
+
+    pub fn restored<T: Serialize>(
+        actor_id: Option<&Uuid>,
+        entity_type: AuditEntityType,
+        entity_id: Uuid,
+        after: &T,
+    ) -> Self {
+        Self {
+            action: AuditAction::Restore,
+            ..Self::created(actor_id, entity_type, entity_id, after)
+        }
+    }
+
+    // marks a field that is never stored in the log, like a password hash, as changed
+    pub fn with_redacted_change(mut self, field: &str) -> Self {
+        for (side, value) in [
+            (&mut self.before, "[redacted]"),
+            (&mut self.after, "[changed]"),
+        ] {
+            if let Some(Value::Object(fields)) = side {
+                fields.insert(field.to_string(), Value::String(value.to_string()));
+            }
+        }
+        self
+    }
+}
+
+fn to_fields<T: Serialize>(value: &T) -> Value {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(fields)) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|(key, _)| !IGNORED_FIELDS.contains(&key.as_str()))
+                .collect::<Map<String, Value>>(),
+        ),
+        Ok(value) => value,
+        Err(_) => Value::Null,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogFilter {
+    pub actor_id: Option<Uuid>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ResponseAuditLog {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub actor_name: Option<String>,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub action: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
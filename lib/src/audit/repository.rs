@@ -0,0 +1,11 @@
+use crate::audit::model::{AuditLogFilter, ResponseAuditLog};
+use crate::util::error::LibError;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait AuditLogRepository: Send + Sync + std::fmt::Debug {
+    async fn get_audit_logs(
+        &self,
+        filter: &AuditLogFilter,
+    ) -> Result<Vec<ResponseAuditLog>, LibError>;
+}
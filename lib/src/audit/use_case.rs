@@ -0,0 +1,21 @@
+use crate::audit::model::{AuditLogFilter, ResponseAuditLog};
+use crate::audit::repository::AuditLogRepository;
+use crate::util::error::LibError;
+use tracing::instrument;
+
+#[derive(Debug)]
+pub struct AuditLogUseCase(Box<dyn AuditLogRepository>);
+
+impl AuditLogUseCase {
+    pub fn new(repository: Box<dyn AuditLogRepository>) -> Self {
+        AuditLogUseCase(repository)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_audit_logs(
+        &self,
+        filter: &AuditLogFilter,
+    ) -> Result<Vec<ResponseAuditLog>, LibError> {
+        self.0.get_audit_logs(filter).await
+    }
+}
@@ -1,19 +1,38 @@
 use async_trait::async_trait;
-use jsonwebtoken::{decode, Validation};
+use jsonwebtoken::{decode, decode_header, Validation};
 
+use crate::session::session::PgSessionRepository;
 use crate::util::error::LibError;
 
 use super::model::{AuthInfo, UserInfo};
 use super::repository::AuthRepository;
 use config::Keys;
+use sqlx::postgres::Postgres;
+use sqlx::Pool;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
-pub struct Auth {}
+pub struct Auth {
+    db_connect: Arc<Pool<Postgres>>,
+    keys: Keys,
+}
 
 impl Auth {
-    pub async fn new() -> Self {
-        Self {}
+    pub async fn new(db_connect: Arc<Pool<Postgres>>, keys: Keys) -> Self {
+        Self { db_connect, keys }
+    }
+
+    // the claims of a token signed by one of `keys`, whether its session is still active
+    // is left to `get_info`
+    pub fn decode_token(keys: &Keys, token: &str) -> Result<UserInfo, LibError> {
+        let kid = decode_header(token).map_err(LibError::JwtError)?.kid;
+        let key = keys
+            .find(kid.as_deref())
+            .ok_or_else(|| LibError::Unauthorized("Unknown signing key".to_string()))?;
+        let user_info = decode(token, &key.decoding, &Validation::new(key.algorithm))
+            .map_err(LibError::JwtError)?
+            .claims;
+        Ok(user_info)
     }
 }
 
@@ -21,9 +40,17 @@ impl Auth {
 impl AuthRepository for Auth {
     async fn get_info(&self, token: &str) -> Result<AuthInfo, LibError> {
         let token = token.to_string();
-        let user_info: UserInfo = decode(&token, &Keys::default().decoding, &Validation::default())
-            .map_err(|e| LibError::JwtError(e))?
-            .claims;
+        let user_info = Self::decode_token(&self.keys, &token)?;
+
+        // a valid signature is not enough, the session must not have been revoked
+        let active =
+            PgSessionRepository::touch_session(&self.db_connect, &user_info.sub, &user_info.sid)
+                .await?;
+        if !active {
+            return Err(LibError::Unauthorized(
+                "Session has been revoked".to_string(),
+            ));
+        }
 
         Ok(AuthInfo { user_info, token })
     }
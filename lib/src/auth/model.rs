@@ -1,17 +1,19 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Deserialize, Debug, Clone, Serialize)]
+#[derive(Deserialize, Debug, Clone, Serialize, ToSchema)]
 pub struct UserInfo {
     pub sub: Uuid,
     pub name: String,
     pub email: String,
     pub roles: String,
+    pub sid: Uuid,
     pub iat: usize,
     pub exp: usize,
 }
 
-#[derive(Deserialize, Debug, Clone, Serialize)]
+#[derive(Deserialize, Debug, Clone, Serialize, ToSchema)]
 pub struct AuthInfo {
     pub user_info: UserInfo,
     pub token: String,
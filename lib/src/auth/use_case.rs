@@ -2,6 +2,7 @@ use crate::auth::auth::Auth;
 use crate::auth::model::AuthInfo;
 use crate::auth::repository::AuthRepository;
 use crate::util::error::LibError;
+use tracing::instrument;
 
 #[derive(Debug, Clone)]
 pub struct AuthUseCase(Box<Auth>);
@@ -13,9 +14,11 @@ impl AuthUseCase {
 }
 
 impl AuthUseCase {
+    #[instrument(skip_all)]
     pub async fn get_info(&self, token: &str) -> Result<AuthInfo, LibError> {
         self.0.get_info(token).await
     }
+    #[instrument(skip_all)]
     pub async fn role_check(
         &self,
         required_roles: Vec<&str>,
@@ -0,0 +1,128 @@
+use crate::idempotency::model::{IdempotencyStatus, IdempotentRequest, StoredResponse};
+use crate::idempotency::repository::IdempotencyRepository;
+use crate::util::error::LibError;
+use async_trait::async_trait;
+use sqlx::postgres::Postgres;
+use sqlx::types::Json;
+use sqlx::Pool;
+use std::sync::Arc;
+use tracing::instrument;
+
+#[derive(Debug)]
+pub struct PgIdempotencyRepository {
+    db_connect: Arc<Pool<Postgres>>,
+    // how long a key is remembered
+    ttl_seconds: i64,
+}
+
+impl PgIdempotencyRepository {
+    pub async fn new(db_connect: Arc<Pool<Postgres>>, ttl_seconds: i64) -> Self {
+        Self {
+            db_connect,
+            ttl_seconds,
+        }
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for PgIdempotencyRepository {
+    #[instrument(skip_all)]
+    async fn begin(&self, request: &IdempotentRequest) -> Result<IdempotencyStatus, LibError> {
+        let db_connect = self.db_connect.clone();
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE created_at < now() - make_interval(secs => $1);
+            "#,
+            self.ttl_seconds as f64
+        )
+        .execute(db_connect.as_ref())
+        .await?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (user_id, key, method, path, fingerprint)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id, key) DO NOTHING;
+            "#,
+            &request.user_id,
+            &request.key,
+            &request.method,
+            &request.path,
+            &request.fingerprint
+        )
+        .execute(db_connect.as_ref())
+        .await?;
+        if inserted.rows_affected() > 0 {
+            return Ok(IdempotencyStatus::Started);
+        }
+
+        let stored = sqlx::query!(
+            r#"
+            SELECT fingerprint, status_code,
+                response_headers as "response_headers: Json<Vec<(String, String)>>",
+                response_body
+            FROM idempotency_keys
+            WHERE user_id = $1 AND key = $2;
+            "#,
+            &request.user_id,
+            &request.key
+        )
+        .fetch_optional(db_connect.as_ref())
+        .await?;
+        let status = match stored {
+            // released in the meantime, the caller may simply retry
+            None => IdempotencyStatus::InProgress,
+            Some(stored) if stored.fingerprint != request.fingerprint => {
+                IdempotencyStatus::Mismatch
+            }
+            Some(stored) => match stored.status_code {
+                Some(status_code) => IdempotencyStatus::Completed(StoredResponse {
+                    status_code,
+                    headers: stored.response_headers.map(|h| h.0).unwrap_or_default(),
+                    body: stored.response_body.unwrap_or_default(),
+                }),
+                None => IdempotencyStatus::InProgress,
+            },
+        };
+        Ok(status)
+    }
+
+    #[instrument(skip_all)]
+    async fn complete(
+        &self,
+        request: &IdempotentRequest,
+        response: &StoredResponse,
+    ) -> Result<(), LibError> {
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET status_code = $3, response_headers = $4, response_body = $5, completed_at = now()
+            WHERE user_id = $1 AND key = $2;
+            "#,
+            &request.user_id,
+            &request.key,
+            response.status_code,
+            Json(&response.headers) as _,
+            &response.body
+        )
+        .execute(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(())
+    }
+
+    #[instrument(skip_all)]
+    async fn release(&self, request: &IdempotentRequest) -> Result<(), LibError> {
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE user_id = $1 AND key = $2 AND completed_at IS NULL;
+            "#,
+            &request.user_id,
+            &request.key
+        )
+        .execute(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+// a mutating request sent with an `Idempotency-Key`, keys are scoped to the user
+#[derive(Debug, Clone)]
+pub struct IdempotentRequest {
+    pub user_id: Uuid,
+    pub key: String,
+    pub method: String,
+    pub path: String,
+    // sha-256 of the method, path and body, a retry has to send the same request
+    pub fingerprint: String,
+}
+
+impl IdempotentRequest {
+    pub fn new(user_id: Uuid, key: &str, method: &str, path: &str, body: &[u8]) -> Self {
+        let mut hasher = Sha256::new();
+        for part in [method.as_bytes(), path.as_bytes(), body] {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        let fingerprint = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        Self {
+            user_id,
+            key: key.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            fingerprint,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status_code: i32,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum IdempotencyStatus {
+    // first time the key is seen, the response has to be saved with `complete`
+    Started,
+    // the key was used for this request before, the saved response is sent again
+    Completed(StoredResponse),
+    // the first request with the key has not finished yet
+    InProgress,
+    // the key was used for a different request
+    Mismatch,
+}
@@ -0,0 +1,14 @@
+use crate::idempotency::model::{IdempotencyStatus, IdempotentRequest, StoredResponse};
+use crate::util::error::LibError;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync + std::fmt::Debug {
+    async fn begin(&self, request: &IdempotentRequest) -> Result<IdempotencyStatus, LibError>;
+    async fn complete(
+        &self,
+        request: &IdempotentRequest,
+        response: &StoredResponse,
+    ) -> Result<(), LibError>;
+    async fn release(&self, request: &IdempotentRequest) -> Result<(), LibError>;
+}
@@ -0,0 +1,33 @@
+use crate::idempotency::model::{IdempotencyStatus, IdempotentRequest, StoredResponse};
+use crate::idempotency::repository::IdempotencyRepository;
+use crate::util::error::LibError;
+use tracing::instrument;
+
+#[derive(Debug)]
+pub struct IdempotencyUseCase(Box<dyn IdempotencyRepository>);
+
+impl IdempotencyUseCase {
+    pub fn new(repository: Box<dyn IdempotencyRepository>) -> Self {
+        IdempotencyUseCase(repository)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn begin(&self, request: &IdempotentRequest) -> Result<IdempotencyStatus, LibError> {
+        self.0.begin(request).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn complete(
+        &self,
+        request: &IdempotentRequest,
+        response: &StoredResponse,
+    ) -> Result<(), LibError> {
+        self.0.complete(request, response).await
+    }
+
+    // forgets the key so a retry runs the request again
+    #[instrument(skip_all)]
+    pub async fn release(&self, request: &IdempotentRequest) -> Result<(), LibError> {
+        self.0.release(request).await
+    }
+}
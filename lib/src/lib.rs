@@ -1,9 +1,17 @@
+#![allow(clippy::module_inception)]
+
 pub mod util {
     pub mod error;
+    pub mod list;
+    #[cfg(any(test, feature = "memory"))]
+    pub mod memory;
+    pub mod model;
     pub mod postgres;
 }
 
 pub mod user {
+    #[cfg(any(test, feature = "memory"))]
+    pub mod memory;
     pub mod model;
     pub mod repository;
     pub mod use_case;
@@ -12,6 +20,8 @@ pub mod user {
 
 pub mod role {
     pub mod entity;
+    #[cfg(any(test, feature = "memory"))]
+    pub mod memory;
     pub mod model;
     pub mod repository;
     pub mod role;
@@ -26,6 +36,8 @@ pub mod auth {
 }
 
 pub mod supplier {
+    #[cfg(any(test, feature = "memory"))]
+    pub mod memory;
     pub mod model;
     pub mod repository;
     pub mod supplier;
@@ -34,6 +46,43 @@ pub mod supplier {
 
 pub mod material {
     pub mod material;
+    #[cfg(any(test, feature = "memory"))]
+    pub mod memory;
+    pub mod model;
+    pub mod repository;
+    pub mod use_case;
+}
+
+pub mod oidc {
+    pub mod model;
+    pub mod oidc;
+    pub mod repository;
+    pub mod use_case;
+}
+
+pub mod session {
+    pub mod model;
+    pub mod repository;
+    pub mod session;
+    pub mod use_case;
+}
+
+pub mod search {
+    pub mod model;
+    pub mod repository;
+    pub mod search;
+    pub mod use_case;
+}
+
+pub mod audit {
+    pub mod audit;
+    pub mod model;
+    pub mod repository;
+    pub mod use_case;
+}
+
+pub mod idempotency {
+    pub mod idempotency;
     pub mod model;
     pub mod repository;
     pub mod use_case;
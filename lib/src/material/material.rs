@@ -1,14 +1,115 @@
+use crate::audit::audit::PgAuditLogRepository;
+use crate::audit::model::{AuditEntityType, AuditEntry};
 use crate::material::model::{
-    CreateMaterialGroup, QueryMaterialGroup, ResponseMaterialGroup, UpdateMaterialGroup,
+    CreateMaterial, CreateMaterialGroup, GroupStock, QueryMaterial, QueryMaterialGroup,
+    ResponseMaterial, ResponseMaterialGroup, StockReport, StockTotals, UpdateMaterialGroup,
 };
 use crate::material::repository::MaterialRepository;
 use crate::util::error::LibError;
+use crate::util::list::{ColumnType, ListColumn, ListSpec};
+use crate::util::model::{
+    check_version, BulkApplied, BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport,
+    ImportRow, ImportRowError, ListQuery, Page,
+};
+use crate::util::postgres::{end_bulk, end_savepoint};
 use async_trait::async_trait;
-use sqlx::postgres::Postgres;
-use sqlx::Pool;
+use sqlx::postgres::{PgConnection, Postgres};
+use sqlx::{Connection, Pool};
 use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use tracing::instrument;
 use uuid::Uuid;
 
+pub(crate) static MATERIAL_GROUP_LIST: ListSpec = ListSpec {
+    select:
+        "SELECT id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version",
+    from: " FROM material_group",
+    id_column: "id",
+    condition: "deleted_at IS NULL",
+    sort_columns: &[
+        ListColumn {
+            name: "created_at",
+            column: "created_at",
+            column_type: ColumnType::Timestamptz,
+        },
+        ListColumn {
+            name: "name",
+            column: "name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "sub_group_name",
+            column: "sub_group_name",
+            column_type: ColumnType::Text,
+        },
+    ],
+    filter_columns: &[
+        ListColumn {
+            name: "name",
+            column: "name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "sub_group_name",
+            column: "sub_group_name",
+            column_type: ColumnType::Text,
+        },
+    ],
+    search_columns: &["name", "sub_group_name"],
+};
+
+pub(crate) static MATERIAL_LIST: ListSpec = ListSpec {
+    select: "SELECT m.id, m.name, m.price, m.description, m.quantity, m.mfg_date, m.exp_date, m.supplier_id, s.name AS supplier_name, m.group_id, g.name AS group_name, m.created_at, m.updated_at",
+    from: " FROM material AS m LEFT JOIN supplier AS s ON m.supplier_id = s.id LEFT JOIN material_group AS g ON m.group_id = g.id",
+    id_column: "m.id",
+    condition: "m.deleted_at IS NULL",
+    sort_columns: &[
+        ListColumn {
+            name: "created_at",
+            column: "m.created_at",
+            column_type: ColumnType::Timestamptz,
+        },
+        ListColumn {
+            name: "name",
+            column: "m.name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "price",
+            column: "m.price",
+            column_type: ColumnType::Integer,
+        },
+        ListColumn {
+            name: "quantity",
+            column: "m.quantity",
+            column_type: ColumnType::Integer,
+        },
+        ListColumn {
+            name: "exp_date",
+            column: "m.exp_date",
+            column_type: ColumnType::Date,
+        },
+    ],
+    filter_columns: &[
+        ListColumn {
+            name: "name",
+            column: "m.name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "supplier_id",
+            column: "m.supplier_id",
+            column_type: ColumnType::Uuid,
+        },
+        ListColumn {
+            name: "group_id",
+            column: "m.group_id",
+            column_type: ColumnType::Uuid,
+        },
+    ],
+    search_columns: &["m.name", "m.description"],
+};
+
 #[derive(Debug)]
 pub struct PgMaterialRepository {
     db_connect: Arc<Pool<Postgres>>,
@@ -22,107 +123,504 @@ impl PgMaterialRepository {
 
 #[async_trait]
 impl MaterialRepository for PgMaterialRepository {
+    #[instrument(skip_all)]
     async fn create_material_group(
         &self,
         material_group: &CreateMaterialGroup,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseMaterialGroup, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let result = Self::insert_material_group(&mut tx, material_group, actor).await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_all_material_groups(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError> {
+        let page = MATERIAL_GROUP_LIST
+            .fetch::<QueryMaterialGroup>(self.db_connect.as_ref(), query)
+            .await?;
+        Ok(page.map(|query| query.to_response_material_group()))
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn get_material_group_by_id(&self, id: Uuid) -> Result<ResponseMaterialGroup, LibError> {
         let query = sqlx::query_as!(
             QueryMaterialGroup,
             r#"
-            INSERT INTO material_group (name, sub_group_name)
-            VALUES ($1, $2)
-            RETURNING *
+            SELECT id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version FROM material_group WHERE id = $1 AND deleted_at IS NULL
             "#,
-            material_group.name,
-            material_group.sub_group_name
+            id
         )
         .fetch_one(self.db_connect.clone().as_ref())
         .await?;
         Ok(query.to_response_material_group())
     }
 
-    async fn get_all_material_groups(&self) -> Result<Vec<ResponseMaterialGroup>, LibError> {
+    #[instrument(skip_all)]
+    async fn get_sub_group_by_group_name(
+        &self,
+        group_name: &str,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError> {
+        let mut query = query.clone();
+        query
+            .filters
+            .push(("name".to_string(), group_name.to_string()));
+        let page = MATERIAL_GROUP_LIST
+            .fetch::<QueryMaterialGroup>(self.db_connect.as_ref(), &query)
+            .await?;
+        Ok(page.map(|query| query.to_response_material_group()))
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn delete_material_group_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let deleted = Self::soft_delete_material_group(&mut tx, id, version, actor).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn update_material_group_by_id(
+        &self,
+        id: &Uuid,
+        material_group: &UpdateMaterialGroup,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let result =
+            Self::update_material_group(&mut tx, id, material_group, version, actor).await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_deleted_material_groups(
+        &self,
+    ) -> Result<Vec<DeletedRecord<ResponseMaterialGroup>>, LibError> {
         let query = sqlx::query_as!(
             QueryMaterialGroup,
             r#"
-            SELECT * FROM material_group
+            SELECT id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version FROM material_group
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC
             "#
         )
         .fetch_all(self.db_connect.clone().as_ref())
         .await?;
-        let result: Vec<ResponseMaterialGroup> = query
-            .into_iter()
-            .map(|query: QueryMaterialGroup| query.to_response_material_group())
+        let result = query
+            .iter()
+            .map(|query: &QueryMaterialGroup| query.to_deleted_material_group())
             .collect();
         Ok(result)
     }
 
-    async fn get_material_group_by_id(&self, id: Uuid) -> Result<ResponseMaterialGroup, LibError> {
+    #[instrument(skip_all, fields(%id))]
+    async fn restore_material_group_by_id(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        let mut tx = self.db_connect.begin().await?;
         let query = sqlx::query_as!(
             QueryMaterialGroup,
             r#"
-            SELECT * FROM material_group WHERE id = $1
+            UPDATE material_group
+            SET deleted_at = NULL,
+                deleted_by = NULL,
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version
             "#,
             id
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *tx)
         .await?;
-        Ok(query.to_response_material_group())
+        let result = query.to_response_material_group();
+        PgAuditLogRepository::record(
+            &mut tx,
+            &AuditEntry::restored(actor, AuditEntityType::MaterialGroup, result.id, &result),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(result)
     }
 
-    async fn get_sub_group_by_group_name(
+    #[instrument(skip_all)]
+    async fn import_materials(
         &self,
-        group_name: &str,
-    ) -> Result<Vec<ResponseMaterialGroup>, LibError> {
-        let query = sqlx::query_as!(
-            QueryMaterialGroup,
-            r#"
-            SELECT * FROM material_group WHERE name = $1
-            "#,
-            group_name
+        rows: &[ImportRow<CreateMaterial>],
+        dry_run: bool,
+        actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let errors = Self::check_references(&mut tx, rows).await?;
+        if dry_run || !errors.is_empty() {
+            return Ok(ImportReport {
+                dry_run,
+                total: rows.len(),
+                imported: 0,
+                errors,
+            });
+        }
+        for row in rows {
+            let material = &row.record;
+            let id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO material (name, price, description, quantity, mfg_date, exp_date, supplier_id, group_id)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id
+                "#,
+                material.name,
+                material.price,
+                material.description,
+                material.quantity,
+                material.mfg_date,
+                material.exp_date,
+                material.supplier_id,
+                material.group_id
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            PgAuditLogRepository::record(
+                &mut tx,
+                &AuditEntry::created(actor, AuditEntityType::Material, id, material),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(ImportReport {
+            dry_run,
+            total: rows.len(),
+            imported: rows.len(),
+            errors,
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn bulk_material_groups(
+        &self,
+        operations: &[BulkOperation<CreateMaterialGroup, UpdateMaterialGroup>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseMaterialGroup>, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let mut outcomes = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let mut savepoint = Connection::begin(&mut *tx).await?;
+            let outcome = match operation {
+                BulkOperation::Create(material_group) => {
+                    Self::insert_material_group(&mut savepoint, material_group, actor)
+                        .await
+                        .map(BulkApplied::Created)
+                }
+                BulkOperation::Update {
+                    id,
+                    version,
+                    record,
+                } => Self::update_material_group(&mut savepoint, id, record, *version, actor)
+                    .await
+                    .map(BulkApplied::Updated),
+                BulkOperation::Delete { id, version } => {
+                    match Self::soft_delete_material_group(&mut savepoint, id, *version, actor)
+                        .await
+                    {
+                        Ok(true) => Ok(BulkApplied::Deleted(*id)),
+                        Ok(false) => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+            end_savepoint(savepoint, &outcome).await?;
+            outcomes.push(outcome);
+        }
+        end_bulk(tx, mode, outcomes).await
+    }
+
+    #[instrument(skip_all)]
+    async fn export_material_groups(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterialGroup>,
+    ) -> Result<(), LibError> {
+        MATERIAL_GROUP_LIST
+            .export(
+                self.db_connect.as_ref(),
+                query,
+                |query: QueryMaterialGroup| query.to_response_material_group(),
+                sender,
+            )
+            .await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_all_materials(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterial>, LibError> {
+        let page = MATERIAL_LIST
+            .fetch::<QueryMaterial>(self.db_connect.as_ref(), query)
+            .await?;
+        Ok(page.map(|query| query.to_response_material()))
+    }
+
+    #[instrument(skip_all)]
+    async fn export_materials(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterial>,
+    ) -> Result<(), LibError> {
+        MATERIAL_LIST
+            .export(
+                self.db_connect.as_ref(),
+                query,
+                |query: QueryMaterial| query.to_response_material(),
+                sender,
+            )
+            .await
+    }
+
+    #[instrument(skip_all)]
+    async fn get_stock_report(&self, low_stock_threshold: i32) -> Result<StockReport, LibError> {
+        let groups = sqlx::query_as!(
+            GroupStock,
+            r#"SELECT g.name AS "group_name?", count(*) AS "materials!",
+                coalesce(sum(m.quantity), 0)::bigint AS "quantity!",
+                coalesce(sum(m.price::bigint * m.quantity), 0)::bigint AS "value!"
+            FROM material AS m LEFT JOIN material_group AS g ON m.group_id = g.id
+            WHERE m.deleted_at IS NULL
+            GROUP BY g.name
+            ORDER BY g.name NULLS LAST"#
         )
-        .fetch_all(self.db_connect.clone().as_ref())
+        .fetch_all(self.db_connect.as_ref())
         .await?;
-        let result: Vec<ResponseMaterialGroup> = query
-            .into_iter()
-            .map(|query: QueryMaterialGroup| query.to_response_material_group())
-            .collect();
-        Ok(result)
+        let low_stock = sqlx::query_as::<_, QueryMaterial>(&format!(
+            "{}{} WHERE {} AND m.quantity <= $1 ORDER BY m.quantity, m.name",
+            MATERIAL_LIST.select, MATERIAL_LIST.from, MATERIAL_LIST.condition
+        ))
+        .bind(low_stock_threshold)
+        .fetch_all(self.db_connect.as_ref())
+        .await?;
+        Ok(StockReport::new(
+            groups,
+            low_stock_threshold,
+            low_stock
+                .iter()
+                .map(|query| query.to_response_material())
+                .collect(),
+        ))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_stock_totals(&self, low_stock_threshold: i32) -> Result<StockTotals, LibError> {
+        let totals = sqlx::query_as!(
+            StockTotals,
+            r#"SELECT count(*) AS "materials!",
+                coalesce(sum(price::bigint * quantity), 0)::bigint AS "value!",
+                count(*) FILTER (WHERE quantity <= $1) AS "low_stock!"
+            FROM material
+            WHERE deleted_at IS NULL"#,
+            low_stock_threshold
+        )
+        .fetch_one(self.db_connect.as_ref())
+        .await?;
+        Ok(totals)
     }
+}
 
-    async fn delete_material_group_by_id(&self, id: &Uuid) -> Result<bool, LibError> {
-        let query = sqlx::query!(
+impl PgMaterialRepository {
+    pub async fn insert_material_group(
+        db_connect: &mut PgConnection,
+        material_group: &CreateMaterialGroup,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        let query = sqlx::query_as!(
+            QueryMaterialGroup,
             r#"
-            DELETE FROM material_group WHERE id = $1
+            INSERT INTO material_group (name, sub_group_name)
+            VALUES ($1, $2)
+            RETURNING id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version
             "#,
-            &id
+            material_group.name,
+            material_group.sub_group_name
+        )
+        .fetch_one(&mut *db_connect)
+        .await?;
+        let result = query.to_response_material_group();
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::created(actor, AuditEntityType::MaterialGroup, result.id, &result),
         )
-        .execute(self.db_connect.clone().as_ref())
         .await?;
-        Ok(query.rows_affected() > 0)
+        Ok(result)
     }
 
-    async fn update_material_group_by_id(
-        &self,
+    pub async fn update_material_group(
+        db_connect: &mut PgConnection,
         id: &Uuid,
         material_group: &UpdateMaterialGroup,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseMaterialGroup, LibError> {
+        let before = sqlx::query_as!(
+            QueryMaterialGroup,
+            r#"
+            SELECT id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version FROM material_group WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_one(&mut *db_connect)
+        .await?
+        .to_response_material_group();
+        check_version(version, before.version)?;
         let query = sqlx::query_as!(
             QueryMaterialGroup,
             r#"
             UPDATE material_group
             SET name = COALESCE($1, name),
                 sub_group_name = COALESCE($2, sub_group_name),
-                updated_at = now()
-            WHERE id = $3
-            RETURNING *
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $3 AND deleted_at IS NULL
+            RETURNING id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version
             "#,
             material_group.name,
             material_group.sub_group_name,
             id
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *db_connect)
         .await?;
-        Ok(query.to_response_material_group())
+        let result = query.to_response_material_group();
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::updated(
+                actor,
+                AuditEntityType::MaterialGroup,
+                result.id,
+                &before,
+                &result,
+            ),
+        )
+        .await?;
+        Ok(result)
+    }
+
+    pub async fn soft_delete_material_group(
+        db_connect: &mut PgConnection,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let query = sqlx::query_as!(
+            QueryMaterialGroup,
+            r#"
+            UPDATE material_group
+            SET deleted_at = now(),
+                deleted_by = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, name, sub_group_name, created_at, updated_at, deleted_at, deleted_by, version
+            "#,
+            &id,
+            actor
+        )
+        .fetch_optional(&mut *db_connect)
+        .await?;
+        let Some(deleted) = query else {
+            return Ok(false);
+        };
+        check_version(version, deleted.version)?;
+        if Self::has_materials(db_connect, id).await? {
+            return Err(LibError::Conflict(
+                "Material group is still referenced by materials".to_string(),
+            ));
+        }
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::deleted(
+                actor,
+                AuditEntityType::MaterialGroup,
+                deleted.id,
+                &deleted.to_response_material_group(),
+            ),
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn has_materials(db_connect: &mut PgConnection, id: &Uuid) -> Result<bool, LibError> {
+        let query = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM material WHERE group_id = $1 AND deleted_at IS NULL
+            ) as "exists!"
+            "#,
+            id
+        )
+        .fetch_one(db_connect)
+        .await?;
+        Ok(query)
+    }
+
+    // suppliers and groups referenced by the rows must exist and not be deleted,
+    // they stay locked until the import commits so they cannot be deleted underneath it
+    async fn check_references(
+        db_connect: &mut PgConnection,
+        rows: &[ImportRow<CreateMaterial>],
+    ) -> Result<Vec<ImportRowError>, LibError> {
+        let supplier_ids: Vec<Uuid> = rows
+            .iter()
+            .filter_map(|row| row.record.supplier_id)
+            .collect();
+        let group_ids: Vec<Uuid> = rows.iter().filter_map(|row| row.record.group_id).collect();
+        let suppliers = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM supplier
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            FOR SHARE
+            "#,
+            &supplier_ids
+        )
+        .fetch_all(&mut *db_connect)
+        .await?;
+        let groups = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM material_group
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            FOR SHARE
+            "#,
+            &group_ids
+        )
+        .fetch_all(&mut *db_connect)
+        .await?;
+        let mut errors = Vec::new();
+        for row in rows {
+            if let Some(id) = row.record.supplier_id.filter(|id| !suppliers.contains(id)) {
+                errors.push(ImportRowError {
+                    row: row.row,
+                    field: Some("supplier_id".to_string()),
+                    message: format!("Supplier {id} not found"),
+                });
+            }
+            if let Some(id) = row.record.group_id.filter(|id| !groups.contains(id)) {
+                errors.push(ImportRowError {
+                    row: row.row,
+                    field: Some("group_id".to_string()),
+                    message: format!("Material group {id} not found"),
+                });
+            }
+        }
+        Ok(errors)
     }
 }
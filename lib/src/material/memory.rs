@@ -0,0 +1,533 @@
+use crate::material::material::{MATERIAL_GROUP_LIST, MATERIAL_LIST};
+use crate::material::model::{
+    CreateMaterial, CreateMaterialGroup, GroupStock, ResponseMaterial, ResponseMaterialGroup,
+    StockReport, StockTotals, UpdateMaterialGroup,
+};
+use crate::material::repository::MaterialRepository;
+use crate::util::error::LibError;
+use crate::util::memory::{not_found, timestamp_key, ListRecord, MemoryDb, MemoryTables};
+use crate::util::model::{
+    check_version, BulkApplied, BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport,
+    ImportRow, ImportRowError, ListQuery, Page,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MaterialGroupRow {
+    pub id: Uuid,
+    pub name: String,
+    pub sub_group_name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub version: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+}
+
+impl MaterialGroupRow {
+    fn to_response_material_group(&self) -> ResponseMaterialGroup {
+        ResponseMaterialGroup {
+            id: self.id,
+            name: self.name.clone(),
+            sub_group_name: self.sub_group_name.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            version: self.version,
+        }
+    }
+}
+
+impl ListRecord for MaterialGroupRow {
+    fn list_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn list_value(&self, name: &str) -> Option<String> {
+        match name {
+            "created_at" => Some(timestamp_key(&self.created_at)),
+            "name" => Some(self.name.clone()),
+            "sub_group_name" => Some(self.sub_group_name.clone()),
+            _ => None,
+        }
+    }
+
+    fn search_values(&self) -> Vec<&str> {
+        vec![&self.name, &self.sub_group_name]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MaterialRow {
+    pub id: Uuid,
+    pub name: String,
+    pub price: i32,
+    pub description: String,
+    pub quantity: i32,
+    pub mfg_date: NaiveDate,
+    pub exp_date: NaiveDate,
+    pub supplier_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// a material joined with the names of its supplier and group
+#[derive(Debug)]
+pub(crate) struct MaterialView(ResponseMaterial);
+
+impl ListRecord for MaterialView {
+    fn list_id(&self) -> Uuid {
+        self.0.id
+    }
+
+    fn list_value(&self, name: &str) -> Option<String> {
+        match name {
+            "created_at" => Some(timestamp_key(&self.0.created_at)),
+            "name" => Some(self.0.name.clone()),
+            "price" => Some(self.0.price.to_string()),
+            "quantity" => Some(self.0.quantity.to_string()),
+            "exp_date" => Some(self.0.exp_date.to_string()),
+            "supplier_id" => self.0.supplier_id.map(|id| id.to_string()),
+            "group_id" => self.0.group_id.map(|id| id.to_string()),
+            _ => None,
+        }
+    }
+
+    fn search_values(&self) -> Vec<&str> {
+        vec![&self.0.name, &self.0.description]
+    }
+}
+
+impl MemoryTables {
+    fn active_material_group_mut(&mut self, id: &Uuid) -> Option<&mut MaterialGroupRow> {
+        self.material_groups
+            .iter_mut()
+            .find(|group| group.id == *id && group.deleted_at.is_none())
+    }
+
+    fn material_view(&self, row: &MaterialRow) -> MaterialView {
+        let supplier = row
+            .supplier_id
+            .and_then(|id| self.suppliers.iter().find(|supplier| supplier.id == id));
+        let group = row
+            .group_id
+            .and_then(|id| self.material_groups.iter().find(|group| group.id == id));
+        MaterialView(ResponseMaterial {
+            id: row.id,
+            name: row.name.clone(),
+            price: row.price,
+            description: row.description.clone(),
+            quantity: row.quantity,
+            mfg_date: row.mfg_date,
+            exp_date: row.exp_date,
+            supplier_id: row.supplier_id,
+            supplier_name: supplier.map(|supplier| supplier.name.clone()),
+            group_id: row.group_id,
+            group_name: group.map(|group| group.name.clone()),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+// MaterialRepository without a database, see MemoryDb
+#[derive(Debug)]
+pub struct MemoryMaterialRepository {
+    db: Arc<MemoryDb>,
+}
+
+impl MemoryMaterialRepository {
+    pub fn new(db: Arc<MemoryDb>) -> Self {
+        Self { db }
+    }
+
+    fn insert_material_group(
+        tables: &mut MemoryTables,
+        material_group: &CreateMaterialGroup,
+    ) -> ResponseMaterialGroup {
+        let now = Utc::now();
+        let row = MaterialGroupRow {
+            id: Uuid::new_v4(),
+            name: material_group.name.clone(),
+            sub_group_name: material_group.sub_group_name.clone(),
+            created_at: now,
+            updated_at: now,
+            version: 1,
+            deleted_at: None,
+            deleted_by: None,
+        };
+        let result = row.to_response_material_group();
+        tables.material_groups.push(row);
+        result
+    }
+
+    fn update_material_group(
+        tables: &mut MemoryTables,
+        id: &Uuid,
+        material_group: &UpdateMaterialGroup,
+        version: Option<i32>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        let row = tables.active_material_group_mut(id).ok_or_else(not_found)?;
+        check_version(version, row.version)?;
+        if let Some(name) = &material_group.name {
+            row.name = name.clone();
+        }
+        if let Some(sub_group_name) = &material_group.sub_group_name {
+            row.sub_group_name = sub_group_name.clone();
+        }
+        row.updated_at = Utc::now();
+        row.version += 1;
+        Ok(row.to_response_material_group())
+    }
+
+    fn soft_delete_material_group(
+        tables: &mut MemoryTables,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let has_materials = tables
+            .materials
+            .iter()
+            .any(|material| material.group_id == Some(*id));
+        let Some(row) = tables.active_material_group_mut(id) else {
+            return Ok(false);
+        };
+        check_version(version, row.version)?;
+        if has_materials {
+            return Err(LibError::Conflict(
+                "Material group is still referenced by materials".to_string(),
+            ));
+        }
+        row.deleted_at = Some(Utc::now());
+        row.deleted_by = actor.copied();
+        Ok(true)
+    }
+
+    // suppliers and groups referenced by the rows must exist and not be deleted
+    fn check_references(
+        tables: &MemoryTables,
+        rows: &[ImportRow<CreateMaterial>],
+    ) -> Vec<ImportRowError> {
+        let mut errors = Vec::new();
+        for row in rows {
+            if let Some(id) = row
+                .record
+                .supplier_id
+                .filter(|id| tables.active_supplier(id).is_none())
+            {
+                errors.push(ImportRowError {
+                    row: row.row,
+                    field: Some("supplier_id".to_string()),
+                    message: format!("Supplier {id} not found"),
+                });
+            }
+            if let Some(id) = row.record.group_id.filter(|id| {
+                !tables
+                    .material_groups
+                    .iter()
+                    .any(|group| group.id == *id && group.deleted_at.is_none())
+            }) {
+                errors.push(ImportRowError {
+                    row: row.row,
+                    field: Some("group_id".to_string()),
+                    message: format!("Material group {id} not found"),
+                });
+            }
+        }
+        errors
+    }
+
+    fn active_group_rows(&self) -> Vec<MaterialGroupRow> {
+        self.db.read(|tables| {
+            tables
+                .material_groups
+                .iter()
+                .filter(|group| group.deleted_at.is_none())
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn material_views(&self) -> Vec<MaterialView> {
+        self.db.read(|tables| {
+            tables
+                .materials
+                .iter()
+                .map(|material| tables.material_view(material))
+                .collect()
+        })
+    }
+}
+
+#[async_trait]
+impl MaterialRepository for MemoryMaterialRepository {
+    async fn create_material_group(
+        &self,
+        material_group: &CreateMaterialGroup,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        self.db
+            .transaction(|tables| Ok(Self::insert_material_group(tables, material_group)))
+    }
+
+    async fn get_all_material_groups(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError> {
+        let page = MATERIAL_GROUP_LIST.fetch_memory(self.active_group_rows(), query)?;
+        Ok(page.map(|row| row.to_response_material_group()))
+    }
+
+    async fn get_material_group_by_id(&self, id: Uuid) -> Result<ResponseMaterialGroup, LibError> {
+        self.db.read(|tables| {
+            tables
+                .material_groups
+                .iter()
+                .find(|group| group.id == id && group.deleted_at.is_none())
+                .map(MaterialGroupRow::to_response_material_group)
+                .ok_or_else(not_found)
+        })
+    }
+
+    async fn get_sub_group_by_group_name(
+        &self,
+        group_name: &str,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError> {
+        let mut query = query.clone();
+        query
+            .filters
+            .push(("name".to_string(), group_name.to_string()));
+        self.get_all_material_groups(&query).await
+    }
+
+    async fn delete_material_group_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        self.db
+            .transaction(|tables| Self::soft_delete_material_group(tables, id, version, actor))
+    }
+
+    async fn update_material_group_by_id(
+        &self,
+        id: &Uuid,
+        material_group: &UpdateMaterialGroup,
+        version: Option<i32>,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        self.db
+            .transaction(|tables| Self::update_material_group(tables, id, material_group, version))
+    }
+
+    async fn get_deleted_material_groups(
+        &self,
+    ) -> Result<Vec<DeletedRecord<ResponseMaterialGroup>>, LibError> {
+        let mut deleted: Vec<DeletedRecord<ResponseMaterialGroup>> = self.db.read(|tables| {
+            tables
+                .material_groups
+                .iter()
+                .filter_map(|row| {
+                    Some(DeletedRecord {
+                        record: row.to_response_material_group(),
+                        deleted_at: row.deleted_at?,
+                        deleted_by: row.deleted_by,
+                    })
+                })
+                .collect()
+        });
+        deleted.sort_by_key(|record| std::cmp::Reverse(record.deleted_at));
+        Ok(deleted)
+    }
+
+    async fn restore_material_group_by_id(
+        &self,
+        id: &Uuid,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        self.db.transaction(|tables| {
+            let row = tables
+                .material_groups
+                .iter_mut()
+                .find(|row| row.id == *id && row.deleted_at.is_some())
+                .ok_or_else(not_found)?;
+            row.deleted_at = None;
+            row.deleted_by = None;
+            row.updated_at = Utc::now();
+            row.version += 1;
+            Ok(row.to_response_material_group())
+        })
+    }
+
+    async fn import_materials(
+        &self,
+        rows: &[ImportRow<CreateMaterial>],
+        dry_run: bool,
+        _actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError> {
+        self.db.transaction(|tables| {
+            let errors = Self::check_references(tables, rows);
+            if dry_run || !errors.is_empty() {
+                return Ok(ImportReport {
+                    dry_run,
+                    total: rows.len(),
+                    imported: 0,
+                    errors,
+                });
+            }
+            let now = Utc::now();
+            for row in rows {
+                let material = &row.record;
+                tables.materials.push(MaterialRow {
+                    id: Uuid::new_v4(),
+                    name: material.name.clone(),
+                    price: material.price,
+                    description: material.description.clone(),
+                    quantity: material.quantity,
+                    mfg_date: material.mfg_date,
+                    exp_date: material.exp_date,
+                    supplier_id: material.supplier_id,
+                    group_id: material.group_id,
+                    created_at: now,
+                    updated_at: now,
+                });
+            }
+            Ok(ImportReport {
+                dry_run,
+                total: rows.len(),
+                imported: rows.len(),
+                errors,
+            })
+        })
+    }
+
+    async fn bulk_material_groups(
+        &self,
+        operations: &[BulkOperation<CreateMaterialGroup, UpdateMaterialGroup>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseMaterialGroup>, LibError> {
+        Ok(self
+            .db
+            .bulk(operations, mode, |tables, operation| match operation {
+                BulkOperation::Create(material_group) => Ok(BulkApplied::Created(
+                    Self::insert_material_group(tables, material_group),
+                )),
+                BulkOperation::Update {
+                    id,
+                    version,
+                    record,
+                } => Self::update_material_group(tables, id, record, *version)
+                    .map(BulkApplied::Updated),
+                BulkOperation::Delete { id, version } => {
+                    match Self::soft_delete_material_group(tables, id, *version, actor)? {
+                        true => Ok(BulkApplied::Deleted(*id)),
+                        false => Err(not_found()),
+                    }
+                }
+            }))
+    }
+
+    async fn export_material_groups(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterialGroup>,
+    ) -> Result<(), LibError> {
+        MATERIAL_GROUP_LIST
+            .export_memory(
+                self.active_group_rows(),
+                query,
+                |row| row.to_response_material_group(),
+                sender,
+            )
+            .await
+    }
+
+    async fn get_all_materials(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterial>, LibError> {
+        let page = MATERIAL_LIST.fetch_memory(self.material_views(), query)?;
+        Ok(page.map(|view| view.0))
+    }
+
+    async fn export_materials(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterial>,
+    ) -> Result<(), LibError> {
+        MATERIAL_LIST
+            .export_memory(self.material_views(), query, |view| view.0, sender)
+            .await
+    }
+
+    async fn get_stock_report(&self, low_stock_threshold: i32) -> Result<StockReport, LibError> {
+        let materials: Vec<ResponseMaterial> = self
+            .material_views()
+            .into_iter()
+            .map(|view| view.0)
+            .collect();
+        let mut groups: Vec<GroupStock> = Vec::new();
+        for material in &materials {
+            let index = match groups
+                .iter()
+                .position(|group| group.group_name == material.group_name)
+            {
+                Some(index) => index,
+                None => {
+                    groups.push(GroupStock {
+                        group_name: material.group_name.clone(),
+                        materials: 0,
+                        quantity: 0,
+                        value: 0,
+                    });
+                    groups.len() - 1
+                }
+            };
+            let group = &mut groups[index];
+            group.materials += 1;
+            group.quantity += i64::from(material.quantity);
+            group.value += i64::from(material.price) * i64::from(material.quantity);
+        }
+        // like Postgres, materials without a group come last
+        groups.sort_by(|a, b| {
+            (a.group_name.is_none(), &a.group_name).cmp(&(b.group_name.is_none(), &b.group_name))
+        });
+        let mut low_stock: Vec<ResponseMaterial> = materials
+            .into_iter()
+            .filter(|material| material.quantity <= low_stock_threshold)
+            .collect();
+        low_stock.sort_by(|a, b| {
+            a.quantity
+                .cmp(&b.quantity)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(StockReport::new(groups, low_stock_threshold, low_stock))
+    }
+
+    async fn get_stock_totals(&self, low_stock_threshold: i32) -> Result<StockTotals, LibError> {
+        let materials: Vec<ResponseMaterial> = self
+            .material_views()
+            .into_iter()
+            .map(|view| view.0)
+            .collect();
+        Ok(StockTotals {
+            materials: materials.len() as i64,
+            value: materials
+                .iter()
+                .map(|material| i64::from(material.price) * i64::from(material.quantity))
+                .sum(),
+            low_stock: materials
+                .iter()
+                .filter(|material| material.quantity <= low_stock_threshold)
+                .count() as i64,
+        })
+    }
+}
@@ -1,8 +1,10 @@
+use crate::util::model::DeletedRecord;
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryMaterialGroup {
     pub id: Uuid,
@@ -10,6 +12,9 @@ pub struct QueryMaterialGroup {
     pub sub_group_name: String,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+    pub version: i32,
 }
 
 impl QueryMaterialGroup {
@@ -20,6 +25,15 @@ impl QueryMaterialGroup {
             sub_group_name: self.sub_group_name.clone(),
             created_at: self.created_at.unwrap(),
             updated_at: self.updated_at.unwrap(),
+            version: self.version,
+        }
+    }
+
+    pub fn to_deleted_material_group(&self) -> DeletedRecord<ResponseMaterialGroup> {
+        DeletedRecord {
+            record: self.to_response_material_group(),
+            deleted_at: self.deleted_at.unwrap_or_default(),
+            deleted_by: self.deleted_by,
         }
     }
 }
@@ -38,11 +52,126 @@ pub struct UpdateMaterialGroup {
     pub sub_group_name: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResponseMaterialGroup {
     pub id: Uuid,
     pub name: String,
     pub sub_group_name: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    // bumped on every change, sent back as ETag
+    pub version: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMaterial {
+    pub name: String,
+    pub price: i32,
+    pub description: String,
+    pub quantity: i32,
+    pub mfg_date: NaiveDate,
+    pub exp_date: NaiveDate,
+    pub supplier_id: Option<Uuid>,
+    pub group_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct QueryMaterial {
+    pub id: Uuid,
+    pub name: String,
+    pub price: i32,
+    pub description: String,
+    pub quantity: i32,
+    pub mfg_date: NaiveDate,
+    pub exp_date: NaiveDate,
+    pub supplier_id: Option<Uuid>,
+    pub supplier_name: Option<String>,
+    pub group_id: Option<Uuid>,
+    pub group_name: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl QueryMaterial {
+    pub fn to_response_material(&self) -> ResponseMaterial {
+        ResponseMaterial {
+            id: self.id,
+            name: self.name.clone(),
+            price: self.price,
+            description: self.description.clone(),
+            quantity: self.quantity,
+            mfg_date: self.mfg_date,
+            exp_date: self.exp_date,
+            supplier_id: self.supplier_id,
+            supplier_name: self.supplier_name.clone(),
+            group_id: self.group_id,
+            group_name: self.group_name.clone(),
+            created_at: self.created_at.unwrap_or_default(),
+            updated_at: self.updated_at.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ResponseMaterial {
+    pub id: Uuid,
+    pub name: String,
+    pub price: i32,
+    pub description: String,
+    pub quantity: i32,
+    pub mfg_date: NaiveDate,
+    pub exp_date: NaiveDate,
+    pub supplier_id: Option<Uuid>,
+    pub supplier_name: Option<String>,
+    pub group_id: Option<Uuid>,
+    pub group_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// stock held per material group, `group_name` is None for materials without a group
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct GroupStock {
+    pub group_name: Option<String>,
+    pub materials: i64,
+    pub quantity: i64,
+    pub value: i64,
+}
+
+// the totals of a StockReport without the materials behind them
+#[derive(Debug, sqlx::FromRow)]
+pub struct StockTotals {
+    pub materials: i64,
+    pub value: i64,
+    pub low_stock: i64,
+}
+
+// what is in stock and what is running out, value is price times quantity
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StockReport {
+    pub materials: i64,
+    pub quantity: i64,
+    pub value: i64,
+    pub groups: Vec<GroupStock>,
+    // materials whose quantity is at or below the threshold, fewest first
+    pub low_stock_threshold: i32,
+    pub low_stock: Vec<ResponseMaterial>,
+}
+
+impl StockReport {
+    pub fn new(
+        groups: Vec<GroupStock>,
+        low_stock_threshold: i32,
+        low_stock: Vec<ResponseMaterial>,
+    ) -> Self {
+        Self {
+            materials: groups.iter().map(|group| group.materials).sum(),
+            quantity: groups.iter().map(|group| group.quantity).sum(),
+            value: groups.iter().map(|group| group.value).sum(),
+            groups,
+            low_stock_threshold,
+            low_stock,
+        }
+    }
 }
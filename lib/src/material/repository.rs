@@ -1,6 +1,13 @@
-use crate::material::model::{CreateMaterialGroup, ResponseMaterialGroup, UpdateMaterialGroup};
+use crate::material::model::{
+    CreateMaterial, CreateMaterialGroup, ResponseMaterial, ResponseMaterialGroup, StockReport,
+    StockTotals, UpdateMaterialGroup,
+};
 use crate::util::error::LibError;
+use crate::util::model::{
+    BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport, ImportRow, ListQuery, Page,
+};
 use async_trait::async_trait;
+use tokio::sync::mpsc::Sender;
 use uuid::Uuid;
 
 #[async_trait]
@@ -8,17 +15,65 @@ pub trait MaterialRepository: Send + Sync + std::fmt::Debug {
     async fn create_material_group(
         &self,
         material_group: &CreateMaterialGroup,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseMaterialGroup, LibError>;
-    async fn get_all_material_groups(&self) -> Result<Vec<ResponseMaterialGroup>, LibError>;
+    async fn get_all_material_groups(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError>;
     async fn get_material_group_by_id(&self, id: Uuid) -> Result<ResponseMaterialGroup, LibError>;
     async fn get_sub_group_by_group_name(
         &self,
         group_name: &str,
-    ) -> Result<Vec<ResponseMaterialGroup>, LibError>;
-    async fn delete_material_group_by_id(&self, id: &Uuid) -> Result<bool, LibError>;
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError>;
+    async fn delete_material_group_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError>;
     async fn update_material_group_by_id(
         &self,
         id: &Uuid,
         material_group: &UpdateMaterialGroup,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError>;
+    async fn get_deleted_material_groups(
+        &self,
+    ) -> Result<Vec<DeletedRecord<ResponseMaterialGroup>>, LibError>;
+    async fn restore_material_group_by_id(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseMaterialGroup, LibError>;
+    async fn import_materials(
+        &self,
+        rows: &[ImportRow<CreateMaterial>],
+        dry_run: bool,
+        actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError>;
+    async fn bulk_material_groups(
+        &self,
+        operations: &[BulkOperation<CreateMaterialGroup, UpdateMaterialGroup>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseMaterialGroup>, LibError>;
+    async fn export_material_groups(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterialGroup>,
+    ) -> Result<(), LibError>;
+    async fn get_all_materials(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterial>, LibError>;
+    async fn export_materials(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterial>,
+    ) -> Result<(), LibError>;
+    async fn get_stock_report(&self, low_stock_threshold: i32) -> Result<StockReport, LibError>;
+    async fn get_stock_totals(&self, low_stock_threshold: i32) -> Result<StockTotals, LibError>;
 }
@@ -1,6 +1,14 @@
-use crate::material::model::{CreateMaterialGroup, ResponseMaterialGroup, UpdateMaterialGroup};
+use crate::material::model::{
+    CreateMaterial, CreateMaterialGroup, ResponseMaterial, ResponseMaterialGroup, StockReport,
+    StockTotals, UpdateMaterialGroup,
+};
 use crate::material::repository::MaterialRepository;
 use crate::util::error::LibError;
+use crate::util::model::{
+    BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport, ImportRow, ListQuery, Page,
+};
+use tokio::sync::mpsc::Sender;
+use tracing::instrument;
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -11,17 +19,24 @@ impl MaterialUseCase {
         MaterialUseCase(repository)
     }
 
+    #[instrument(skip_all)]
     pub async fn create_material_group(
         &self,
         material_group: &CreateMaterialGroup,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseMaterialGroup, LibError> {
-        self.0.create_material_group(material_group).await
+        self.0.create_material_group(material_group, actor).await
     }
 
-    pub async fn get_all_material_groups(&self) -> Result<Vec<ResponseMaterialGroup>, LibError> {
-        self.0.get_all_material_groups().await
+    #[instrument(skip_all)]
+    pub async fn get_all_material_groups(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError> {
+        self.0.get_all_material_groups(query).await
     }
 
+    #[instrument(skip_all, fields(%id))]
     pub async fn get_material_group_by_id(
         &self,
         id: Uuid,
@@ -29,15 +44,23 @@ impl MaterialUseCase {
         self.0.get_material_group_by_id(id).await
     }
 
+    #[instrument(skip_all)]
     pub async fn get_sub_group_by_group_name(
         &self,
         group_name: &str,
-    ) -> Result<Vec<ResponseMaterialGroup>, LibError> {
-        self.0.get_sub_group_by_group_name(group_name).await
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterialGroup>, LibError> {
+        self.0.get_sub_group_by_group_name(group_name, query).await
     }
 
-    pub async fn delete_material_group_by_id(&self, id: &Uuid) -> Result<bool, LibError> {
-        match self.0.delete_material_group_by_id(id).await {
+    #[instrument(skip_all, fields(%id))]
+    pub async fn delete_material_group_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        match self.0.delete_material_group_by_id(id, version, actor).await {
             Ok(r) => match r {
                 true => Ok(true),
                 false => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
@@ -46,11 +69,417 @@ impl MaterialUseCase {
         }
     }
 
+    #[instrument(skip_all, fields(%id))]
     pub async fn update_material_group_by_id(
         &self,
         id: &Uuid,
         material_group: &UpdateMaterialGroup,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseMaterialGroup, LibError> {
+        self.0
+            .update_material_group_by_id(id, material_group, version, actor)
+            .await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_deleted_material_groups(
+        &self,
+    ) -> Result<Vec<DeletedRecord<ResponseMaterialGroup>>, LibError> {
+        self.0.get_deleted_material_groups().await
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    pub async fn restore_material_group_by_id(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseMaterialGroup, LibError> {
-        self.0.update_material_group_by_id(id, material_group).await
+        self.0.restore_material_group_by_id(id, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn import_materials(
+        &self,
+        rows: &[ImportRow<CreateMaterial>],
+        dry_run: bool,
+        actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError> {
+        self.0.import_materials(rows, dry_run, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn bulk_material_groups(
+        &self,
+        operations: &[BulkOperation<CreateMaterialGroup, UpdateMaterialGroup>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseMaterialGroup>, LibError> {
+        self.0.bulk_material_groups(operations, mode, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn export_material_groups(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterialGroup>,
+    ) -> Result<(), LibError> {
+        self.0.export_material_groups(query, sender).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_all_materials(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseMaterial>, LibError> {
+        self.0.get_all_materials(query).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn export_materials(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseMaterial>,
+    ) -> Result<(), LibError> {
+        self.0.export_materials(query, sender).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_stock_report(
+        &self,
+        low_stock_threshold: i32,
+    ) -> Result<StockReport, LibError> {
+        self.0.get_stock_report(low_stock_threshold).await
+    }
+
+    // what the metrics need, counted by the database
+    #[instrument(skip_all)]
+    pub async fn get_stock_totals(
+        &self,
+        low_stock_threshold: i32,
+    ) -> Result<StockTotals, LibError> {
+        self.0.get_stock_totals(low_stock_threshold).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::memory::MemoryMaterialRepository;
+    use crate::supplier::memory::MemorySupplierRepository;
+    use crate::supplier::model::CreateSupplier;
+    use crate::supplier::repository::SupplierRepository;
+    use crate::util::memory::MemoryDb;
+    use crate::util::model::SortDirection;
+    use chrono::NaiveDate;
+    use std::sync::Arc;
+
+    fn use_case() -> (MaterialUseCase, Arc<MemoryDb>) {
+        let db = Arc::new(MemoryDb::new());
+        let use_case = MaterialUseCase::new(Box::new(MemoryMaterialRepository::new(db.clone())));
+        (use_case, db)
+    }
+
+    fn group(name: &str, sub_group_name: &str) -> CreateMaterialGroup {
+        CreateMaterialGroup {
+            name: name.to_string(),
+            sub_group_name: sub_group_name.to_string(),
+        }
+    }
+
+    fn material(
+        name: &str,
+        price: i32,
+        supplier_id: Option<Uuid>,
+        group_id: Option<Uuid>,
+    ) -> CreateMaterial {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        CreateMaterial {
+            name: name.to_string(),
+            price,
+            description: format!("{name} for testing"),
+            quantity: 1,
+            mfg_date: date,
+            exp_date: date,
+            supplier_id,
+            group_id,
+        }
+    }
+
+    fn rows(materials: Vec<CreateMaterial>) -> Vec<ImportRow<CreateMaterial>> {
+        materials
+            .into_iter()
+            .enumerate()
+            .map(|(i, record)| ImportRow { row: i + 2, record })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn groups_are_created_updated_and_found_by_name() {
+        let (use_case, _) = use_case();
+        let tools = use_case
+            .create_material_group(&group("Tools", "Hand"), None)
+            .await
+            .unwrap();
+        use_case
+            .create_material_group(&group("Tools", "Power"), None)
+            .await
+            .unwrap();
+        use_case
+            .create_material_group(&group("Paint", "Oil"), None)
+            .await
+            .unwrap();
+
+        let changes = UpdateMaterialGroup {
+            name: None,
+            sub_group_name: Some("Manual".to_string()),
+        };
+        let updated = use_case
+            .update_material_group_by_id(&tools.id, &changes, Some(1), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            (updated.sub_group_name.as_str(), updated.version),
+            ("Manual", 2)
+        );
+
+        let query = ListQuery {
+            sort: Some("sub_group_name".to_string()),
+            ..ListQuery::default()
+        };
+        let page = use_case
+            .get_sub_group_by_group_name("tools", &query)
+            .await
+            .unwrap();
+        let sub_groups: Vec<_> = page
+            .items
+            .iter()
+            .map(|group| group.sub_group_name.as_str())
+            .collect();
+        assert_eq!(sub_groups, ["Manual", "Power"]);
+    }
+
+    #[tokio::test]
+    async fn deleted_groups_can_be_restored() {
+        let (use_case, _) = use_case();
+        let tools = use_case
+            .create_material_group(&group("Tools", "Hand"), None)
+            .await
+            .unwrap();
+        let err = use_case
+            .delete_material_group_by_id(&tools.id, Some(3), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::PreconditionFailed(_)));
+        use_case
+            .delete_material_group_by_id(&tools.id, Some(1), None)
+            .await
+            .unwrap();
+        assert!(use_case.get_material_group_by_id(tools.id).await.is_err());
+        assert_eq!(
+            use_case.get_deleted_material_groups().await.unwrap().len(),
+            1
+        );
+
+        use_case
+            .restore_material_group_by_id(&tools.id, None)
+            .await
+            .unwrap();
+        assert!(use_case.get_material_group_by_id(tools.id).await.is_ok());
+        let err = use_case
+            .restore_material_group_by_id(&tools.id, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::SqlxError(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn imports_check_references() {
+        let (use_case, db) = use_case();
+        let supplier = MemorySupplierRepository::new(db)
+            .create_supplier(
+                &CreateSupplier {
+                    name: "Acme".to_string(),
+                    email: "acme@example.com".to_string(),
+                    phone: String::new(),
+                    address: String::new(),
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let tools = use_case
+            .create_material_group(&group("Tools", "Hand"), None)
+            .await
+            .unwrap();
+
+        let missing = Uuid::new_v4();
+        let invalid = rows(vec![
+            material("Bolt", 10, Some(supplier.id), Some(tools.id)),
+            material("Nut", 5, Some(missing), None),
+        ]);
+        let report = use_case
+            .import_materials(&invalid, false, None)
+            .await
+            .unwrap();
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].row, 3);
+        assert_eq!(report.errors[0].field.as_deref(), Some("supplier_id"));
+
+        let valid = rows(vec![
+            material("Bolt", 10, Some(supplier.id), Some(tools.id)),
+            material("Nut", 5, None, None),
+        ]);
+        let report = use_case
+            .import_materials(&valid, false, None)
+            .await
+            .unwrap();
+        assert_eq!(report.imported, 2);
+        let query = ListQuery {
+            filters: vec![("group_id".to_string(), tools.id.to_string())],
+            ..ListQuery::default()
+        };
+        let page = use_case.get_all_materials(&query).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].supplier_name.as_deref(), Some("Acme"));
+        assert_eq!(page.items[0].group_name.as_deref(), Some("Tools"));
+
+        let err = use_case
+            .delete_material_group_by_id(&tools.id, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn materials_are_paged_by_cursor() {
+        let (use_case, _) = use_case();
+        let materials = vec![
+            material("Bolt", 9, None, None),
+            material("Nut", 100, None, None),
+            material("Screw", 25, None, None),
+        ];
+        use_case
+            .import_materials(&rows(materials), false, None)
+            .await
+            .unwrap();
+
+        let mut query = ListQuery {
+            sort: Some("price".to_string()),
+            direction: SortDirection::Desc,
+            limit: 2,
+            ..ListQuery::default()
+        };
+        let mut prices = Vec::new();
+        loop {
+            let page = use_case.get_all_materials(&query).await.unwrap();
+            prices.extend(page.items.iter().map(|material| material.price));
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(prices, [100, 25, 9]);
+
+        query.sort = Some("name".to_string());
+        assert!(matches!(
+            use_case.get_all_materials(&query).await,
+            Err(LibError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn all_or_nothing_bulk_keeps_nothing_when_an_item_fails() {
+        let (use_case, _) = use_case();
+        let operations = [
+            BulkOperation::Create(group("Tools", "Hand")),
+            BulkOperation::Update {
+                id: Uuid::new_v4(),
+                version: None,
+                record: UpdateMaterialGroup {
+                    name: None,
+                    sub_group_name: None,
+                },
+            },
+        ];
+        let result = use_case
+            .bulk_material_groups(&operations, BulkMode::AllOrNothing, None)
+            .await
+            .unwrap();
+        assert!(!result.committed);
+        let page = use_case
+            .get_all_material_groups(&ListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.total, 0);
+    }
+
+    #[tokio::test]
+    async fn export_sends_every_group() {
+        let (use_case, _) = use_case();
+        for (name, sub_group_name) in [("Tools", "Hand"), ("Paint", "Oil")] {
+            use_case
+                .create_material_group(&group(name, sub_group_name), None)
+                .await
+                .unwrap();
+        }
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        let query = ListQuery {
+            sort: Some("name".to_string()),
+            ..ListQuery::default()
+        };
+        use_case
+            .export_material_groups(&query, &sender)
+            .await
+            .unwrap();
+        drop(sender);
+        let mut exported = Vec::new();
+        while let Some(group) = receiver.recv().await {
+            exported.push(group.name);
+        }
+        assert_eq!(exported, ["Paint", "Tools"]);
+    }
+
+    #[tokio::test]
+    async fn stock_report_sums_groups_and_lists_low_stock() {
+        let (use_case, _) = use_case();
+        let tools = use_case
+            .create_material_group(&group("Tools", "Hand"), None)
+            .await
+            .unwrap();
+        let mut hammer = material("Hammer", 20, None, Some(tools.id));
+        hammer.quantity = 3;
+        let mut saw = material("Saw", 50, None, Some(tools.id));
+        saw.quantity = 40;
+        let mut glue = material("Glue", 5, None, None);
+        glue.quantity = 0;
+        use_case
+            .import_materials(&rows(vec![hammer, saw, glue]), false, None)
+            .await
+            .unwrap();
+
+        let report = use_case.get_stock_report(5).await.unwrap();
+        assert_eq!(report.materials, 3);
+        assert_eq!(report.quantity, 43);
+        assert_eq!(report.value, 3 * 20 + 40 * 50);
+        let groups: Vec<(Option<&str>, i64)> = report
+            .groups
+            .iter()
+            .map(|group| (group.group_name.as_deref(), group.value))
+            .collect();
+        assert_eq!(groups, [(Some("Tools"), 2060), (None, 0)]);
+        let low_stock: Vec<&str> = report
+            .low_stock
+            .iter()
+            .map(|material| material.name.as_str())
+            .collect();
+        assert_eq!(low_stock, ["Glue", "Hammer"]);
+
+        let totals = use_case.get_stock_totals(5).await.unwrap();
+        assert_eq!(
+            (totals.materials, totals.value, totals.low_stock),
+            (report.materials, report.value, 2)
+        );
     }
 }
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcCallback {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub id_token: String,
+}
+
+#[derive(Debug)]
+pub struct LoginState {
+    pub code_verifier: String,
+    pub nonce: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+    pub nonce: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl IdTokenClaims {
+    // providers send groups either as a list or as a single string
+    pub fn groups(&self, claim: &str) -> Vec<String> {
+        match self.extra.get(claim) {
+            Some(Value::Array(groups)) => groups
+                .iter()
+                .filter_map(|group| group.as_str().map(|group| group.to_string()))
+                .collect(),
+            Some(Value::String(group)) => vec![group.clone()],
+            _ => vec![],
+        }
+    }
+
+    pub fn username(&self) -> String {
+        self.preferred_username
+            .clone()
+            .or(self.name.clone())
+            .or(self
+                .email
+                .as_ref()
+                .and_then(|email| email.split('@').next().map(|name| name.to_string())))
+            .unwrap_or(self.sub.clone())
+    }
+}
@@ -0,0 +1,472 @@
+use crate::audit::audit::PgAuditLogRepository;
+use crate::audit::model::{AuditEntityType, AuditEntry};
+use crate::oidc::model::{
+    IdTokenClaims, LoginState, OidcCallback, ProviderMetadata, TokenResponse,
+};
+use crate::oidc::repository::OidcRepository;
+use crate::role::role::PgRoleRepository;
+use crate::session::session::PgSessionRepository;
+use crate::user::model::{AuthBody, ClientInfo, CurrentUser, Id};
+use crate::user::user::PgUserRepository;
+use crate::util::error::LibError;
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use config::{AuthConfig, OidcConfig};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::postgres::Postgres;
+use sqlx::{Pool, Transaction};
+use std::sync::Arc;
+use tokio::sync::{OnceCell, RwLock};
+use tracing::instrument;
+use url::Url;
+use uuid::Uuid;
+
+const LOGIN_STATE_TTL_SECONDS: f64 = 600.0;
+
+#[derive(Debug)]
+pub struct PgOidcRepository {
+    db_connect: Arc<Pool<Postgres>>,
+    config: Option<OidcConfig>,
+    auth: AuthConfig,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    // the provider's signing keys, fetched again when a token names a kid they do not have
+    jwks: RwLock<Option<JwkSet>>,
+}
+
+impl PgOidcRepository {
+    pub async fn new(
+        db_connect: Arc<Pool<Postgres>>,
+        config: Option<OidcConfig>,
+        auth: AuthConfig,
+    ) -> Self {
+        Self {
+            db_connect,
+            config,
+            auth,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+            jwks: RwLock::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl OidcRepository for PgOidcRepository {
+    #[instrument(skip_all)]
+    async fn authorization_url(&self) -> Result<String, LibError> {
+        let config = self.config()?;
+        let metadata = self.metadata().await?;
+        let state = random_string(32);
+        let nonce = random_string(32);
+        let code_verifier = random_string(64);
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        sqlx::query!(
+            r#"
+            DELETE FROM oidc_login_states
+            WHERE created_at < now() - make_interval(secs => $1);
+            "#,
+            LOGIN_STATE_TTL_SECONDS
+        )
+        .execute(self.db_connect.clone().as_ref())
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO oidc_login_states (state, code_verifier, nonce)
+            VALUES ($1, $2, $3);
+            "#,
+            &state,
+            &code_verifier,
+            &nonce
+        )
+        .execute(self.db_connect.clone().as_ref())
+        .await?;
+
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", config.client_id.as_str()),
+                ("redirect_uri", config.redirect_uri.as_str()),
+                ("scope", config.scopes.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| LibError::Http(e.to_string()))?;
+        Ok(url.to_string())
+    }
+
+    #[instrument(skip_all)]
+    async fn login(
+        &self,
+        callback: &OidcCallback,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, LibError> {
+        let config = self.config()?;
+        let login_state = self.take_login_state(&callback.state).await?;
+        let id_token = self
+            .exchange_code(config, &callback.code, &login_state.code_verifier)
+            .await?;
+        let claims = self.verify_id_token(config, &id_token).await?;
+        if claims.nonce.as_deref() != Some(login_state.nonce.as_str()) {
+            return Err(LibError::Unauthorized("Invalid OIDC nonce".to_string()));
+        }
+
+        let role_name =
+            map_role(config, &claims.groups(&config.groups_claim)).ok_or_else(|| {
+                LibError::Unauthorized("No role is mapped to the user's groups".to_string())
+            })?;
+        let role_id = PgRoleRepository::find_role(&self.db_connect, &role_name).await?;
+
+        let mut tx = self.db_connect.begin().await?;
+        let user_id = self.provision_user(&mut tx, &claims, &role_id).await?;
+        let user = sqlx::query_as!(
+            CurrentUser,
+            r#"
+            SELECT u.id, u.name, u.email, u.hash as hashed_password, r.name as role_name,
+                u.failed_login_attempts, u.last_failed_login_at, u.locked_until
+            FROM users as u
+            INNER JOIN roles as r
+            ON u.role_id = r.id
+            WHERE u.id = $1;
+            "#,
+            &user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        PgSessionRepository::start_session(&self.db_connect, &user, client, &self.auth).await
+    }
+}
+
+impl PgOidcRepository {
+    fn config(&self) -> Result<&OidcConfig, LibError> {
+        self.config
+            .as_ref()
+            .ok_or_else(|| LibError::NotFound("OIDC login is not configured".to_string()))
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, LibError> {
+        let config = self.config()?;
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!("{}/.well-known/openid-configuration", config.issuer_url);
+                self.get_json::<ProviderMetadata>(&url).await
+            })
+            .await
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, LibError> {
+        self.http
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| LibError::Http(e.to_string()))?
+            .json::<T>()
+            .await
+            .map_err(|e| LibError::Http(e.to_string()))
+    }
+
+    async fn take_login_state(&self, state: &str) -> Result<LoginState, LibError> {
+        sqlx::query_as!(
+            LoginState,
+            r#"
+            DELETE FROM oidc_login_states
+            WHERE state = $1 AND created_at > now() - make_interval(secs => $2)
+            RETURNING code_verifier, nonce;
+            "#,
+            state,
+            LOGIN_STATE_TTL_SECONDS
+        )
+        .fetch_optional(self.db_connect.clone().as_ref())
+        .await?
+        .ok_or_else(|| LibError::Unauthorized("Invalid or expired OIDC state".to_string()))
+    }
+
+    async fn exchange_code(
+        &self,
+        config: &OidcConfig,
+        code: &str,
+        code_verifier: &str,
+    ) -> Result<String, LibError> {
+        let metadata = self.metadata().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_uri.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(client_secret) = &config.client_secret {
+            form.push(("client_secret", client_secret.as_str()));
+        }
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| LibError::Http(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(LibError::Unauthorized(
+                "OIDC authorization code was rejected".to_string(),
+            ));
+        }
+        let token = response
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| LibError::Http(e.to_string()))?;
+        Ok(token.id_token)
+    }
+
+    async fn verify_id_token(
+        &self,
+        config: &OidcConfig,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, LibError> {
+        let metadata = self.metadata().await?;
+        let header = decode_header(id_token).map_err(LibError::JwtError)?;
+        let jwk = self.signing_key(header.kid.as_deref()).await?;
+        let key = DecodingKey::from_jwk(&jwk).map_err(LibError::JwtError)?;
+        // the algorithm the key was published for, a token must not choose its own
+        let algorithm = key_algorithm(&jwk)
+            .ok_or_else(|| LibError::Unauthorized("Unsupported OIDC signing key".to_string()))?;
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_audience(&[&config.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|e| LibError::Unauthorized(format!("Invalid OIDC id token: {e}")))?
+            .claims;
+        Ok(claims)
+    }
+
+    // providers rotate keys by publishing the new one before signing with it, so an unknown
+    // kid means the cached key set is out of date
+    async fn signing_key(&self, kid: Option<&str>) -> Result<Jwk, LibError> {
+        if let Some(jwk) = self
+            .jwks
+            .read()
+            .await
+            .as_ref()
+            .and_then(|jwks| find_key(jwks, kid))
+        {
+            return Ok(jwk.clone());
+        }
+        let metadata = self.metadata().await?;
+        let jwks = self.get_json::<JwkSet>(&metadata.jwks_uri).await?;
+        let jwk = find_key(&jwks, kid).cloned();
+        *self.jwks.write().await = Some(jwks);
+        jwk.ok_or_else(|| LibError::Unauthorized("Unknown OIDC signing key".to_string()))
+    }
+
+    // links the IdP identity to a local user, creating the user on first login
+    async fn provision_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        claims: &IdTokenClaims,
+        role_id: &Uuid,
+    ) -> Result<Uuid, LibError> {
+        let issuer = &self.metadata().await?.issuer;
+        let linked = sqlx::query_as!(
+            Id,
+            r#"
+            SELECT user_id as id FROM user_identities
+            WHERE issuer = $1 AND subject = $2;
+            "#,
+            issuer,
+            &claims.sub
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let user_id = match linked {
+            Some(linked) => linked.id,
+            None => {
+                let email = claims.email.as_ref().ok_or_else(|| {
+                    LibError::Unauthorized("OIDC provider did not return an email".to_string())
+                })?;
+                let existing = sqlx::query_as!(
+                    Id,
+                    r#"
+                    SELECT id FROM users WHERE email = $1 AND deleted_at IS NULL;
+                    "#,
+                    email
+                )
+                .fetch_optional(&mut **tx)
+                .await?;
+                let user_id = match existing {
+                    Some(existing) if claims.email_verified == Some(true) => existing.id,
+                    Some(_) => {
+                        return Err(LibError::Unauthorized(
+                            "Email is already registered and not verified by the OIDC provider"
+                                .to_string(),
+                        ))
+                    }
+                    None => self.create_user(tx, claims, email, role_id).await?,
+                };
+                sqlx::query!(
+                    r#"
+                    INSERT INTO user_identities (user_id, issuer, subject)
+                    VALUES ($1, $2, $3);
+                    "#,
+                    &user_id,
+                    issuer,
+                    &claims.sub
+                )
+                .execute(&mut **tx)
+                .await?;
+                user_id
+            }
+        };
+
+        let deleted = sqlx::query_scalar!(
+            r#"SELECT deleted_at IS NOT NULL as "deleted!" FROM users WHERE id = $1"#,
+            &user_id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+        if deleted {
+            return Err(LibError::Unauthorized(
+                "Account has been deleted".to_string(),
+            ));
+        }
+
+        // group membership is owned by the IdP, so the role is synced on every login
+        let before = PgUserRepository::query_user(tx, &user_id)
+            .await?
+            .to_response_user();
+        let synced = sqlx::query!(
+            r#"
+            UPDATE users
+            SET role_id = $1,
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $2 AND role_id <> $1;
+            "#,
+            role_id,
+            &user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        if synced.rows_affected() > 0 {
+            let after = PgUserRepository::query_user(tx, &user_id)
+                .await?
+                .to_response_user();
+            PgAuditLogRepository::record(
+                tx,
+                &AuditEntry::updated(None, AuditEntityType::User, user_id, &before, &after),
+            )
+            .await?;
+        }
+        Ok(user_id)
+    }
+
+    async fn create_user(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        claims: &IdTokenClaims,
+        email: &str,
+        role_id: &Uuid,
+    ) -> Result<Uuid, LibError> {
+        let base_name = claims.username();
+        let mut name = base_name.clone();
+        while sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM users WHERE name = $1 AND deleted_at IS NULL) as "exists!""#,
+            &name
+        )
+        .fetch_one(&mut **tx)
+        .await?
+        {
+            name = format!("{}_{}", base_name, random_string(4).to_lowercase());
+        }
+
+        // SSO users never log in with a password, so they get an unguessable one
+        let hashed_password = bcrypt::hash(random_string(64), self.auth.bcrypt_cost)?;
+        let id = sqlx::query_as!(
+            Id,
+            r#"
+            INSERT INTO users (name, email, hash, address, role_id)
+            VALUES ($1, $2, $3, NULL, $4)
+            RETURNING id;
+            "#,
+            &name,
+            email,
+            &hashed_password,
+            role_id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+        let created = PgUserRepository::query_user(tx, &id.id)
+            .await?
+            .to_response_user();
+        PgAuditLogRepository::record(
+            tx,
+            &AuditEntry::created(None, AuditEntityType::User, id.id, &created),
+        )
+        .await?;
+        Ok(id.id)
+    }
+}
+
+fn find_key<'a>(jwks: &'a JwkSet, kid: Option<&str>) -> Option<&'a Jwk> {
+    match kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    }
+}
+
+// the `alg` of the key, or the usual one for its type when the provider left it out.
+// Symmetric keys are never accepted, the client secret must not verify id tokens.
+fn key_algorithm(jwk: &Jwk) -> Option<Algorithm> {
+    match jwk.common.key_algorithm {
+        Some(KeyAlgorithm::RS256) => Some(Algorithm::RS256),
+        Some(KeyAlgorithm::RS384) => Some(Algorithm::RS384),
+        Some(KeyAlgorithm::RS512) => Some(Algorithm::RS512),
+        Some(KeyAlgorithm::PS256) => Some(Algorithm::PS256),
+        Some(KeyAlgorithm::PS384) => Some(Algorithm::PS384),
+        Some(KeyAlgorithm::PS512) => Some(Algorithm::PS512),
+        Some(KeyAlgorithm::ES256) => Some(Algorithm::ES256),
+        Some(KeyAlgorithm::ES384) => Some(Algorithm::ES384),
+        Some(KeyAlgorithm::EdDSA) => Some(Algorithm::EdDSA),
+        Some(_) => None,
+        None => match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Some(Algorithm::RS256),
+            AlgorithmParameters::EllipticCurve(ec) => match ec.curve {
+                EllipticCurve::P256 => Some(Algorithm::ES256),
+                EllipticCurve::P384 => Some(Algorithm::ES384),
+                _ => None,
+            },
+            AlgorithmParameters::OctetKeyPair(_) => Some(Algorithm::EdDSA),
+            AlgorithmParameters::OctetKey(_) => None,
+        },
+    }
+}
+
+fn map_role(config: &OidcConfig, groups: &[String]) -> Option<String> {
+    config
+        .role_mapping
+        .iter()
+        .find(|(group, _)| groups.contains(group))
+        .map(|(_, role)| role.clone())
+        .or(config.default_role.clone())
+}
+
+fn random_string(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,14 @@
+use crate::oidc::model::OidcCallback;
+use crate::user::model::{AuthBody, ClientInfo};
+use crate::util::error::LibError;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait OidcRepository: Send + Sync + std::fmt::Debug {
+    async fn authorization_url(&self) -> Result<String, LibError>;
+    async fn login(
+        &self,
+        callback: &OidcCallback,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, LibError>;
+}
@@ -0,0 +1,28 @@
+use crate::oidc::model::OidcCallback;
+use crate::oidc::repository::OidcRepository;
+use crate::user::model::{AuthBody, ClientInfo};
+use crate::util::error::LibError;
+use tracing::instrument;
+
+#[derive(Debug)]
+pub struct OidcUseCase(Box<dyn OidcRepository>);
+
+impl OidcUseCase {
+    pub fn new(repository: Box<dyn OidcRepository>) -> Self {
+        OidcUseCase(repository)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn authorization_url(&self) -> Result<String, LibError> {
+        self.0.authorization_url().await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn login(
+        &self,
+        callback: &OidcCallback,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, LibError> {
+        self.0.login(callback, client).await
+    }
+}
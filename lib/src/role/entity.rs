@@ -4,9 +4,12 @@ use serde::{Deserialize, Serialize};
 pub enum Role {
     Admin,
     User,
+    // a role created at runtime, by its name
+    Custom(String),
 }
 
 impl Role {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Role> {
         let s = &s.to_lowercase();
         let s = s[..1].to_uppercase() + &s[1..];
@@ -16,10 +19,15 @@ impl Role {
             _ => None,
         }
     }
+    // any role by name, the built-in ones are matched like `from_str` matches them
+    pub fn named(name: &str) -> Role {
+        Role::from_str(name).unwrap_or_else(|| Role::Custom(name.to_string()))
+    }
     pub fn to_str(&self) -> String {
         match self {
             Role::Admin => String::from("Admin"),
             Role::User => String::from("User"),
+            Role::Custom(name) => name.clone(),
         }
     }
 }
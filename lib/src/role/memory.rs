@@ -0,0 +1,233 @@
+use crate::role::model::{RequestRole, ResponseRole};
+use crate::role::repository::RoleRepository;
+use crate::role::role::ROLE_LIST;
+use crate::util::error::{ConstraintError, LibError};
+use crate::util::memory::{not_found, timestamp_key, ListRecord, MemoryDb, MemoryTables};
+use crate::util::model::{check_version, DeletedRecord, ListQuery, Page};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub(crate) struct RoleRow {
+    pub id: Uuid,
+    pub name: String,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+}
+
+impl RoleRow {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            version: 1,
+            created_at: Utc::now(),
+            deleted_at: None,
+            deleted_by: None,
+        }
+    }
+
+    fn to_response_role(&self) -> ResponseRole {
+        ResponseRole {
+            id: self.id,
+            name: self.name.clone(),
+            version: self.version,
+        }
+    }
+}
+
+impl ListRecord for RoleRow {
+    fn list_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn list_value(&self, name: &str) -> Option<String> {
+        match name {
+            "created_at" => Some(timestamp_key(&self.created_at)),
+            "name" => Some(self.name.clone()),
+            _ => None,
+        }
+    }
+
+    fn search_values(&self) -> Vec<&str> {
+        vec![&self.name]
+    }
+}
+
+impl MemoryTables {
+    pub(crate) fn active_role(&self, id: &Uuid) -> Option<&RoleRow> {
+        self.roles
+            .iter()
+            .find(|role| role.id == *id && role.deleted_at.is_none())
+    }
+
+    pub(crate) fn find_role(&self, name: &str) -> Result<Uuid, LibError> {
+        self.roles
+            .iter()
+            .find(|role| role.name == name && role.deleted_at.is_none())
+            .map(|role| role.id)
+            .ok_or_else(not_found)
+    }
+
+    // role names are unique among roles that are not deleted, like the index
+    fn check_role_name(&self, name: &str, id: Option<&Uuid>) -> Result<(), LibError> {
+        if self
+            .roles
+            .iter()
+            .any(|role| role.name == name && Some(&role.id) != id && role.deleted_at.is_none())
+        {
+            return Err(LibError::Constraint(ConstraintError::Duplicate {
+                field: "name".to_string(),
+            }));
+        }
+        Ok(())
+    }
+}
+
+// RoleRepository without a database, see MemoryDb
+#[derive(Debug)]
+pub struct MemoryRoleRepository {
+    db: Arc<MemoryDb>,
+}
+
+impl MemoryRoleRepository {
+    pub fn new(db: Arc<MemoryDb>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RoleRepository for MemoryRoleRepository {
+    async fn create_role(
+        &self,
+        role: &RequestRole,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        self.db.transaction(|tables| {
+            tables.check_role_name(&role.name, None)?;
+            let row = RoleRow::new(&role.name);
+            let result = row.to_response_role();
+            tables.roles.push(row);
+            Ok(result)
+        })
+    }
+
+    async fn get_roles(&self, query: &ListQuery) -> Result<Page<ResponseRole>, LibError> {
+        let rows = self.db.read(|tables| {
+            tables
+                .roles
+                .iter()
+                .filter(|role| role.deleted_at.is_none())
+                .cloned()
+                .collect()
+        });
+        let page = ROLE_LIST.fetch_memory(rows, query)?;
+        Ok(page.map(|row| row.to_response_role()))
+    }
+
+    async fn update_role(
+        &self,
+        id: &Uuid,
+        role: &RequestRole,
+        version: Option<i32>,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        self.db.transaction(|tables| {
+            tables.check_role_name(&role.name, Some(id))?;
+            let row = tables
+                .roles
+                .iter_mut()
+                .find(|row| row.id == *id && row.deleted_at.is_none())
+                .ok_or_else(not_found)?;
+            check_version(version, row.version)?;
+            row.name = role.name.clone();
+            row.version += 1;
+            Ok(row.to_response_role())
+        })
+    }
+
+    async fn delete_role(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        self.db.transaction(|tables| {
+            let in_use = tables
+                .users
+                .iter()
+                .any(|user| user.role_id == *id && user.deleted_at.is_none());
+            let Some(row) = tables
+                .roles
+                .iter_mut()
+                .find(|row| row.id == *id && row.deleted_at.is_none())
+            else {
+                return Ok(false);
+            };
+            check_version(version, row.version)?;
+            if in_use {
+                return Err(LibError::Conflict(
+                    "Role is still assigned to users".to_string(),
+                ));
+            }
+            row.deleted_at = Some(Utc::now());
+            row.deleted_by = actor.copied();
+            Ok(true)
+        })
+    }
+
+    async fn find_role(&self, role: &str) -> Result<Uuid, LibError> {
+        self.db.read(|tables| tables.find_role(role))
+    }
+
+    async fn get_role_by_id(&self, id: &Uuid) -> Result<ResponseRole, LibError> {
+        self.db.read(|tables| {
+            tables
+                .active_role(id)
+                .map(RoleRow::to_response_role)
+                .ok_or_else(not_found)
+        })
+    }
+
+    async fn get_deleted_roles(&self) -> Result<Vec<DeletedRecord<ResponseRole>>, LibError> {
+        let mut deleted: Vec<DeletedRecord<ResponseRole>> = self.db.read(|tables| {
+            tables
+                .roles
+                .iter()
+                .filter_map(|row| {
+                    Some(DeletedRecord {
+                        record: row.to_response_role(),
+                        deleted_at: row.deleted_at?,
+                        deleted_by: row.deleted_by,
+                    })
+                })
+                .collect()
+        });
+        deleted.sort_by_key(|record| std::cmp::Reverse(record.deleted_at));
+        Ok(deleted)
+    }
+
+    async fn restore_role(
+        &self,
+        id: &Uuid,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        self.db.transaction(|tables| {
+            let row = tables
+                .roles
+                .iter_mut()
+                .find(|row| row.id == *id && row.deleted_at.is_some())
+                .ok_or_else(not_found)?;
+            row.deleted_at = None;
+            row.deleted_by = None;
+            row.version += 1;
+            let restored = row.to_response_role();
+            tables.check_role_name(&restored.name, Some(id))?;
+            Ok(restored)
+        })
+    }
+}
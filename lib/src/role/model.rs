@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct ResponseRole {
     pub id: Uuid,
     pub name: String,
+    // bumped on every change, sent back as ETag
+    pub version: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,14 +1,33 @@
 use crate::role::model::{RequestRole, ResponseRole};
 use crate::util::error::LibError;
+use crate::util::model::{DeletedRecord, ListQuery, Page};
 use async_trait::async_trait;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait RoleRepository: Send + Sync + std::fmt::Debug {
-    async fn create_role(&self, role: &RequestRole) -> Result<ResponseRole, LibError>;
-    async fn get_roles(&self) -> Result<Vec<ResponseRole>, LibError>;
-    async fn update_role(&self, id: &Uuid, role: &RequestRole) -> Result<ResponseRole, LibError>;
-    async fn delete_role(&self, id: &Uuid) -> Result<bool, LibError>;
-    async fn find_role(&self, role: &String) -> Result<Uuid, LibError>;
+    async fn create_role(
+        &self,
+        role: &RequestRole,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError>;
+    async fn get_roles(&self, query: &ListQuery) -> Result<Page<ResponseRole>, LibError>;
+    async fn update_role(
+        &self,
+        id: &Uuid,
+        role: &RequestRole,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError>;
+    async fn delete_role(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError>;
+    async fn find_role(&self, role: &str) -> Result<Uuid, LibError>;
     async fn get_role_by_id(&self, id: &Uuid) -> Result<ResponseRole, LibError>;
+    async fn get_deleted_roles(&self) -> Result<Vec<DeletedRecord<ResponseRole>>, LibError>;
+    async fn restore_role(&self, id: &Uuid, actor: Option<&Uuid>)
+        -> Result<ResponseRole, LibError>;
 }
@@ -1,12 +1,42 @@
+use crate::audit::audit::PgAuditLogRepository;
+use crate::audit::model::{AuditEntityType, AuditEntry};
 use crate::role::model::{RequestRole, ResponseRole};
 use crate::role::repository::RoleRepository;
 use crate::util::error::LibError;
+use crate::util::list::{ColumnType, ListColumn, ListSpec};
+use crate::util::model::{check_version, DeletedRecord, ListQuery, Page};
 use async_trait::async_trait;
 use sqlx::postgres::Postgres;
 use sqlx::Pool;
 use std::sync::Arc;
+use tracing::instrument;
 use uuid::Uuid;
 
+pub(crate) static ROLE_LIST: ListSpec = ListSpec {
+    select: "SELECT id, name, version",
+    from: " FROM roles",
+    id_column: "id",
+    condition: "deleted_at IS NULL",
+    sort_columns: &[
+        ListColumn {
+            name: "created_at",
+            column: "created_at",
+            column_type: ColumnType::Timestamp,
+        },
+        ListColumn {
+            name: "name",
+            column: "name",
+            column_type: ColumnType::Text,
+        },
+    ],
+    filter_columns: &[ListColumn {
+        name: "name",
+        column: "name",
+        column_type: ColumnType::Text,
+    }],
+    search_columns: &["name"],
+};
+
 #[derive(Debug)]
 pub struct PgRoleRepository {
     db_connect: Arc<Pool<Postgres>>,
@@ -20,68 +50,195 @@ impl PgRoleRepository {
 
 #[async_trait]
 impl RoleRepository for PgRoleRepository {
-    async fn create_role(&self, role: &RequestRole) -> Result<ResponseRole, LibError> {
+    #[instrument(skip_all)]
+    async fn create_role(
+        &self,
+        role: &RequestRole,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        let mut tx = self.db_connect.begin().await?;
         let query = sqlx::query_as!(
             ResponseRole,
             r#"
             INSERT INTO roles (name)
             VALUES ($1)
-            RETURNING id, name;
+            RETURNING id, name, version;
             "#,
             &role.name
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *tx)
+        .await?;
+        PgAuditLogRepository::record(
+            &mut tx,
+            &AuditEntry::created(actor, AuditEntityType::Role, query.id, &query),
+        )
         .await?;
+        tx.commit().await?;
         Ok(query)
     }
 
-    async fn get_roles(&self) -> Result<Vec<ResponseRole>, LibError> {
+    #[instrument(skip_all)]
+    async fn get_roles(&self, query: &ListQuery) -> Result<Page<ResponseRole>, LibError> {
+        ROLE_LIST.fetch(self.db_connect.as_ref(), query).await
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn update_role(
+        &self,
+        id: &Uuid,
+        role: &RequestRole,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let before = sqlx::query_as!(
+            ResponseRole,
+            r#"
+            SELECT id, name, version FROM roles WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE;
+            "#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        check_version(version, before.version)?;
         let query = sqlx::query_as!(
             ResponseRole,
             r#"
-            SELECT id, name FROM roles;
-            "#
+            UPDATE roles
+            SET name = $1,
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $2 AND deleted_at IS NULL
+            RETURNING id, name, version;
+            "#,
+            &role.name,
+            &id
         )
-        .fetch_all(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *tx)
         .await?;
+        PgAuditLogRepository::record(
+            &mut tx,
+            &AuditEntry::updated(actor, AuditEntityType::Role, query.id, &before, &query),
+        )
+        .await?;
+        tx.commit().await?;
         Ok(query)
     }
 
-    async fn update_role(&self, id: &Uuid, role: &RequestRole) -> Result<ResponseRole, LibError> {
+    #[instrument(skip_all, fields(%id))]
+    async fn delete_role(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let mut tx = self.db_connect.begin().await?;
         let query = sqlx::query_as!(
             ResponseRole,
             r#"
             UPDATE roles
-            SET name = $1,
-                updated_at = now()
-            WHERE id = $2
-            RETURNING id, name;
+            SET deleted_at = now(),
+                deleted_by = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, name, version;
+            "#,
+            &id,
+            actor
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(deleted) = query else {
+            return Ok(false);
+        };
+        check_version(version, deleted.version)?;
+        let in_use = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM users WHERE role_id = $1 AND deleted_at IS NULL
+            ) as "exists!";
             "#,
-            &role.name,
             &id
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *tx)
         .await?;
-        Ok(query)
+        if in_use {
+            return Err(LibError::Conflict(
+                "Role is still assigned to users".to_string(),
+            ));
+        }
+        PgAuditLogRepository::record(
+            &mut tx,
+            &AuditEntry::deleted(actor, AuditEntityType::Role, deleted.id, &deleted),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(true)
     }
 
-    async fn delete_role(&self, id: &Uuid) -> Result<bool, LibError> {
+    #[instrument(skip_all)]
+    async fn get_deleted_roles(&self) -> Result<Vec<DeletedRecord<ResponseRole>>, LibError> {
         let query = sqlx::query!(
             r#"
-            DELETE FROM roles WHERE id = $1
+            SELECT id, name, version, deleted_at as "deleted_at!", deleted_by FROM roles
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC;
+            "#
+        )
+        .fetch_all(self.db_connect.clone().as_ref())
+        .await?;
+        let result = query
+            .into_iter()
+            .map(|row| DeletedRecord {
+                record: ResponseRole {
+                    id: row.id,
+                    name: row.name,
+                    version: row.version,
+                },
+                deleted_at: row.deleted_at,
+                deleted_by: row.deleted_by,
+            })
+            .collect();
+        Ok(result)
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn restore_role(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let query = sqlx::query_as!(
+            ResponseRole,
+            r#"
+            UPDATE roles
+            SET deleted_at = NULL,
+                deleted_by = NULL,
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING id, name, version;
             "#,
             &id
         )
-        .execute(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *tx)
         .await?;
-        Ok(query.rows_affected() > 0)
+        PgAuditLogRepository::record(
+            &mut tx,
+            &AuditEntry::restored(actor, AuditEntityType::Role, query.id, &query),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(query)
     }
 
-    async fn find_role(&self, name: &String) -> Result<Uuid, LibError> {
+    #[instrument(skip_all)]
+    async fn find_role(&self, name: &str) -> Result<Uuid, LibError> {
         let query_result = sqlx::query_as!(
             ResponseRole,
             r#"
-            SELECT id, name FROM roles WHERE name = $1;
+            SELECT id, name, version FROM roles WHERE name = $1 AND deleted_at IS NULL;
             "#,
             &name,
         )
@@ -90,11 +247,12 @@ impl RoleRepository for PgRoleRepository {
         Ok(query_result.id)
     }
 
+    #[instrument(skip_all, fields(%id))]
     async fn get_role_by_id(&self, id: &Uuid) -> Result<ResponseRole, LibError> {
         let query = sqlx::query_as!(
             ResponseRole,
             r#"
-            SELECT id, name FROM roles WHERE id = $1;
+            SELECT id, name, version FROM roles WHERE id = $1 AND deleted_at IS NULL;
             "#,
             &id
         )
@@ -107,12 +265,12 @@ impl RoleRepository for PgRoleRepository {
 impl PgRoleRepository {
     pub async fn find_role(
         db_connect: &Arc<Pool<Postgres>>,
-        name: &String,
+        name: &str,
     ) -> Result<Uuid, sqlx::Error> {
         let query_result = sqlx::query_as!(
             ResponseRole,
             r#"
-            SELECT id, name FROM roles WHERE name = $1;
+            SELECT id, name, version FROM roles WHERE name = $1 AND deleted_at IS NULL;
             "#,
             &name,
         )
@@ -1,6 +1,8 @@
 use super::repository::RoleRepository;
 use crate::role::model::{RequestRole, ResponseRole};
 use crate::util::error::LibError;
+use crate::util::model::{DeletedRecord, ListQuery, Page};
+use tracing::instrument;
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -10,21 +12,36 @@ impl RoleUseCase {
     pub fn new(role_repository: Box<dyn RoleRepository>) -> Self {
         Self(role_repository)
     }
-    pub async fn create_role(&self, role: &RequestRole) -> Result<ResponseRole, LibError> {
-        self.0.create_role(&role).await
+    #[instrument(skip_all)]
+    pub async fn create_role(
+        &self,
+        role: &RequestRole,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        self.0.create_role(role, actor).await
     }
-    pub async fn get_roles(&self) -> Result<Vec<ResponseRole>, LibError> {
-        self.0.get_roles().await
+    #[instrument(skip_all)]
+    pub async fn get_roles(&self, query: &ListQuery) -> Result<Page<ResponseRole>, LibError> {
+        self.0.get_roles(query).await
     }
+    #[instrument(skip_all, fields(%id))]
     pub async fn update_role(
         &self,
         id: &Uuid,
         role: &RequestRole,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseRole, LibError> {
-        self.0.update_role(&id, &role).await
+        self.0.update_role(id, role, version, actor).await
     }
-    pub async fn delete_role(&self, id: &Uuid) -> Result<bool, LibError> {
-        match self.0.delete_role(&id).await {
+    #[instrument(skip_all, fields(%id))]
+    pub async fn delete_role(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        match self.0.delete_role(id, version, actor).await {
             Ok(r) => match r {
                 true => Ok(true),
                 false => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
@@ -32,10 +49,203 @@ impl RoleUseCase {
             Err(e) => Err(e),
         }
     }
-    pub async fn find_role(&self, role: &String) -> Result<Uuid, LibError> {
-        self.0.find_role(&role).await
+    #[instrument(skip_all)]
+    pub async fn find_role(&self, role: &str) -> Result<Uuid, LibError> {
+        self.0.find_role(role).await
     }
+    #[instrument(skip_all, fields(%id))]
     pub async fn get_role_by_id(&self, id: &Uuid) -> Result<ResponseRole, LibError> {
-        self.0.get_role_by_id(&id).await
+        self.0.get_role_by_id(id).await
+    }
+    #[instrument(skip_all)]
+    pub async fn get_deleted_roles(&self) -> Result<Vec<DeletedRecord<ResponseRole>>, LibError> {
+        self.0.get_deleted_roles().await
+    }
+    #[instrument(skip_all, fields(%id))]
+    pub async fn restore_role(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseRole, LibError> {
+        self.0.restore_role(id, actor).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::role::entity::Role;
+    use crate::role::memory::MemoryRoleRepository;
+    use crate::user::memory::MemoryUserRepository;
+    use crate::user::model::CreateUser;
+    use crate::user::repository::UserRepository;
+    use crate::util::memory::MemoryDb;
+    use crate::util::model::SortDirection;
+    use config::{Keys, LoginPolicy};
+    use std::sync::Arc;
+
+    fn use_case() -> (RoleUseCase, Arc<MemoryDb>) {
+        let db = Arc::new(MemoryDb::new());
+        let use_case = RoleUseCase::new(Box::new(MemoryRoleRepository::new(db.clone())));
+        (use_case, db)
+    }
+
+    fn role(name: &str) -> RequestRole {
+        RequestRole {
+            name: name.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn starts_with_the_seeded_roles() {
+        let (use_case, _) = use_case();
+        let admin = use_case.find_role("Admin").await.unwrap();
+        assert_eq!(use_case.get_role_by_id(&admin).await.unwrap().name, "Admin");
+        assert!(use_case.find_role("Auditor").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn role_names_are_unique() {
+        let (use_case, _) = use_case();
+        use_case.create_role(&role("Auditor"), None).await.unwrap();
+        let err = use_case
+            .create_role(&role("Auditor"), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::Constraint(e) if e.field() == Some("name")));
+    }
+
+    #[tokio::test]
+    async fn updates_check_the_version() {
+        let (use_case, _) = use_case();
+        let created = use_case.create_role(&role("Auditor"), None).await.unwrap();
+        let updated = use_case
+            .update_role(&created.id, &role("Reviewer"), Some(1), None)
+            .await
+            .unwrap();
+        assert_eq!((updated.name.as_str(), updated.version), ("Reviewer", 2));
+
+        let err = use_case
+            .update_role(&created.id, &role("Auditor"), Some(1), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::PreconditionFailed(_)));
+        assert_eq!(
+            use_case.get_role_by_id(&created.id).await.unwrap().name,
+            "Reviewer"
+        );
+    }
+
+    #[tokio::test]
+    async fn deleted_roles_can_be_restored() {
+        let (use_case, _) = use_case();
+        let created = use_case.create_role(&role("Auditor"), None).await.unwrap();
+        let actor = Uuid::new_v4();
+        assert!(use_case
+            .delete_role(&created.id, None, Some(&actor))
+            .await
+            .unwrap());
+        assert!(use_case.get_role_by_id(&created.id).await.is_err());
+
+        let deleted = use_case.get_deleted_roles().await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].deleted_by, Some(actor));
+
+        let restored = use_case.restore_role(&created.id, None).await.unwrap();
+        assert_eq!(restored.version, 2);
+        assert!(use_case.get_deleted_roles().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleted_roles_release_their_name() {
+        let (use_case, _) = use_case();
+        let deleted = use_case.create_role(&role("Auditor"), None).await.unwrap();
+        use_case.delete_role(&deleted.id, None, None).await.unwrap();
+        use_case.create_role(&role("Auditor"), None).await.unwrap();
+
+        let err = use_case.restore_role(&deleted.id, None).await.unwrap_err();
+        assert!(matches!(err, LibError::Constraint(e) if e.field() == Some("name")));
+        assert_eq!(use_case.get_deleted_roles().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deleting_a_missing_role_is_not_found() {
+        let (use_case, _) = use_case();
+        let err = use_case
+            .delete_role(&Uuid::new_v4(), None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::SqlxError(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn roles_in_use_are_not_deleted() {
+        let (use_case, db) = use_case();
+        let users = MemoryUserRepository::new(db, LoginPolicy::default(), Keys::new(&[7; 32]));
+        users
+            .create_user(
+                &CreateUser {
+                    name: "alice".to_string(),
+                    email: "alice@example.com".to_string(),
+                    password: "Passw0rd".to_string(),
+                    address: String::new(),
+                    role: Role::User,
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        let user_role = use_case.find_role("User").await.unwrap();
+        let err = use_case
+            .delete_role(&user_role, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::Conflict(_)));
+        assert!(use_case.get_role_by_id(&user_role).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn roles_are_listed_sorted_and_searched() {
+        let (use_case, _) = use_case();
+        for name in ["Auditor", "Buyer", "Clerk"] {
+            use_case.create_role(&role(name), None).await.unwrap();
+        }
+        let query = ListQuery {
+            sort: Some("name".to_string()),
+            direction: SortDirection::Desc,
+            limit: 2,
+            ..ListQuery::default()
+        };
+        let page = use_case.get_roles(&query).await.unwrap();
+        let names: Vec<_> = page.items.iter().map(|role| role.name.as_str()).collect();
+        assert_eq!(names, ["User", "Clerk"]);
+        assert_eq!(page.total, 5);
+
+        let next = ListQuery {
+            cursor: page.next_cursor,
+            ..query
+        };
+        let page = use_case.get_roles(&next).await.unwrap();
+        let names: Vec<_> = page.items.iter().map(|role| role.name.as_str()).collect();
+        assert_eq!(names, ["Buyer", "Auditor"]);
+        assert_eq!(page.page, None);
+
+        let search = ListQuery {
+            search: Some("ER".to_string()),
+            ..ListQuery::default()
+        };
+        let page = use_case.get_roles(&search).await.unwrap();
+        let mut names: Vec<_> = page.items.iter().map(|role| role.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["Buyer", "Clerk", "User"]);
+
+        let unknown = ListQuery {
+            sort: Some("version".to_string()),
+            ..ListQuery::default()
+        };
+        assert!(matches!(
+            use_case.get_roles(&unknown).await,
+            Err(LibError::InvalidInput(_))
+        ));
     }
 }
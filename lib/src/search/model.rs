@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Material,
+    Supplier,
+    MaterialGroup,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 3] = [
+        SearchKind::Material,
+        SearchKind::Supplier,
+        SearchKind::MaterialGroup,
+    ];
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            SearchKind::Material => "material",
+            SearchKind::Supplier => "supplier",
+            SearchKind::MaterialGroup => "material_group",
+        }
+    }
+
+    pub fn from_name(kind: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.to_str() == kind)
+    }
+}
+
+#[derive(Debug)]
+pub struct SearchQuery {
+    pub term: String,
+    pub kinds: Vec<SearchKind>,
+    // only admins get to see soft deleted rows
+    pub include_deleted: bool,
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ResponseSearchResult {
+    pub kind: String,
+    pub id: Uuid,
+    pub title: String,
+    pub subtitle: Option<String>,
+    // escaped HTML with the matched words wrapped in <mark></mark>
+    pub highlight: String,
+    pub rank: f32,
+    pub deleted: bool,
+}
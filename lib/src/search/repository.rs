@@ -0,0 +1,8 @@
+use crate::search::model::{ResponseSearchResult, SearchQuery};
+use crate::util::error::LibError;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait SearchRepository: Send + Sync + std::fmt::Debug {
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<ResponseSearchResult>, LibError>;
+}
@@ -0,0 +1,86 @@
+use crate::search::model::{ResponseSearchResult, SearchQuery};
+use crate::search::repository::SearchRepository;
+use crate::util::error::LibError;
+use async_trait::async_trait;
+use sqlx::postgres::Postgres;
+use sqlx::Pool;
+use std::sync::Arc;
+use tracing::instrument;
+
+#[derive(Debug)]
+pub struct PgSearchRepository {
+    db_connect: Arc<Pool<Postgres>>,
+}
+
+impl PgSearchRepository {
+    pub async fn new(db_connect: Arc<Pool<Postgres>>) -> Self {
+        Self { db_connect }
+    }
+}
+
+#[async_trait]
+impl SearchRepository for PgSearchRepository {
+    // full text matches rank first, trigram similarity adds typo tolerant and partial word matches.
+    // Highlights are escaped HTML, only the <mark> tags around the matches are markup.
+    #[instrument(skip_all)]
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<ResponseSearchResult>, LibError> {
+        let kinds: Vec<String> = query
+            .kinds
+            .iter()
+            .map(|kind| kind.to_str().to_string())
+            .collect();
+        let result = sqlx::query_as!(
+            ResponseSearchResult,
+            r#"
+            WITH input AS (
+                SELECT websearch_to_tsquery('simple', $1) AS query, $1::text AS term
+            )
+            SELECT kind as "kind!", id as "id!", title as "title!", subtitle,
+                highlight as "highlight!", rank as "rank!", deleted as "deleted!"
+            FROM (
+                SELECT 'material' AS kind, m.id, m.name AS title, m.description AS subtitle,
+                    ts_headline('simple', html_escape(m.name || ' ' || m.description), i.query,
+                        'StartSel=<mark>, StopSel=</mark>, MaxFragments=2') AS highlight,
+                    ts_rank(m.search_vector, i.query)
+                        + greatest(word_similarity(i.term, m.name), word_similarity(i.term, m.description)) AS rank,
+                    m.deleted_at IS NOT NULL AS deleted
+                FROM material AS m, input AS i
+                WHERE 'material' = ANY($2::text[])
+                  AND ($3::bool OR m.deleted_at IS NULL)
+                  AND (m.search_vector @@ i.query OR i.term <% m.name OR i.term <% m.description)
+                UNION ALL
+                SELECT 'supplier', s.id, s.name, s.email,
+                    ts_headline('simple', html_escape(s.name || ' ' || s.email), i.query,
+                        'StartSel=<mark>, StopSel=</mark>'),
+                    ts_rank(s.search_vector, i.query)
+                        + greatest(word_similarity(i.term, s.name), word_similarity(i.term, s.email)),
+                    s.deleted_at IS NOT NULL
+                FROM supplier AS s, input AS i
+                WHERE 'supplier' = ANY($2::text[])
+                  AND ($3::bool OR s.deleted_at IS NULL)
+                  AND (s.search_vector @@ i.query OR i.term <% s.name OR i.term <% s.email)
+                UNION ALL
+                SELECT 'material_group', g.id, g.name, g.sub_group_name,
+                    ts_headline('simple', html_escape(g.name || ' ' || g.sub_group_name), i.query,
+                        'StartSel=<mark>, StopSel=</mark>'),
+                    ts_rank(g.search_vector, i.query)
+                        + greatest(word_similarity(i.term, g.name), word_similarity(i.term, g.sub_group_name)),
+                    g.deleted_at IS NOT NULL
+                FROM material_group AS g, input AS i
+                WHERE 'material_group' = ANY($2::text[])
+                  AND ($3::bool OR g.deleted_at IS NULL)
+                  AND (g.search_vector @@ i.query OR i.term <% g.name OR i.term <% g.sub_group_name)
+            ) AS results
+            ORDER BY rank DESC, title
+            LIMIT $4;
+            "#,
+            query.term.trim(),
+            &kinds,
+            query.include_deleted,
+            query.limit
+        )
+        .fetch_all(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,21 @@
+use crate::search::model::{ResponseSearchResult, SearchQuery};
+use crate::search::repository::SearchRepository;
+use crate::util::error::LibError;
+use tracing::instrument;
+
+#[derive(Debug)]
+pub struct SearchUseCase(Box<dyn SearchRepository>);
+
+impl SearchUseCase {
+    pub fn new(repository: Box<dyn SearchRepository>) -> Self {
+        SearchUseCase(repository)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn search(&self, query: &SearchQuery) -> Result<Vec<ResponseSearchResult>, LibError> {
+        if query.term.trim().is_empty() || query.kinds.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.0.search(query).await
+    }
+}
@@ -0,0 +1,15 @@
+use crate::session::model::ResponseSession;
+use crate::util::error::LibError;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait SessionRepository: Send + Sync + std::fmt::Debug {
+    async fn get_sessions(
+        &self,
+        user_id: &Uuid,
+        current_session_id: Option<&Uuid>,
+    ) -> Result<Vec<ResponseSession>, LibError>;
+    async fn revoke_session(&self, user_id: &Uuid, session_id: &Uuid) -> Result<bool, LibError>;
+    async fn revoke_all_sessions(&self, user_id: &Uuid) -> Result<u64, LibError>;
+}
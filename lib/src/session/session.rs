@@ -0,0 +1,149 @@
+use crate::session::model::ResponseSession;
+use crate::session::repository::SessionRepository;
+use crate::user::model::{AuthBody, ClientInfo, CurrentUser, Id};
+use crate::util::error::LibError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use config::AuthConfig;
+use jsonwebtoken::encode;
+use sqlx::postgres::{PgConnection, Postgres};
+use sqlx::Pool;
+use std::sync::Arc;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct PgSessionRepository {
+    db_connect: Arc<Pool<Postgres>>,
+}
+
+impl PgSessionRepository {
+    pub async fn new(db_connect: Arc<Pool<Postgres>>) -> Self {
+        Self { db_connect }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for PgSessionRepository {
+    #[instrument(skip_all)]
+    async fn get_sessions(
+        &self,
+        user_id: &Uuid,
+        current_session_id: Option<&Uuid>,
+    ) -> Result<Vec<ResponseSession>, LibError> {
+        let query = sqlx::query_as!(
+            ResponseSession,
+            r#"
+            SELECT id, user_id, user_agent, ip_address, created_at, last_active_at, expires_at,
+                ($2::uuid IS NOT NULL AND id = $2) as "current!"
+            FROM sessions
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > now()
+            ORDER BY last_active_at DESC;
+            "#,
+            &user_id,
+            current_session_id
+        )
+        .fetch_all(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(query)
+    }
+
+    #[instrument(skip_all)]
+    async fn revoke_session(&self, user_id: &Uuid, session_id: &Uuid) -> Result<bool, LibError> {
+        let query = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET revoked_at = now()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL;
+            "#,
+            &session_id,
+            &user_id
+        )
+        .execute(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(query.rows_affected() > 0)
+    }
+
+    #[instrument(skip_all)]
+    async fn revoke_all_sessions(&self, user_id: &Uuid) -> Result<u64, LibError> {
+        let mut db_connect = self.db_connect.acquire().await?;
+        let revoked = Self::revoke_user_sessions(&mut db_connect, user_id).await?;
+        Ok(revoked)
+    }
+}
+
+impl PgSessionRepository {
+    // every successful login opens a session whose id is carried in the token as `sid`
+    pub async fn start_session(
+        db_connect: &Arc<Pool<Postgres>>,
+        user: &CurrentUser,
+        client: &ClientInfo,
+        auth: &AuthConfig,
+    ) -> Result<AuthBody, LibError> {
+        let session_id = Uuid::new_v4();
+        let claims = user.to_claims(session_id, auth.token_ttl_seconds);
+        let expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0).unwrap_or_default();
+        sqlx::query!(
+            r#"
+            INSERT INTO sessions (id, user_id, user_agent, ip_address, expires_at)
+            VALUES ($1, $2, $3, $4, $5);
+            "#,
+            &session_id,
+            &user.id,
+            client.user_agent,
+            client.ip_address,
+            expires_at
+        )
+        .execute(db_connect.clone().as_ref())
+        .await?;
+
+        let keys = &auth.keys;
+        let token = encode(&keys.header(), &claims, &keys.encoding).map_err(LibError::JwtError)?;
+        Ok(AuthBody { token })
+    }
+
+    pub async fn revoke_user_sessions(
+        db_connect: &mut PgConnection,
+        user_id: &Uuid,
+    ) -> Result<u64, sqlx::Error> {
+        let query = sqlx::query!(
+            r#"
+            UPDATE sessions
+            SET revoked_at = now()
+            WHERE user_id = $1 AND revoked_at IS NULL;
+            "#,
+            &user_id
+        )
+        .execute(db_connect)
+        .await?;
+        Ok(query.rows_affected())
+    }
+
+    // whether the session is still valid. last_active_at is only written once a minute, every
+    // authenticated request comes through here and would otherwise update the row each time.
+    pub async fn touch_session(
+        db_connect: &Arc<Pool<Postgres>>,
+        user_id: &Uuid,
+        session_id: &Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let query = sqlx::query_as!(
+            Id,
+            r#"
+            WITH session AS (
+                SELECT id, last_active_at FROM sessions
+                WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            ), touched AS (
+                UPDATE sessions
+                SET last_active_at = now()
+                WHERE id IN (SELECT id FROM session WHERE last_active_at < now() - interval '1 minute')
+            )
+            SELECT id as "id!" FROM session;
+            "#,
+            &session_id,
+            &user_id
+        )
+        .fetch_optional(db_connect.clone().as_ref())
+        .await?;
+        Ok(query.is_some())
+    }
+}
@@ -0,0 +1,43 @@
+use crate::session::model::ResponseSession;
+use crate::session::repository::SessionRepository;
+use crate::util::error::LibError;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct SessionUseCase(Box<dyn SessionRepository>);
+
+impl SessionUseCase {
+    pub fn new(repository: Box<dyn SessionRepository>) -> Self {
+        SessionUseCase(repository)
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_sessions(
+        &self,
+        user_id: &Uuid,
+        current_session_id: Option<&Uuid>,
+    ) -> Result<Vec<ResponseSession>, LibError> {
+        self.0.get_sessions(user_id, current_session_id).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn revoke_session(
+        &self,
+        user_id: &Uuid,
+        session_id: &Uuid,
+    ) -> Result<bool, LibError> {
+        match self.0.revoke_session(user_id, session_id).await {
+            Ok(r) => match r {
+                true => Ok(true),
+                false => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub async fn revoke_all_sessions(&self, user_id: &Uuid) -> Result<u64, LibError> {
+        self.0.revoke_all_sessions(user_id).await
+    }
+}
@@ -0,0 +1,321 @@
+use crate::supplier::model::{CreateSupplier, ResponseSupplier, UpdateSupplier};
+use crate::supplier::repository::SupplierRepository;
+use crate::supplier::supplier::SUPPLIER_LIST;
+use crate::util::error::LibError;
+use crate::util::memory::{naive_timestamp_key, not_found, ListRecord, MemoryDb, MemoryTables};
+use crate::util::model::{
+    check_version, BulkApplied, BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport,
+    ImportRow, ListQuery, Page,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub(crate) struct SupplierRow {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+    pub address: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub version: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+}
+
+impl SupplierRow {
+    fn to_response_supplier(&self) -> ResponseSupplier {
+        ResponseSupplier {
+            id: self.id,
+            name: self.name.clone(),
+            email: self.email.clone(),
+            phone: self.phone.clone(),
+            address: self.address.clone(),
+            created_at: self.created_at.to_string(),
+            updated_at: self.updated_at.to_string(),
+            version: self.version,
+        }
+    }
+}
+
+impl ListRecord for SupplierRow {
+    fn list_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn list_value(&self, name: &str) -> Option<String> {
+        match name {
+            "created_at" => Some(naive_timestamp_key(&self.created_at)),
+            "updated_at" => Some(naive_timestamp_key(&self.updated_at)),
+            "name" => Some(self.name.clone()),
+            "email" => Some(self.email.clone()),
+            "phone" => Some(self.phone.clone()),
+            _ => None,
+        }
+    }
+
+    fn search_values(&self) -> Vec<&str> {
+        vec![&self.name, &self.email, &self.phone, &self.address]
+    }
+}
+
+impl MemoryTables {
+    pub(crate) fn active_supplier(&self, id: &Uuid) -> Option<&SupplierRow> {
+        self.suppliers
+            .iter()
+            .find(|supplier| supplier.id == *id && supplier.deleted_at.is_none())
+    }
+
+    fn active_supplier_mut(&mut self, id: &Uuid) -> Option<&mut SupplierRow> {
+        self.suppliers
+            .iter_mut()
+            .find(|supplier| supplier.id == *id && supplier.deleted_at.is_none())
+    }
+}
+
+// SupplierRepository without a database, see MemoryDb
+#[derive(Debug)]
+pub struct MemorySupplierRepository {
+    db: Arc<MemoryDb>,
+}
+
+impl MemorySupplierRepository {
+    pub fn new(db: Arc<MemoryDb>) -> Self {
+        Self { db }
+    }
+
+    fn insert_supplier(tables: &mut MemoryTables, supplier: &CreateSupplier) -> ResponseSupplier {
+        let now = Utc::now().naive_utc();
+        let row = SupplierRow {
+            id: Uuid::new_v4(),
+            name: supplier.name.clone(),
+            email: supplier.email.clone(),
+            phone: supplier.phone.clone(),
+            address: supplier.address.clone(),
+            created_at: now,
+            updated_at: now,
+            version: 1,
+            deleted_at: None,
+            deleted_by: None,
+        };
+        let result = row.to_response_supplier();
+        tables.suppliers.push(row);
+        result
+    }
+
+    fn update_supplier(
+        tables: &mut MemoryTables,
+        id: &Uuid,
+        supplier: &UpdateSupplier,
+        version: Option<i32>,
+    ) -> Result<ResponseSupplier, LibError> {
+        let row = tables.active_supplier_mut(id).ok_or_else(not_found)?;
+        check_version(version, row.version)?;
+        if let Some(name) = &supplier.name {
+            row.name = name.clone();
+        }
+        if let Some(email) = &supplier.email {
+            row.email = email.clone();
+        }
+        if let Some(phone) = &supplier.phone {
+            row.phone = phone.clone();
+        }
+        if let Some(address) = &supplier.address {
+            row.address = address.clone();
+        }
+        row.updated_at = Utc::now().naive_utc();
+        row.version += 1;
+        Ok(row.to_response_supplier())
+    }
+
+    fn soft_delete_supplier(
+        tables: &mut MemoryTables,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let has_materials = tables
+            .materials
+            .iter()
+            .any(|material| material.supplier_id == Some(*id));
+        let Some(row) = tables.active_supplier_mut(id) else {
+            return Ok(false);
+        };
+        check_version(version, row.version)?;
+        if has_materials {
+            return Err(LibError::Conflict(
+                "Supplier is still referenced by materials".to_string(),
+            ));
+        }
+        row.deleted_at = Some(Utc::now());
+        row.deleted_by = actor.copied();
+        Ok(true)
+    }
+
+    fn active_rows(&self) -> Vec<SupplierRow> {
+        self.db.read(|tables| {
+            tables
+                .suppliers
+                .iter()
+                .filter(|supplier| supplier.deleted_at.is_none())
+                .cloned()
+                .collect()
+        })
+    }
+}
+
+#[async_trait]
+impl SupplierRepository for MemorySupplierRepository {
+    async fn get_all_suppliers(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseSupplier>, LibError> {
+        let page = SUPPLIER_LIST.fetch_memory(self.active_rows(), query)?;
+        Ok(page.map(|row| row.to_response_supplier()))
+    }
+
+    async fn create_supplier(
+        &self,
+        user: &CreateSupplier,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
+        self.db
+            .transaction(|tables| Ok(Self::insert_supplier(tables, user)))
+    }
+
+    async fn get_supplier_by_id(&self, id: &Uuid) -> Result<ResponseSupplier, LibError> {
+        self.db.read(|tables| {
+            tables
+                .active_supplier(id)
+                .map(SupplierRow::to_response_supplier)
+                .ok_or_else(not_found)
+        })
+    }
+
+    async fn delete_supplier_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        self.db
+            .transaction(|tables| Self::soft_delete_supplier(tables, id, version, actor))
+    }
+
+    async fn update_supplier_by_id(
+        &self,
+        id: &Uuid,
+        user: &UpdateSupplier,
+        version: Option<i32>,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
+        self.db
+            .transaction(|tables| Self::update_supplier(tables, id, user, version))
+    }
+
+    async fn get_deleted_suppliers(
+        &self,
+    ) -> Result<Vec<DeletedRecord<ResponseSupplier>>, LibError> {
+        let mut deleted: Vec<DeletedRecord<ResponseSupplier>> = self.db.read(|tables| {
+            tables
+                .suppliers
+                .iter()
+                .filter_map(|row| {
+                    Some(DeletedRecord {
+                        record: row.to_response_supplier(),
+                        deleted_at: row.deleted_at?,
+                        deleted_by: row.deleted_by,
+                    })
+                })
+                .collect()
+        });
+        deleted.sort_by_key(|record| std::cmp::Reverse(record.deleted_at));
+        Ok(deleted)
+    }
+
+    async fn restore_supplier_by_id(
+        &self,
+        id: &Uuid,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
+        self.db.transaction(|tables| {
+            let row = tables
+                .suppliers
+                .iter_mut()
+                .find(|row| row.id == *id && row.deleted_at.is_some())
+                .ok_or_else(not_found)?;
+            row.deleted_at = None;
+            row.deleted_by = None;
+            row.updated_at = Utc::now().naive_utc();
+            row.version += 1;
+            Ok(row.to_response_supplier())
+        })
+    }
+
+    async fn import_suppliers(
+        &self,
+        rows: &[ImportRow<CreateSupplier>],
+        dry_run: bool,
+        _actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError> {
+        if !dry_run {
+            self.db.transaction(|tables| {
+                for row in rows {
+                    Self::insert_supplier(tables, &row.record);
+                }
+                Ok(())
+            })?;
+        }
+        Ok(ImportReport {
+            dry_run,
+            total: rows.len(),
+            imported: if dry_run { 0 } else { rows.len() },
+            errors: Vec::new(),
+        })
+    }
+
+    async fn bulk_suppliers(
+        &self,
+        operations: &[BulkOperation<CreateSupplier, UpdateSupplier>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseSupplier>, LibError> {
+        Ok(self
+            .db
+            .bulk(operations, mode, |tables, operation| match operation {
+                BulkOperation::Create(supplier) => Ok(BulkApplied::Created(Self::insert_supplier(
+                    tables, supplier,
+                ))),
+                BulkOperation::Update {
+                    id,
+                    version,
+                    record,
+                } => Self::update_supplier(tables, id, record, *version).map(BulkApplied::Updated),
+                BulkOperation::Delete { id, version } => {
+                    match Self::soft_delete_supplier(tables, id, *version, actor)? {
+                        true => Ok(BulkApplied::Deleted(*id)),
+                        false => Err(not_found()),
+                    }
+                }
+            }))
+    }
+
+    async fn export_suppliers(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseSupplier>,
+    ) -> Result<(), LibError> {
+        SUPPLIER_LIST
+            .export_memory(
+                self.active_rows(),
+                query,
+                |row| row.to_response_supplier(),
+                sender,
+            )
+            .await
+    }
+}
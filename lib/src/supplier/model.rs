@@ -1,8 +1,10 @@
-use chrono::NaiveDateTime;
+use crate::util::model::DeletedRecord;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct QuerySupplier {
     pub id: Uuid,
     pub name: String,
@@ -11,6 +13,9 @@ pub struct QuerySupplier {
     pub address: String,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+    pub version: i32,
 }
 
 impl QuerySupplier {
@@ -23,6 +28,15 @@ impl QuerySupplier {
             address: self.address.clone(),
             created_at: self.created_at.unwrap().to_string(),
             updated_at: self.updated_at.unwrap().to_string(),
+            version: self.version,
+        }
+    }
+
+    pub fn to_deleted_supplier(&self) -> DeletedRecord<ResponseSupplier> {
+        DeletedRecord {
+            record: self.to_response_supplier(),
+            deleted_at: self.deleted_at.unwrap_or_default(),
+            deleted_by: self.deleted_by,
         }
     }
 }
@@ -43,7 +57,7 @@ pub struct UpdateSupplier {
     pub address: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResponseSupplier {
     pub id: Uuid,
     pub name: String,
@@ -52,4 +66,6 @@ pub struct ResponseSupplier {
     pub address: String,
     pub created_at: String,
     pub updated_at: String,
+    // bumped on every change, sent back as ETag
+    pub version: i32,
 }
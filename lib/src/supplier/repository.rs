@@ -1,17 +1,59 @@
 use crate::supplier::model::{CreateSupplier, ResponseSupplier, UpdateSupplier};
 use crate::util::error::LibError;
+use crate::util::model::{
+    BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport, ImportRow, ListQuery, Page,
+};
 use async_trait::async_trait;
+use tokio::sync::mpsc::Sender;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait SupplierRepository: Send + Sync + std::fmt::Debug {
-    async fn get_all_suppliers(&self) -> Result<Vec<ResponseSupplier>, LibError>;
-    async fn create_supplier(&self, user: &CreateSupplier) -> Result<ResponseSupplier, LibError>;
+    async fn get_all_suppliers(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseSupplier>, LibError>;
+    async fn create_supplier(
+        &self,
+        user: &CreateSupplier,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError>;
     async fn get_supplier_by_id(&self, id: &Uuid) -> Result<ResponseSupplier, LibError>;
-    async fn delete_supplier_by_id(&self, id: &Uuid) -> Result<bool, LibError>;
+    async fn delete_supplier_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError>;
     async fn update_supplier_by_id(
         &self,
         id: &Uuid,
         user: &UpdateSupplier,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseSupplier, LibError>;
+    async fn get_deleted_suppliers(&self)
+        -> Result<Vec<DeletedRecord<ResponseSupplier>>, LibError>;
+    async fn restore_supplier_by_id(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError>;
+    async fn import_suppliers(
+        &self,
+        rows: &[ImportRow<CreateSupplier>],
+        dry_run: bool,
+        actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError>;
+    async fn bulk_suppliers(
+        &self,
+        operations: &[BulkOperation<CreateSupplier, UpdateSupplier>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseSupplier>, LibError>;
+    async fn export_suppliers(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseSupplier>,
+    ) -> Result<(), LibError>;
 }
@@ -1,13 +1,71 @@
+use crate::audit::audit::PgAuditLogRepository;
+use crate::audit::model::{AuditEntityType, AuditEntry};
 use crate::supplier::model::{CreateSupplier, ResponseSupplier, UpdateSupplier};
 use crate::supplier::repository::SupplierRepository;
 use crate::util::error::LibError;
+use crate::util::list::{ColumnType, ListColumn, ListSpec};
+use crate::util::model::{
+    check_version, BulkApplied, BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport,
+    ImportRow, ListQuery, Page,
+};
+use crate::util::postgres::{end_bulk, end_savepoint};
 use async_trait::async_trait;
-use sqlx::postgres::Postgres;
-use sqlx::Pool;
+use sqlx::postgres::{PgConnection, Postgres};
+use sqlx::{Connection, Pool};
 use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
 use uuid::Uuid;
 
 use super::model::QuerySupplier;
+use tracing::instrument;
+
+pub(crate) static SUPPLIER_LIST: ListSpec = ListSpec {
+    select:
+        "SELECT id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version",
+    from: " FROM supplier",
+    id_column: "id",
+    condition: "deleted_at IS NULL",
+    sort_columns: &[
+        ListColumn {
+            name: "created_at",
+            column: "created_at",
+            column_type: ColumnType::Timestamp,
+        },
+        ListColumn {
+            name: "updated_at",
+            column: "updated_at",
+            column_type: ColumnType::Timestamp,
+        },
+        ListColumn {
+            name: "name",
+            column: "name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "email",
+            column: "email",
+            column_type: ColumnType::Text,
+        },
+    ],
+    filter_columns: &[
+        ListColumn {
+            name: "name",
+            column: "name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "email",
+            column: "email",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "phone",
+            column: "phone",
+            column_type: ColumnType::Text,
+        },
+    ],
+    search_columns: &["name", "email", "phone", "address"],
+};
 
 #[derive(Debug)]
 pub struct PgSupplierRepository {
@@ -23,73 +81,256 @@ impl PgSupplierRepository {
 
 #[async_trait]
 impl SupplierRepository for PgSupplierRepository {
-    async fn get_all_suppliers(&self) -> Result<Vec<ResponseSupplier>, LibError> {
+    #[instrument(skip_all)]
+    async fn get_all_suppliers(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseSupplier>, LibError> {
+        let page = SUPPLIER_LIST
+            .fetch::<QuerySupplier>(self.db_connect.as_ref(), query)
+            .await?;
+        Ok(page.map(|query| query.to_response_supplier()))
+    }
+
+    #[instrument(skip_all)]
+    async fn create_supplier(
+        &self,
+        user: &CreateSupplier,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let result = Self::insert_supplier(&mut tx, user, actor).await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_supplier_by_id(&self, id: &uuid::Uuid) -> Result<ResponseSupplier, LibError> {
         let query = sqlx::query_as!(
             QuerySupplier,
             r#"
-            SELECT * FROM supplier
-            "#
+            SELECT id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version FROM supplier
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            id
         )
-        .fetch_all(self.db_connect.clone().as_ref())
+        .fetch_one(self.db_connect.clone().as_ref())
         .await?;
-        let result: Vec<ResponseSupplier> = query
-            .into_iter()
-            .map(|query: QuerySupplier| query.to_response_supplier())
-            .collect();
+        Ok(query.to_response_supplier())
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn delete_supplier_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let deleted = Self::soft_delete_supplier(&mut tx, id, version, actor).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn update_supplier_by_id(
+        &self,
+        id: &Uuid,
+        user: &UpdateSupplier,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let result = Self::update_supplier(&mut tx, id, user, version, actor).await?;
+        tx.commit().await?;
         Ok(result)
     }
 
-    async fn create_supplier(&self, user: &CreateSupplier) -> Result<ResponseSupplier, LibError> {
+    #[instrument(skip_all)]
+    async fn get_deleted_suppliers(
+        &self,
+    ) -> Result<Vec<DeletedRecord<ResponseSupplier>>, LibError> {
         let query = sqlx::query_as!(
             QuerySupplier,
             r#"
-            INSERT INTO supplier (name, address, phone, email)
-            VALUES ($1, $2, $3, $4)
-            RETURNING *
-            "#,
-            user.name,
-            user.address,
-            user.phone,
-            user.email
+            SELECT id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version FROM supplier
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC
+            "#
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_all(self.db_connect.clone().as_ref())
         .await?;
-        Ok(query.to_response_supplier())
+        let result = query
+            .iter()
+            .map(|query: &QuerySupplier| query.to_deleted_supplier())
+            .collect();
+        Ok(result)
     }
 
-    async fn get_supplier_by_id(&self, id: &uuid::Uuid) -> Result<ResponseSupplier, LibError> {
+    #[instrument(skip_all, fields(%id))]
+    async fn restore_supplier_by_id(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
+        let mut tx = self.db_connect.begin().await?;
         let query = sqlx::query_as!(
             QuerySupplier,
             r#"
-            SELECT * FROM supplier
-            WHERE id = $1
+            UPDATE supplier
+            SET deleted_at = NULL,
+                deleted_by = NULL,
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version
             "#,
             id
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *tx)
         .await?;
-        Ok(query.to_response_supplier())
+        let result = query.to_response_supplier();
+        PgAuditLogRepository::record(
+            &mut tx,
+            &AuditEntry::restored(actor, AuditEntityType::Supplier, result.id, &result),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(result)
     }
 
-    async fn delete_supplier_by_id(&self, id: &Uuid) -> Result<bool, LibError> {
+    #[instrument(skip_all)]
+    async fn import_suppliers(
+        &self,
+        rows: &[ImportRow<CreateSupplier>],
+        dry_run: bool,
+        actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError> {
+        if dry_run {
+            return Ok(ImportReport {
+                dry_run,
+                total: rows.len(),
+                imported: 0,
+                errors: Vec::new(),
+            });
+        }
+        let mut tx = self.db_connect.begin().await?;
+        for row in rows {
+            Self::insert_supplier(&mut tx, &row.record, actor).await?;
+        }
+        tx.commit().await?;
+        Ok(ImportReport {
+            dry_run,
+            total: rows.len(),
+            imported: rows.len(),
+            errors: Vec::new(),
+        })
+    }
+
+    #[instrument(skip_all)]
+    async fn bulk_suppliers(
+        &self,
+        operations: &[BulkOperation<CreateSupplier, UpdateSupplier>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseSupplier>, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let mut outcomes = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let mut savepoint = Connection::begin(&mut *tx).await?;
+            let outcome = match operation {
+                BulkOperation::Create(supplier) => {
+                    Self::insert_supplier(&mut savepoint, supplier, actor)
+                        .await
+                        .map(BulkApplied::Created)
+                }
+                BulkOperation::Update {
+                    id,
+                    version,
+                    record,
+                } => Self::update_supplier(&mut savepoint, id, record, *version, actor)
+                    .await
+                    .map(BulkApplied::Updated),
+                BulkOperation::Delete { id, version } => {
+                    match Self::soft_delete_supplier(&mut savepoint, id, *version, actor).await {
+                        Ok(true) => Ok(BulkApplied::Deleted(*id)),
+                        Ok(false) => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+            end_savepoint(savepoint, &outcome).await?;
+            outcomes.push(outcome);
+        }
+        end_bulk(tx, mode, outcomes).await
+    }
+
+    #[instrument(skip_all)]
+    async fn export_suppliers(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseSupplier>,
+    ) -> Result<(), LibError> {
+        SUPPLIER_LIST
+            .export(
+                self.db_connect.as_ref(),
+                query,
+                |query: QuerySupplier| query.to_response_supplier(),
+                sender,
+            )
+            .await
+    }
+}
+
+impl PgSupplierRepository {
+    pub async fn insert_supplier(
+        db_connect: &mut PgConnection,
+        user: &CreateSupplier,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
         let query = sqlx::query_as!(
             QuerySupplier,
             r#"
-            DELETE FROM supplier 
-            WHERE id = $1
+            INSERT INTO supplier (name, address, phone, email)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version
             "#,
-            &id
+            user.name,
+            user.address,
+            user.phone,
+            user.email
+        )
+        .fetch_one(&mut *db_connect)
+        .await?;
+        let result = query.to_response_supplier();
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::created(actor, AuditEntityType::Supplier, result.id, &result),
         )
-        .execute(self.db_connect.clone().as_ref())
         .await?;
-        Ok(query.rows_affected() > 0)
+        Ok(result)
     }
 
-    async fn update_supplier_by_id(
-        &self,
+    pub async fn update_supplier(
+        db_connect: &mut PgConnection,
         id: &Uuid,
         user: &UpdateSupplier,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseSupplier, LibError> {
+        let before = sqlx::query_as!(
+            QuerySupplier,
+            r#"
+            SELECT id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version FROM supplier
+            WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_one(&mut *db_connect)
+        .await?
+        .to_response_supplier();
+        check_version(version, before.version)?;
         let query = sqlx::query_as!(
             QuerySupplier,
             r#"
@@ -98,9 +339,10 @@ impl SupplierRepository for PgSupplierRepository {
                 address = COALESCE($2, address), 
                 phone = COALESCE($3, phone),
                 email = COALESCE($4, email),
-                updated_at = now()
-            WHERE id = $5
-            RETURNING *
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $5 AND deleted_at IS NULL
+            RETURNING id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version
             "#,
             user.name,
             user.address,
@@ -108,8 +350,76 @@ impl SupplierRepository for PgSupplierRepository {
             user.email,
             id
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *db_connect)
         .await?;
-        Ok(query.to_response_supplier())
+        let result = query.to_response_supplier();
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::updated(
+                actor,
+                AuditEntityType::Supplier,
+                result.id,
+                &before,
+                &result,
+            ),
+        )
+        .await?;
+        Ok(result)
+    }
+
+    pub async fn soft_delete_supplier(
+        db_connect: &mut PgConnection,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let query = sqlx::query_as!(
+            QuerySupplier,
+            r#"
+            UPDATE supplier
+            SET deleted_at = now(),
+                deleted_by = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, name, email, phone, address, created_at, updated_at, deleted_at, deleted_by, version
+            "#,
+            &id,
+            actor
+        )
+        .fetch_optional(&mut *db_connect)
+        .await?;
+        let Some(deleted) = query else {
+            return Ok(false);
+        };
+        check_version(version, deleted.version)?;
+        if Self::has_materials(db_connect, id).await? {
+            return Err(LibError::Conflict(
+                "Supplier is still referenced by materials".to_string(),
+            ));
+        }
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::deleted(
+                actor,
+                AuditEntityType::Supplier,
+                deleted.id,
+                &deleted.to_response_supplier(),
+            ),
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn has_materials(db_connect: &mut PgConnection, id: &Uuid) -> Result<bool, LibError> {
+        let query = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM material WHERE supplier_id = $1 AND deleted_at IS NULL
+            ) as "exists!"
+            "#,
+            id
+        )
+        .fetch_one(db_connect)
+        .await?;
+        Ok(query)
     }
 }
@@ -1,6 +1,11 @@
 use crate::supplier::model::{CreateSupplier, ResponseSupplier, UpdateSupplier};
 use crate::supplier::repository::SupplierRepository;
 use crate::util::error::LibError;
+use crate::util::model::{
+    BulkMode, BulkOperation, BulkResult, DeletedRecord, ImportReport, ImportRow, ListQuery, Page,
+};
+use tokio::sync::mpsc::Sender;
+use tracing::instrument;
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -11,23 +16,36 @@ impl SupplierUseCase {
         SupplierUseCase(repository)
     }
 
-    pub async fn get_all_suppliers(&self) -> Result<Vec<ResponseSupplier>, LibError> {
-        self.0.get_all_suppliers().await
+    #[instrument(skip_all)]
+    pub async fn get_all_suppliers(
+        &self,
+        query: &ListQuery,
+    ) -> Result<Page<ResponseSupplier>, LibError> {
+        self.0.get_all_suppliers(query).await
     }
 
+    #[instrument(skip_all)]
     pub async fn create_supplier(
         &self,
         supplier: &CreateSupplier,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseSupplier, LibError> {
-        self.0.create_supplier(supplier).await
+        self.0.create_supplier(supplier, actor).await
     }
 
+    #[instrument(skip_all, fields(%id))]
     pub async fn get_supplier_by_id(&self, id: &Uuid) -> Result<ResponseSupplier, LibError> {
         self.0.get_supplier_by_id(id).await
     }
 
-    pub async fn delete_supplier_by_id(&self, id: &Uuid) -> Result<bool, LibError> {
-        match self.0.delete_supplier_by_id(id).await {
+    #[instrument(skip_all, fields(%id))]
+    pub async fn delete_supplier_by_id(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        match self.0.delete_supplier_by_id(id, version, actor).await {
             Ok(r) => match r {
                 true => Ok(true),
                 false => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
@@ -36,11 +54,345 @@ impl SupplierUseCase {
         }
     }
 
+    #[instrument(skip_all, fields(%id))]
     pub async fn update_supplier_by_id(
         &self,
         id: &Uuid,
         supplier: &UpdateSupplier,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseSupplier, LibError> {
-        self.0.update_supplier_by_id(id, supplier).await
+        self.0
+            .update_supplier_by_id(id, supplier, version, actor)
+            .await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_deleted_suppliers(
+        &self,
+    ) -> Result<Vec<DeletedRecord<ResponseSupplier>>, LibError> {
+        self.0.get_deleted_suppliers().await
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    pub async fn restore_supplier_by_id(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseSupplier, LibError> {
+        self.0.restore_supplier_by_id(id, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn import_suppliers(
+        &self,
+        rows: &[ImportRow<CreateSupplier>],
+        dry_run: bool,
+        actor: Option<&Uuid>,
+    ) -> Result<ImportReport, LibError> {
+        self.0.import_suppliers(rows, dry_run, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn bulk_suppliers(
+        &self,
+        operations: &[BulkOperation<CreateSupplier, UpdateSupplier>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseSupplier>, LibError> {
+        self.0.bulk_suppliers(operations, mode, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn export_suppliers(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseSupplier>,
+    ) -> Result<(), LibError> {
+        self.0.export_suppliers(query, sender).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::memory::MemoryMaterialRepository;
+    use crate::material::model::CreateMaterial;
+    use crate::material::repository::MaterialRepository;
+    use crate::supplier::memory::MemorySupplierRepository;
+    use crate::util::memory::MemoryDb;
+    use crate::util::model::SortDirection;
+    use chrono::NaiveDate;
+    use std::sync::Arc;
+
+    fn use_case() -> (SupplierUseCase, Arc<MemoryDb>) {
+        let db = Arc::new(MemoryDb::new());
+        let use_case = SupplierUseCase::new(Box::new(MemorySupplierRepository::new(db.clone())));
+        (use_case, db)
+    }
+
+    fn supplier(name: &str) -> CreateSupplier {
+        CreateSupplier {
+            name: name.to_string(),
+            email: format!("{}@example.com", name.to_lowercase()),
+            phone: "0123456789".to_string(),
+            address: format!("{name} street 1"),
+        }
+    }
+
+    fn no_changes() -> UpdateSupplier {
+        UpdateSupplier {
+            name: None,
+            email: None,
+            phone: None,
+            address: None,
+        }
+    }
+
+    fn names(page: &Page<ResponseSupplier>) -> Vec<&str> {
+        page.items
+            .iter()
+            .map(|supplier| supplier.name.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn suppliers_are_created_and_updated() {
+        let (use_case, _) = use_case();
+        let created = use_case
+            .create_supplier(&supplier("Acme"), None)
+            .await
+            .unwrap();
+        let changes = UpdateSupplier {
+            phone: Some("999".to_string()),
+            ..no_changes()
+        };
+        let updated = use_case
+            .update_supplier_by_id(&created.id, &changes, Some(1), None)
+            .await
+            .unwrap();
+        assert_eq!((updated.phone.as_str(), updated.version), ("999", 2));
+        assert_eq!(updated.name, "Acme");
+
+        let err = use_case
+            .update_supplier_by_id(&created.id, &changes, Some(1), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::PreconditionFailed(_)));
+        let err = use_case
+            .update_supplier_by_id(&Uuid::new_v4(), &changes, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::SqlxError(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn suppliers_are_listed_filtered_and_searched() {
+        let (use_case, _) = use_case();
+        for name in ["Acme", "Globex", "Initech"] {
+            use_case
+                .create_supplier(&supplier(name), None)
+                .await
+                .unwrap();
+        }
+        let query = ListQuery {
+            sort: Some("name".to_string()),
+            direction: SortDirection::Desc,
+            ..ListQuery::default()
+        };
+        let page = use_case.get_all_suppliers(&query).await.unwrap();
+        assert_eq!(names(&page), ["Initech", "Globex", "Acme"]);
+
+        let query = ListQuery {
+            filters: vec![("email".to_string(), "GLOBEX@example.com".to_string())],
+            ..ListQuery::default()
+        };
+        let page = use_case.get_all_suppliers(&query).await.unwrap();
+        assert_eq!(names(&page), ["Globex"]);
+
+        let query = ListQuery {
+            search: Some("initech street".to_string()),
+            ..ListQuery::default()
+        };
+        let page = use_case.get_all_suppliers(&query).await.unwrap();
+        assert_eq!(names(&page), ["Initech"]);
+
+        let query = ListQuery {
+            sort: Some("name".to_string()),
+            page: 2,
+            limit: 2,
+            ..ListQuery::default()
+        };
+        let page = use_case.get_all_suppliers(&query).await.unwrap();
+        assert_eq!(names(&page), ["Initech"]);
+        assert_eq!((page.total, page.page), (3, Some(2)));
+
+        let query = ListQuery {
+            page: i64::MAX,
+            ..ListQuery::default()
+        };
+        assert!(matches!(
+            use_case.get_all_suppliers(&query).await,
+            Err(LibError::InvalidInput(_))
+        ));
+
+        let query = ListQuery {
+            filters: vec![("address".to_string(), "x".to_string())],
+            ..ListQuery::default()
+        };
+        assert!(matches!(
+            use_case.get_all_suppliers(&query).await,
+            Err(LibError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleted_suppliers_can_be_restored() {
+        let (use_case, _) = use_case();
+        let created = use_case
+            .create_supplier(&supplier("Acme"), None)
+            .await
+            .unwrap();
+        use_case
+            .delete_supplier_by_id(&created.id, None, None)
+            .await
+            .unwrap();
+        assert!(use_case.get_supplier_by_id(&created.id).await.is_err());
+        let err = use_case
+            .delete_supplier_by_id(&created.id, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::SqlxError(sqlx::Error::RowNotFound)));
+
+        let deleted = use_case.get_deleted_suppliers().await.unwrap();
+        assert_eq!(deleted[0].record.id, created.id);
+        let restored = use_case
+            .restore_supplier_by_id(&created.id, None)
+            .await
+            .unwrap();
+        assert_eq!(restored.version, 2);
+    }
+
+    #[tokio::test]
+    async fn suppliers_with_materials_are_not_deleted() {
+        let (use_case, db) = use_case();
+        let created = use_case
+            .create_supplier(&supplier("Acme"), None)
+            .await
+            .unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let material = CreateMaterial {
+            name: "Bolt".to_string(),
+            price: 10,
+            description: String::new(),
+            quantity: 1,
+            mfg_date: date,
+            exp_date: date,
+            supplier_id: Some(created.id),
+            group_id: None,
+        };
+        MemoryMaterialRepository::new(db)
+            .import_materials(
+                &[ImportRow {
+                    row: 2,
+                    record: material,
+                }],
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        let err = use_case
+            .delete_supplier_by_id(&created.id, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn imports_write_nothing_on_a_dry_run() {
+        let (use_case, _) = use_case();
+        let rows: Vec<_> = ["Acme", "Globex"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| ImportRow {
+                row: i + 2,
+                record: supplier(name),
+            })
+            .collect();
+        let report = use_case.import_suppliers(&rows, true, None).await.unwrap();
+        assert_eq!((report.total, report.imported), (2, 0));
+        let page = use_case
+            .get_all_suppliers(&ListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.total, 0);
+
+        let report = use_case.import_suppliers(&rows, false, None).await.unwrap();
+        assert_eq!(report.imported, 2);
+        let page = use_case
+            .get_all_suppliers(&ListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(page.total, 2);
+    }
+
+    #[tokio::test]
+    async fn best_effort_bulk_keeps_the_items_that_succeed() {
+        let (use_case, _) = use_case();
+        let existing = use_case
+            .create_supplier(&supplier("Acme"), None)
+            .await
+            .unwrap();
+        let operations = [
+            BulkOperation::Create(supplier("Globex")),
+            BulkOperation::Update {
+                id: existing.id,
+                version: Some(5),
+                record: no_changes(),
+            },
+            BulkOperation::Delete {
+                id: existing.id,
+                version: Some(1),
+            },
+        ];
+        let result = use_case
+            .bulk_suppliers(&operations, BulkMode::BestEffort, None)
+            .await
+            .unwrap();
+        assert!(result.committed);
+        assert!(matches!(
+            result.outcomes[1],
+            Err(LibError::PreconditionFailed(_))
+        ));
+        let page = use_case
+            .get_all_suppliers(&ListQuery::default())
+            .await
+            .unwrap();
+        assert_eq!(names(&page), ["Globex"]);
+    }
+
+    #[tokio::test]
+    async fn export_sends_every_supplier() {
+        let (use_case, _) = use_case();
+        for name in ["Acme", "Globex", "Initech"] {
+            use_case
+                .create_supplier(&supplier(name), None)
+                .await
+                .unwrap();
+        }
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        let query = ListQuery {
+            sort: Some("name".to_string()),
+            search: Some("e".to_string()),
+            ..ListQuery::default()
+        };
+        use_case.export_suppliers(&query, &sender).await.unwrap();
+        drop(sender);
+        let mut exported = Vec::new();
+        while let Some(supplier) = receiver.recv().await {
+            exported.push(supplier.name);
+        }
+        assert_eq!(exported, ["Acme", "Globex", "Initech"]);
     }
 }
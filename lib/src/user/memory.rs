@@ -0,0 +1,606 @@
+use crate::user::model::{
+    AuthBody, ClientInfo, CreateUser, CurrentUser, LoginEventFilter, LoginEventReason,
+    ResponseLoginEvent, ResponseUser, UpdateUser,
+};
+use crate::user::repository::UserRepository;
+use crate::user::user::USER_LIST;
+use crate::util::error::{ConstraintError, LibError};
+use crate::util::memory::{naive_timestamp_key, not_found, ListRecord, MemoryDb, MemoryTables};
+use crate::util::model::{
+    check_version, BulkApplied, BulkMode, BulkOperation, BulkResult, DeletedRecord, ListQuery, Page,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use config::{AuthConfig, Keys, LoginPolicy};
+use jsonwebtoken::encode;
+use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use uuid::Uuid;
+
+// the lowest cost bcrypt accepts, hashing at the default cost would dominate every test
+const HASH_COST: u32 = 4;
+
+#[derive(Debug, Clone)]
+pub(crate) struct UserRow {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub hash: String,
+    pub address: String,
+    pub role_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub version: i32,
+    pub failed_login_attempts: i32,
+    pub last_failed_login_at: Option<DateTime<Utc>>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<Uuid>,
+}
+
+// a user row joined with the name of its role, what the list and the responses are built from
+#[derive(Debug, Clone)]
+pub(crate) struct UserView {
+    row: UserRow,
+    role_name: String,
+}
+
+impl UserView {
+    fn to_response_user(&self) -> ResponseUser {
+        ResponseUser {
+            id: self.row.id,
+            name: self.row.name.clone(),
+            email: self.row.email.clone(),
+            address: self.row.address.clone(),
+            role_id: self.row.role_id,
+            role_name: self.role_name.clone(),
+            created_at: self.row.created_at.to_string(),
+            updated_at: self.row.updated_at.to_string(),
+            version: self.row.version,
+        }
+    }
+}
+
+impl ListRecord for UserView {
+    fn list_id(&self) -> Uuid {
+        self.row.id
+    }
+
+    fn list_value(&self, name: &str) -> Option<String> {
+        match name {
+            "created_at" => Some(naive_timestamp_key(&self.row.created_at)),
+            "name" => Some(self.row.name.clone()),
+            "email" => Some(self.row.email.clone()),
+            "role" => Some(self.role_name.clone()),
+            "role_id" => Some(self.row.role_id.to_string()),
+            _ => None,
+        }
+    }
+
+    fn search_values(&self) -> Vec<&str> {
+        vec![&self.row.name, &self.row.email, &self.row.address]
+    }
+}
+
+impl MemoryTables {
+    fn user_view(&self, row: &UserRow) -> UserView {
+        let role_name = self
+            .roles
+            .iter()
+            .find(|role| role.id == row.role_id)
+            .map(|role| role.name.clone())
+            .unwrap_or_default();
+        UserView {
+            row: row.clone(),
+            role_name,
+        }
+    }
+
+    fn active_user(&self, id: &Uuid) -> Result<UserView, LibError> {
+        self.users
+            .iter()
+            .find(|user| user.id == *id && user.deleted_at.is_none())
+            .map(|user| self.user_view(user))
+            .ok_or_else(not_found)
+    }
+
+    fn active_user_mut(&mut self, id: &Uuid) -> Result<&mut UserRow, LibError> {
+        self.users
+            .iter_mut()
+            .find(|user| user.id == *id && user.deleted_at.is_none())
+            .ok_or_else(not_found)
+    }
+
+    // names and emails are unique among users that are not deleted, like the indexes
+    fn check_user_unique(
+        &self,
+        name: Option<&String>,
+        email: Option<&String>,
+        id: Option<&Uuid>,
+    ) -> Result<(), LibError> {
+        for user in self
+            .users
+            .iter()
+            .filter(|user| Some(&user.id) != id && user.deleted_at.is_none())
+        {
+            let field = if name == Some(&user.name) {
+                "name"
+            } else if email == Some(&user.email) {
+                "email"
+            } else {
+                continue;
+            };
+            return Err(LibError::Constraint(ConstraintError::Duplicate {
+                field: field.to_string(),
+            }));
+        }
+        Ok(())
+    }
+
+    fn record_login_event(
+        &mut self,
+        user_id: Option<&Uuid>,
+        email: &str,
+        client: &ClientInfo,
+        reason: LoginEventReason,
+    ) {
+        self.login_events.push(ResponseLoginEvent {
+            id: Uuid::new_v4(),
+            user_id: user_id.copied(),
+            email: email.to_string(),
+            ip_address: client.ip_address.clone(),
+            success: reason == LoginEventReason::Success,
+            reason: reason.to_str().to_string(),
+            created_at: Utc::now(),
+        });
+    }
+}
+
+// UserRepository without a database, see MemoryDb; tokens are signed with `keys`
+// and no session is stored for them
+pub struct MemoryUserRepository {
+    db: Arc<MemoryDb>,
+    login_policy: LoginPolicy,
+    keys: Keys,
+}
+
+impl MemoryUserRepository {
+    pub fn new(db: Arc<MemoryDb>, login_policy: LoginPolicy, keys: Keys) -> Self {
+        Self {
+            db,
+            login_policy,
+            keys,
+        }
+    }
+
+    fn insert_user(
+        tables: &mut MemoryTables,
+        user: &CreateUser,
+        hashed_password: String,
+    ) -> Result<ResponseUser, LibError> {
+        let role_id = tables.find_role(&user.role.to_str())?;
+        tables.check_user_unique(Some(&user.name), Some(&user.email), None)?;
+        let now = Utc::now().naive_utc();
+        let row = UserRow {
+            id: Uuid::new_v4(),
+            name: user.name.clone(),
+            email: user.email.clone(),
+            hash: hashed_password,
+            address: user.address.clone(),
+            role_id,
+            created_at: now,
+            updated_at: now,
+            version: 1,
+            failed_login_attempts: 0,
+            last_failed_login_at: None,
+            locked_until: None,
+            deleted_at: None,
+            deleted_by: None,
+        };
+        let result = tables.user_view(&row).to_response_user();
+        tables.users.push(row);
+        Ok(result)
+    }
+
+    fn update_user_record(
+        tables: &mut MemoryTables,
+        id: &Uuid,
+        user: &UpdateUser,
+        hashed_password: Option<String>,
+        version: Option<i32>,
+    ) -> Result<ResponseUser, LibError> {
+        let role_id = match &user.role {
+            Some(role) => Some(tables.find_role(&role.to_str())?),
+            None => None,
+        };
+        tables.check_user_unique(user.name.as_ref(), user.email.as_ref(), Some(id))?;
+        let row = tables.active_user_mut(id)?;
+        check_version(version, row.version)?;
+        if let Some(name) = &user.name {
+            row.name = name.clone();
+        }
+        if let Some(email) = &user.email {
+            row.email = email.clone();
+        }
+        if let Some(hash) = hashed_password {
+            row.hash = hash;
+        }
+        if let Some(address) = &user.address {
+            row.address = address.clone();
+        }
+        if let Some(role_id) = role_id {
+            row.role_id = role_id;
+        }
+        row.updated_at = Utc::now().naive_utc();
+        row.version += 1;
+        tables.active_user(id).map(|user| user.to_response_user())
+    }
+
+    fn soft_delete_user(
+        tables: &mut MemoryTables,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let Ok(row) = tables.active_user_mut(id) else {
+            return Ok(false);
+        };
+        check_version(version, row.version)?;
+        row.deleted_at = Some(Utc::now());
+        row.deleted_by = actor.copied();
+        Ok(true)
+    }
+
+    fn hash(password: &str) -> Result<String, LibError> {
+        bcrypt::hash(password, HASH_COST).map_err(LibError::BcryptError)
+    }
+}
+
+impl std::fmt::Debug for MemoryUserRepository {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryUserRepository")
+            .field("db", &self.db)
+            .field("login_policy", &self.login_policy)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl UserRepository for MemoryUserRepository {
+    async fn hash_password(&self, password: &str) -> Result<String, bcrypt::BcryptError> {
+        bcrypt::hash(password, HASH_COST)
+    }
+
+    async fn verify_password(
+        &self,
+        password: &str,
+        hash: &str,
+    ) -> Result<bool, bcrypt::BcryptError> {
+        bcrypt::verify(password, hash)
+    }
+
+    async fn create_user(
+        &self,
+        user: &CreateUser,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        let hashed_password = Self::hash(&user.password)?;
+        self.db
+            .transaction(|tables| Self::insert_user(tables, user, hashed_password))
+    }
+
+    async fn update_user(
+        &self,
+        id: &Uuid,
+        user: &UpdateUser,
+        version: Option<i32>,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        let hashed_password = user.password.as_deref().map(Self::hash).transpose()?;
+        self.db.transaction(|tables| {
+            Self::update_user_record(tables, id, user, hashed_password, version)
+        })
+    }
+
+    async fn delete_user(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        self.db
+            .transaction(|tables| Self::soft_delete_user(tables, id, version, actor))
+    }
+
+    async fn get_user_by_id(&self, id: &Uuid) -> Result<ResponseUser, LibError> {
+        self.db
+            .read(|tables| tables.active_user(id).map(|user| user.to_response_user()))
+    }
+
+    async fn get_users(&self, query: &ListQuery) -> Result<Page<ResponseUser>, LibError> {
+        let rows = self.db.read(|tables| {
+            tables
+                .users
+                .iter()
+                .filter(|user| user.deleted_at.is_none())
+                .map(|user| tables.user_view(user))
+                .collect()
+        });
+        let page = USER_LIST.fetch_memory(rows, query)?;
+        Ok(page.map(|user| user.to_response_user()))
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<CurrentUser, LibError> {
+        self.db.read(|tables| {
+            let user = tables
+                .users
+                .iter()
+                .find(|user| user.email == email && user.deleted_at.is_none())
+                .map(|user| tables.user_view(user))
+                .ok_or_else(not_found)?;
+            Ok(CurrentUser {
+                id: user.row.id,
+                name: user.row.name,
+                email: user.row.email,
+                hashed_password: user.row.hash,
+                role_name: user.role_name,
+                failed_login_attempts: user.row.failed_login_attempts,
+                last_failed_login_at: user.row.last_failed_login_at,
+                locked_until: user.row.locked_until,
+            })
+        })
+    }
+
+    async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, LibError> {
+        let now = Utc::now();
+        if let Some(ip_address) = &client.ip_address {
+            let since = now - Duration::seconds(self.login_policy.attempt_window_seconds);
+            let blocked = self.db.transaction(|tables| {
+                let failures = tables
+                    .login_events
+                    .iter()
+                    .filter(|event| {
+                        event.ip_address.as_ref() == Some(ip_address)
+                            && (event.reason == LoginEventReason::InvalidPassword.to_str()
+                                || event.reason == LoginEventReason::UnknownEmail.to_str())
+                            && event.created_at > since
+                    })
+                    .count() as i64;
+                let blocked = failures >= self.login_policy.ip_max_attempts;
+                if blocked {
+                    tables.record_login_event(None, email, client, LoginEventReason::IpBlocked);
+                }
+                Ok(blocked)
+            })?;
+            if blocked {
+                return Err(LibError::TooManyRequests(
+                    "Too many failed login attempts, try again later".to_string(),
+                ));
+            }
+        }
+
+        let user = match self.get_user_by_email(email).await {
+            Ok(user) => user,
+            Err(LibError::SqlxError(sqlx::Error::RowNotFound)) => {
+                self.db.transaction(|tables| {
+                    tables.record_login_event(None, email, client, LoginEventReason::UnknownEmail);
+                    Ok(())
+                })?;
+                return Err(LibError::Unauthorized(
+                    "Invalid email or password".to_string(),
+                ));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let is_valid = self
+            .verify_password(password, &user.hashed_password)
+            .await
+            .map_err(LibError::BcryptError)?;
+        // refused like a wrong password, as in the Postgres repository
+        let refused = match user.is_locked(now) {
+            true => Some(LoginEventReason::AccountLocked),
+            false => user
+                .login_retry_after(&self.login_policy, now)
+                .map(|_| LoginEventReason::Throttled),
+        };
+        if let Some(reason) = refused {
+            self.db.transaction(|tables| {
+                tables.record_login_event(Some(&user.id), email, client, reason);
+                Ok(())
+            })?;
+            return Err(LibError::Unauthorized(
+                "Invalid email or password".to_string(),
+            ));
+        }
+        self.db.transaction(|tables| {
+            let row = tables.active_user_mut(&user.id)?;
+            if is_valid {
+                row.failed_login_attempts = 0;
+                row.last_failed_login_at = None;
+                row.locked_until = None;
+            } else {
+                row.failed_login_attempts += 1;
+                row.last_failed_login_at = Some(now);
+                if row.failed_login_attempts >= self.login_policy.max_attempts {
+                    row.locked_until =
+                        Some(now + Duration::seconds(self.login_policy.lockout_seconds));
+                }
+            }
+            let reason = match is_valid {
+                true => LoginEventReason::Success,
+                false => LoginEventReason::InvalidPassword,
+            };
+            tables.record_login_event(Some(&user.id), email, client, reason);
+            Ok(())
+        })?;
+        if !is_valid {
+            return Err(LibError::Unauthorized(
+                "Invalid email or password".to_string(),
+            ));
+        }
+
+        let claims = user.to_claims(Uuid::new_v4(), AuthConfig::DEFAULT_TOKEN_TTL_SECONDS);
+        let token = encode(&self.keys.header(), &claims, &self.keys.encoding)
+            .map_err(LibError::JwtError)?;
+        Ok(AuthBody { token })
+    }
+
+    async fn unlock_user(&self, id: &Uuid) -> Result<bool, LibError> {
+        self.db.transaction(|tables| {
+            let Ok(row) = tables.active_user_mut(id) else {
+                return Ok(false);
+            };
+            row.failed_login_attempts = 0;
+            row.last_failed_login_at = None;
+            row.locked_until = None;
+            Ok(true)
+        })
+    }
+
+    // sessions are not kept, none to revoke
+    async fn reset_password(&self, id: &Uuid, password: &str) -> Result<u64, LibError> {
+        let hashed_password = Self::hash(password)?;
+        let changes = UpdateUser {
+            name: None,
+            email: None,
+            password: Some(password.to_string()),
+            address: None,
+            role: None,
+        };
+        self.db.transaction(|tables| {
+            Self::update_user_record(tables, id, &changes, Some(hashed_password), None)?;
+            let row = tables.active_user_mut(id)?;
+            row.failed_login_attempts = 0;
+            row.last_failed_login_at = None;
+            row.locked_until = None;
+            Ok(0)
+        })
+    }
+
+    async fn get_deleted_users(&self) -> Result<Vec<DeletedRecord<ResponseUser>>, LibError> {
+        let mut deleted: Vec<DeletedRecord<ResponseUser>> = self.db.read(|tables| {
+            tables
+                .users
+                .iter()
+                .filter_map(|user| {
+                    Some(DeletedRecord {
+                        record: tables.user_view(user).to_response_user(),
+                        deleted_at: user.deleted_at?,
+                        deleted_by: user.deleted_by,
+                    })
+                })
+                .collect()
+        });
+        deleted.sort_by_key(|record| std::cmp::Reverse(record.deleted_at));
+        Ok(deleted)
+    }
+
+    async fn restore_user(
+        &self,
+        id: &Uuid,
+        _actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        self.db.transaction(|tables| {
+            let row = tables
+                .users
+                .iter_mut()
+                .find(|user| user.id == *id && user.deleted_at.is_some())
+                .ok_or_else(not_found)?;
+            row.deleted_at = None;
+            row.deleted_by = None;
+            row.updated_at = Utc::now().naive_utc();
+            row.version += 1;
+            let (name, email, role_id) = (row.name.clone(), row.email.clone(), row.role_id);
+            tables.check_user_unique(Some(&name), Some(&email), Some(id))?;
+            if tables.active_role(&role_id).is_none() {
+                return Err(LibError::Conflict(
+                    "Role of the user has been deleted".to_string(),
+                ));
+            }
+            tables.active_user(id).map(|user| user.to_response_user())
+        })
+    }
+
+    async fn get_login_events(
+        &self,
+        filter: &LoginEventFilter,
+    ) -> Result<Vec<ResponseLoginEvent>, LibError> {
+        let mut events: Vec<ResponseLoginEvent> = self.db.read(|tables| {
+            tables
+                .login_events
+                .iter()
+                .filter(|event| filter.user_id.is_none() || event.user_id == filter.user_id)
+                .filter(|event| {
+                    filter
+                        .email
+                        .as_ref()
+                        .is_none_or(|email| event.email == *email)
+                })
+                .filter(|event| {
+                    filter.ip_address.is_none() || event.ip_address == filter.ip_address
+                })
+                .cloned()
+                .collect()
+        });
+        // events recorded in the same instant keep their order, newest first
+        events.reverse();
+        events.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+        events.truncate(filter.limit.max(0) as usize);
+        Ok(events)
+    }
+
+    async fn bulk_users(
+        &self,
+        operations: &[BulkOperation<CreateUser, UpdateUser>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseUser>, LibError> {
+        Ok(self
+            .db
+            .bulk(operations, mode, |tables, operation| match operation {
+                BulkOperation::Create(user) => {
+                    let hashed_password = Self::hash(&user.password)?;
+                    Self::insert_user(tables, user, hashed_password).map(BulkApplied::Created)
+                }
+                BulkOperation::Update {
+                    id,
+                    version,
+                    record,
+                } => {
+                    let hashed_password = record.password.as_deref().map(Self::hash).transpose()?;
+                    Self::update_user_record(tables, id, record, hashed_password, *version)
+                        .map(BulkApplied::Updated)
+                }
+                BulkOperation::Delete { id, version } => {
+                    match Self::soft_delete_user(tables, id, *version, actor)? {
+                        true => Ok(BulkApplied::Deleted(*id)),
+                        false => Err(not_found()),
+                    }
+                }
+            }))
+    }
+
+    async fn export_users(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseUser>,
+    ) -> Result<(), LibError> {
+        let rows = self.db.read(|tables| {
+            tables
+                .users
+                .iter()
+                .filter(|user| user.deleted_at.is_none())
+                .map(|user| tables.user_view(user))
+                .collect()
+        });
+        USER_LIST
+            .export_memory(rows, query, |user| user.to_response_user(), sender)
+            .await
+    }
+}
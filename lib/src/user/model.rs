@@ -1,6 +1,8 @@
 use crate::role::entity::Role;
-use chrono::{Duration, NaiveDateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use config::LoginPolicy;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -14,6 +16,7 @@ pub struct Claims {
     pub name: String,
     pub email: String,
     pub roles: String,
+    pub sid: Uuid,
     pub iat: usize,
     pub exp: usize,
 }
@@ -25,28 +28,98 @@ pub struct CurrentUser {
     pub email: String,
     pub hashed_password: String,
     pub role_name: String,
+    pub failed_login_attempts: i32,
+    pub last_failed_login_at: Option<DateTime<Utc>>,
+    pub locked_until: Option<DateTime<Utc>>,
 }
 
 impl CurrentUser {
-    pub fn to_claims(&self) -> Claims {
-        let exp_duration = Duration::minutes(5);
+    pub fn is_locked(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until
+            .is_some_and(|locked_until| locked_until > now)
+    }
+
+    // progressive delay: base * 2^(failures - 1), never longer than a lockout
+    pub fn login_retry_after(&self, policy: &LoginPolicy, now: DateTime<Utc>) -> Option<Duration> {
+        let last_failed_login_at = self.last_failed_login_at?;
+        if self.failed_login_attempts <= 0 {
+            return None;
+        }
+        let exponent = (self.failed_login_attempts - 1).min(20) as u32;
+        let delay = Duration::milliseconds(policy.delay_base_millis.saturating_mul(1 << exponent))
+            .min(Duration::seconds(policy.lockout_seconds));
+        let retry_after = last_failed_login_at + delay - now;
+        (retry_after > Duration::zero()).then_some(retry_after)
+    }
+
+    pub fn to_claims(&self, session_id: Uuid, ttl_seconds: i64) -> Claims {
+        let exp_duration = Duration::seconds(ttl_seconds);
         Claims {
             sub: self.id,
             name: self.name.clone(),
             email: self.email.clone(),
             roles: self.role_name.clone(),
+            sid: session_id,
             iat: (Utc::now()).timestamp() as usize,
             exp: (Utc::now() + exp_duration).timestamp() as usize,
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthBody {
     pub token: String,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginEventReason {
+    Success,
+    InvalidPassword,
+    UnknownEmail,
+    AccountLocked,
+    Throttled,
+    IpBlocked,
+}
+
+impl LoginEventReason {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            LoginEventReason::Success => "success",
+            LoginEventReason::InvalidPassword => "invalid_password",
+            LoginEventReason::UnknownEmail => "unknown_email",
+            LoginEventReason::AccountLocked => "account_locked",
+            LoginEventReason::Throttled => "throttled",
+            LoginEventReason::IpBlocked => "ip_blocked",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
+pub struct LoginEventFilter {
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub ip_address: Option<String>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResponseLoginEvent {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub email: String,
+    pub ip_address: Option<String>,
+    pub success: bool,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct QueryUser {
     pub id: Uuid,
     pub name: String,
@@ -56,6 +129,7 @@ pub struct QueryUser {
     pub role_name: String,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
+    pub version: i32,
 }
 
 impl QueryUser {
@@ -69,6 +143,7 @@ impl QueryUser {
             role_name: self.role_name.clone(),
             created_at: self.created_at.unwrap().to_string(),
             updated_at: self.updated_at.unwrap().to_string(),
+            version: self.version,
         }
     }
 }
@@ -91,7 +166,7 @@ pub struct UpdateUser {
     pub role: Option<Role>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResponseUser {
     pub id: Uuid,
     pub name: String,
@@ -101,4 +176,6 @@ pub struct ResponseUser {
     pub role_name: String,
     pub created_at: String,
     pub updated_at: String,
+    // bumped on every change, sent back as ETag
+    pub version: i32,
 }
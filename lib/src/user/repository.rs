@@ -1,6 +1,11 @@
-use crate::user::model::{AuthBody, CreateUser, CurrentUser, ResponseUser, UpdateUser};
+use crate::user::model::{
+    AuthBody, ClientInfo, CreateUser, CurrentUser, LoginEventFilter, ResponseLoginEvent,
+    ResponseUser, UpdateUser,
+};
 use crate::util::error::LibError;
+use crate::util::model::{BulkMode, BulkOperation, BulkResult, DeletedRecord, ListQuery, Page};
 use async_trait::async_trait;
+use tokio::sync::mpsc::Sender;
 use uuid::Uuid;
 
 #[async_trait]
@@ -11,11 +16,51 @@ pub trait UserRepository: Send + Sync + std::fmt::Debug {
         password: &str,
         hash: &str,
     ) -> Result<bool, bcrypt::BcryptError>;
-    async fn create_user(&self, user: &CreateUser) -> Result<ResponseUser, LibError>;
-    async fn update_user(&self, id: &Uuid, user: &UpdateUser) -> Result<ResponseUser, LibError>;
-    async fn delete_user(&self, id: &Uuid) -> Result<bool, LibError>;
+    async fn create_user(
+        &self,
+        user: &CreateUser,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError>;
+    async fn update_user(
+        &self,
+        id: &Uuid,
+        user: &UpdateUser,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError>;
+    async fn delete_user(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError>;
     async fn get_user_by_id(&self, id: &Uuid) -> Result<ResponseUser, LibError>;
-    async fn get_users(&self) -> Result<Vec<ResponseUser>, LibError>;
-    async fn get_user_by_email(&self, email: &String) -> Result<CurrentUser, LibError>;
-    async fn login(&self, email: &String, password: &String) -> Result<AuthBody, LibError>;
+    async fn get_users(&self, query: &ListQuery) -> Result<Page<ResponseUser>, LibError>;
+    async fn get_user_by_email(&self, email: &str) -> Result<CurrentUser, LibError>;
+    async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, LibError>;
+    async fn unlock_user(&self, id: &Uuid) -> Result<bool, LibError>;
+    async fn reset_password(&self, id: &Uuid, password: &str) -> Result<u64, LibError>;
+    async fn get_deleted_users(&self) -> Result<Vec<DeletedRecord<ResponseUser>>, LibError>;
+    async fn restore_user(&self, id: &Uuid, actor: Option<&Uuid>)
+        -> Result<ResponseUser, LibError>;
+    async fn get_login_events(
+        &self,
+        filter: &LoginEventFilter,
+    ) -> Result<Vec<ResponseLoginEvent>, LibError>;
+    async fn bulk_users(
+        &self,
+        operations: &[BulkOperation<CreateUser, UpdateUser>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseUser>, LibError>;
+    async fn export_users(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseUser>,
+    ) -> Result<(), LibError>;
 }
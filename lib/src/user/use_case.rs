@@ -1,6 +1,12 @@
-use crate::user::model::{AuthBody, CreateUser, ResponseUser, UpdateUser};
+use crate::user::model::{
+    AuthBody, ClientInfo, CreateUser, LoginEventFilter, ResponseLoginEvent, ResponseUser,
+    UpdateUser,
+};
 use crate::user::repository::UserRepository;
 use crate::util::error::LibError;
+use crate::util::model::{BulkMode, BulkOperation, BulkResult, DeletedRecord, ListQuery, Page};
+use tokio::sync::mpsc::Sender;
+use tracing::instrument;
 use uuid::Uuid;
 
 #[derive(Debug)]
@@ -10,18 +16,32 @@ impl UserUseCase {
     pub fn new(user_repository: Box<dyn UserRepository>) -> Self {
         Self(user_repository)
     }
-    pub async fn create_user(&self, user: &CreateUser) -> Result<ResponseUser, LibError> {
-        self.0.create_user(&user).await
+    #[instrument(skip_all)]
+    pub async fn create_user(
+        &self,
+        user: &CreateUser,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        self.0.create_user(user, actor).await
     }
+    #[instrument(skip_all, fields(%id))]
     pub async fn update_user(
         &self,
         id: &Uuid,
         user: &UpdateUser,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
     ) -> Result<ResponseUser, LibError> {
-        self.0.update_user(&id, &user).await
+        self.0.update_user(id, user, version, actor).await
     }
-    pub async fn delete_user(&self, id: &Uuid) -> Result<bool, LibError> {
-        match self.0.delete_user(&id).await {
+    #[instrument(skip_all, fields(%id))]
+    pub async fn delete_user(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        match self.0.delete_user(id, version, actor).await {
             Ok(r) => match r {
                 true => Ok(true),
                 false => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
@@ -29,13 +49,429 @@ impl UserUseCase {
             Err(e) => Err(e),
         }
     }
+    #[instrument(skip_all, fields(%id))]
     pub async fn get_user_by_id(&self, id: &Uuid) -> Result<ResponseUser, LibError> {
-        self.0.get_user_by_id(&id).await
+        self.0.get_user_by_id(id).await
+    }
+    #[instrument(skip_all)]
+    pub async fn get_users(&self, query: &ListQuery) -> Result<Page<ResponseUser>, LibError> {
+        self.0.get_users(query).await
+    }
+    #[instrument(skip_all)]
+    pub async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, LibError> {
+        self.0.login(email, password, client).await
+    }
+    #[instrument(skip_all, fields(%id))]
+    pub async fn unlock_user(&self, id: &Uuid) -> Result<bool, LibError> {
+        match self.0.unlock_user(id).await {
+            Ok(r) => match r {
+                true => Ok(true),
+                false => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+    // sets a new password, signs the user out everywhere and lifts a lockout, all or nothing.
+    // Returns how many sessions were revoked.
+    #[instrument(skip_all, fields(%id))]
+    pub async fn reset_password(&self, id: &Uuid, password: &str) -> Result<u64, LibError> {
+        self.0.reset_password(id, password).await
+    }
+    #[instrument(skip_all)]
+    pub async fn get_login_events(
+        &self,
+        filter: &LoginEventFilter,
+    ) -> Result<Vec<ResponseLoginEvent>, LibError> {
+        self.0.get_login_events(filter).await
+    }
+    #[instrument(skip_all)]
+    pub async fn get_deleted_users(&self) -> Result<Vec<DeletedRecord<ResponseUser>>, LibError> {
+        self.0.get_deleted_users().await
+    }
+    #[instrument(skip_all, fields(%id))]
+    pub async fn restore_user(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        self.0.restore_user(id, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn bulk_users(
+        &self,
+        operations: &[BulkOperation<CreateUser, UpdateUser>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseUser>, LibError> {
+        self.0.bulk_users(operations, mode, actor).await
+    }
+
+    #[instrument(skip_all)]
+    pub async fn export_users(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseUser>,
+    ) -> Result<(), LibError> {
+        self.0.export_users(query, sender).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::role::entity::Role;
+    use crate::user::memory::MemoryUserRepository;
+    use crate::user::model::Claims;
+    use crate::util::memory::MemoryDb;
+    use config::{Keys, LoginPolicy};
+    use jsonwebtoken::{decode, Validation};
+    use std::sync::Arc;
+
+    const SECRET: [u8; 32] = [7; 32];
+
+    fn use_case() -> UserUseCase {
+        let policy = LoginPolicy {
+            max_attempts: 3,
+            lockout_seconds: 900,
+            ip_max_attempts: 5,
+            attempt_window_seconds: 900,
+            delay_base_millis: 0,
+        };
+        let repository =
+            MemoryUserRepository::new(Arc::new(MemoryDb::new()), policy, Keys::new(&SECRET));
+        UserUseCase::new(Box::new(repository))
+    }
+
+    fn new_user(name: &str, role: Role) -> CreateUser {
+        CreateUser {
+            name: name.to_string(),
+            email: format!("{name}@example.com"),
+            password: "Passw0rd".to_string(),
+            address: "Main street 1".to_string(),
+            role,
+        }
     }
-    pub async fn get_users(&self) -> Result<Vec<ResponseUser>, LibError> {
-        self.0.get_users().await
+
+    fn no_changes() -> UpdateUser {
+        UpdateUser {
+            name: None,
+            email: None,
+            password: None,
+            address: None,
+            role: None,
+        }
     }
-    pub async fn login(&self, email: &String, password: &String) -> Result<AuthBody, LibError> {
-        self.0.login(&email, &password).await
+
+    fn client(ip_address: &str) -> ClientInfo {
+        ClientInfo {
+            ip_address: Some(ip_address.to_string()),
+            user_agent: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn created_users_carry_their_role() {
+        let use_case = use_case();
+        let created = use_case
+            .create_user(&new_user("alice", Role::Admin), None)
+            .await
+            .unwrap();
+        assert_eq!(created.role_name, "Admin");
+        assert_eq!(created.version, 1);
+        let found = use_case.get_user_by_id(&created.id).await.unwrap();
+        assert_eq!(found.email, "alice@example.com");
+    }
+
+    #[tokio::test]
+    async fn emails_are_unique() {
+        let use_case = use_case();
+        use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+        let mut duplicate = new_user("alice2", Role::User);
+        duplicate.email = "alice@example.com".to_string();
+        let err = use_case.create_user(&duplicate, None).await.unwrap_err();
+        assert!(matches!(err, LibError::Constraint(e) if e.field() == Some("email")));
+    }
+
+    #[tokio::test]
+    async fn login_issues_a_token_for_the_user() {
+        let use_case = use_case();
+        let created = use_case
+            .create_user(&new_user("alice", Role::Admin), None)
+            .await
+            .unwrap();
+        let body = use_case
+            .login("alice@example.com", "Passw0rd", &client("10.0.0.1"))
+            .await
+            .unwrap();
+        let claims = decode::<Claims>(
+            &body.token,
+            &Keys::new(&SECRET).decoding,
+            &Validation::default(),
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(claims.sub, created.id);
+        assert_eq!(claims.roles, "Admin");
+
+        let err = use_case
+            .login("alice@example.com", "wrong", &client("10.0.0.1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::Unauthorized(_)));
+        let err = use_case
+            .login("nobody@example.com", "Passw0rd", &client("10.0.0.1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::Unauthorized(_)));
+
+        let filter = LoginEventFilter {
+            user_id: None,
+            email: None,
+            ip_address: Some("10.0.0.1".to_string()),
+            limit: 10,
+        };
+        let reasons: Vec<_> = use_case
+            .get_login_events(&filter)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.reason)
+            .collect();
+        assert_eq!(reasons, ["unknown_email", "invalid_password", "success"]);
+    }
+
+    #[tokio::test]
+    async fn accounts_lock_after_repeated_failures() {
+        let use_case = use_case();
+        let created = use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            let err = use_case
+                .login("alice@example.com", "wrong", &ClientInfo::default())
+                .await
+                .unwrap_err();
+            assert!(matches!(err, LibError::Unauthorized(_)));
+        }
+        // the right password is refused like a wrong one, nothing tells the account exists
+        let err = use_case
+            .login("alice@example.com", "Passw0rd", &ClientInfo::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::Unauthorized(_)));
+
+        use_case.unlock_user(&created.id).await.unwrap();
+        assert!(use_case
+            .login("alice@example.com", "Passw0rd", &ClientInfo::default())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn clients_are_blocked_after_repeated_failures() {
+        let use_case = use_case();
+        use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+        for i in 0..5 {
+            let email = format!("nobody{i}@example.com");
+            let _ = use_case.login(&email, "wrong", &client("10.0.0.2")).await;
+        }
+        let err = use_case
+            .login("alice@example.com", "Passw0rd", &client("10.0.0.2"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::TooManyRequests(_)));
+        assert!(use_case
+            .login("alice@example.com", "Passw0rd", &client("10.0.0.3"))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn updates_change_role_and_password() {
+        let use_case = use_case();
+        let created = use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+        let changes = UpdateUser {
+            password: Some("N3wPassword".to_string()),
+            role: Some(Role::Admin),
+            ..no_changes()
+        };
+        let updated = use_case
+            .update_user(&created.id, &changes, Some(1), None)
+            .await
+            .unwrap();
+        assert_eq!((updated.role_name.as_str(), updated.version), ("Admin", 2));
+        assert!(use_case
+            .login("alice@example.com", "N3wPassword", &ClientInfo::default())
+            .await
+            .is_ok());
+
+        let err = use_case
+            .update_user(&created.id, &no_changes(), Some(1), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::PreconditionFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn password_resets_lift_the_lockout() {
+        let use_case = use_case();
+        let created = use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            let _ = use_case
+                .login("alice@example.com", "wrong", &ClientInfo::default())
+                .await;
+        }
+        use_case
+            .reset_password(&created.id, "N3wPassword")
+            .await
+            .unwrap();
+        assert!(use_case
+            .login("alice@example.com", "N3wPassword", &ClientInfo::default())
+            .await
+            .is_ok());
+        let reset = use_case.get_user_by_id(&created.id).await.unwrap();
+        assert_eq!(reset.version, 2);
+    }
+
+    #[tokio::test]
+    async fn deleted_users_can_be_restored() {
+        let use_case = use_case();
+        let created = use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+        use_case
+            .delete_user(&created.id, Some(1), None)
+            .await
+            .unwrap();
+        assert!(use_case.get_user_by_id(&created.id).await.is_err());
+        assert!(use_case
+            .login("alice@example.com", "Passw0rd", &ClientInfo::default())
+            .await
+            .is_err());
+        assert_eq!(use_case.get_deleted_users().await.unwrap().len(), 1);
+
+        let restored = use_case.restore_user(&created.id, None).await.unwrap();
+        assert_eq!(restored.version, 2);
+
+        let err = use_case
+            .delete_user(&Uuid::new_v4(), None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, LibError::SqlxError(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn deleted_users_release_their_email() {
+        let use_case = use_case();
+        let deleted = use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+        use_case.delete_user(&deleted.id, None, None).await.unwrap();
+        use_case
+            .create_user(&new_user("alice", Role::User), None)
+            .await
+            .unwrap();
+
+        let err = use_case.restore_user(&deleted.id, None).await.unwrap_err();
+        assert!(matches!(err, LibError::Constraint(e) if e.field() == Some("name")));
+        assert_eq!(use_case.get_deleted_users().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn users_are_listed_by_role() {
+        let use_case = use_case();
+        for (name, role) in [
+            ("alice", Role::Admin),
+            ("bob", Role::User),
+            ("carol", Role::User),
+        ] {
+            use_case
+                .create_user(&new_user(name, role), None)
+                .await
+                .unwrap();
+        }
+        let query = ListQuery {
+            sort: Some("name".to_string()),
+            filters: vec![("role".to_string(), "user".to_string())],
+            ..ListQuery::default()
+        };
+        let page = use_case.get_users(&query).await.unwrap();
+        let names: Vec<_> = page.items.iter().map(|user| user.name.as_str()).collect();
+        assert_eq!(names, ["bob", "carol"]);
+        assert_eq!(page.total, 2);
+    }
+
+    #[tokio::test]
+    async fn all_or_nothing_bulk_keeps_nothing_when_an_item_fails() {
+        let use_case = use_case();
+        let operations = [
+            BulkOperation::Create(new_user("alice", Role::User)),
+            BulkOperation::Delete {
+                id: Uuid::new_v4(),
+                version: None,
+            },
+        ];
+        let result = use_case
+            .bulk_users(&operations, BulkMode::AllOrNothing, None)
+            .await
+            .unwrap();
+        assert!(!result.committed);
+        assert!(result.outcomes[0].is_ok());
+        assert!(result.outcomes[1].is_err());
+        let page = use_case.get_users(&ListQuery::default()).await.unwrap();
+        assert_eq!(page.total, 0);
+
+        let result = use_case
+            .bulk_users(&operations, BulkMode::BestEffort, None)
+            .await
+            .unwrap();
+        assert!(result.committed);
+        let page = use_case.get_users(&ListQuery::default()).await.unwrap();
+        assert_eq!(page.total, 1);
+    }
+
+    #[tokio::test]
+    async fn export_sends_every_user() {
+        let use_case = use_case();
+        for name in ["alice", "bob"] {
+            use_case
+                .create_user(&new_user(name, Role::User), None)
+                .await
+                .unwrap();
+        }
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        let query = ListQuery {
+            sort: Some("email".to_string()),
+            limit: 1,
+            ..ListQuery::default()
+        };
+        use_case.export_users(&query, &sender).await.unwrap();
+        drop(sender);
+        let mut emails = Vec::new();
+        while let Some(user) = receiver.recv().await {
+            emails.push(user.email);
+        }
+        assert_eq!(emails, ["alice@example.com", "bob@example.com"]);
     }
 }
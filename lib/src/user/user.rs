@@ -1,52 +1,523 @@
+use crate::audit::audit::PgAuditLogRepository;
+use crate::audit::model::{AuditEntityType, AuditEntry};
 use crate::role::role::PgRoleRepository;
+use crate::session::session::PgSessionRepository;
 use crate::user::model::{
-    AuthBody, CreateUser, CurrentUser, Id, QueryUser, ResponseUser, UpdateUser,
+    AuthBody, ClientInfo, CreateUser, CurrentUser, Id, LoginEventFilter, LoginEventReason,
+    QueryUser, ResponseLoginEvent, ResponseUser, UpdateUser,
 };
 use crate::user::repository::UserRepository;
 use crate::util::error::LibError;
+use crate::util::list::{ColumnType, ListColumn, ListSpec};
+use crate::util::model::{
+    check_version, BulkApplied, BulkMode, BulkOperation, BulkResult, DeletedRecord, ListQuery, Page,
+};
+use crate::util::postgres::{end_bulk, end_savepoint};
 use async_trait::async_trait;
-use config::Keys;
-use jsonwebtoken::{encode, Algorithm, Header};
-use sqlx::postgres::Postgres;
-use sqlx::Pool;
+use chrono::{DateTime, Duration, Utc};
+use config::{AuthConfig, LoginPolicy};
+use sqlx::postgres::{PgConnection, Postgres};
+use sqlx::{Connection, Pool};
 use std::sync::Arc;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::OnceCell;
+use tracing::instrument;
 use uuid::Uuid;
 
+pub(crate) static USER_LIST: ListSpec = ListSpec {
+    select: "SELECT u.id, u.name, u.email, u.address, u.role_id, r.name as role_name, u.created_at, u.updated_at, u.version",
+    from: " FROM users as u INNER JOIN roles as r ON u.role_id = r.id",
+    id_column: "u.id",
+    condition: "u.deleted_at IS NULL",
+    sort_columns: &[
+        ListColumn {
+            name: "created_at",
+            column: "u.created_at",
+            column_type: ColumnType::Timestamp,
+        },
+        ListColumn {
+            name: "name",
+            column: "u.name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "email",
+            column: "u.email",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "role",
+            column: "r.name",
+            column_type: ColumnType::Text,
+        },
+    ],
+    filter_columns: &[
+        ListColumn {
+            name: "name",
+            column: "u.name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "email",
+            column: "u.email",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "role",
+            column: "r.name",
+            column_type: ColumnType::Text,
+        },
+        ListColumn {
+            name: "role_id",
+            column: "u.role_id",
+            column_type: ColumnType::Uuid,
+        },
+    ],
+    search_columns: &["u.name", "u.email", "u.address"],
+};
+
 #[derive(Debug)]
 pub struct PgUserRepository {
     db_connect: Arc<Pool<Postgres>>,
+    login_policy: LoginPolicy,
+    auth: AuthConfig,
+    // hashed on the first login of an unknown email, at the configured cost
+    dummy_hash: OnceCell<String>,
 }
 
 impl PgUserRepository {
-    pub async fn new(db_connect: Arc<Pool<Postgres>>) -> Self {
-        Self { db_connect }
+    pub async fn new(
+        db_connect: Arc<Pool<Postgres>>,
+        login_policy: LoginPolicy,
+        auth: AuthConfig,
+    ) -> Self {
+        Self {
+            db_connect,
+            login_policy,
+            auth,
+            dummy_hash: OnceCell::new(),
+        }
+    }
+
+    async fn dummy_hash(&self) -> Result<&str, LibError> {
+        let hash = self
+            .dummy_hash
+            .get_or_try_init(|| async { self.hash_password("dummy password").await })
+            .await?;
+        Ok(hash)
     }
 }
 
+// bcrypt would stall the other requests of its runtime thread
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, bcrypt::BcryptError> + Send + 'static,
+) -> Result<T, bcrypt::BcryptError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+fn invalid_credentials() -> LibError {
+    LibError::Unauthorized("Invalid email or password".to_string())
+}
+
 #[async_trait]
 impl UserRepository for PgUserRepository {
+    #[instrument(skip_all)]
     async fn hash_password(&self, password: &str) -> Result<String, bcrypt::BcryptError> {
-        let hashed_password = bcrypt::hash(password, bcrypt::DEFAULT_COST)?;
-        Ok(hashed_password)
+        let password = password.to_string();
+        let cost = self.auth.bcrypt_cost;
+        blocking(move || bcrypt::hash(password, cost)).await
     }
 
+    #[instrument(skip_all)]
     async fn verify_password(
         &self,
         password: &str,
         hash: &str,
     ) -> Result<bool, bcrypt::BcryptError> {
-        let result = bcrypt::verify(password, hash)?;
+        let (password, hash) = (password.to_string(), hash.to_string());
+        blocking(move || bcrypt::verify(password, &hash)).await
+    }
+
+    #[instrument(skip_all)]
+    async fn create_user(
+        &self,
+        user: &CreateUser,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        let hashed_password = self.hash_password(&user.password).await?;
+        let mut tx = self.db_connect.begin().await?;
+        let result = self
+            .insert_user(&mut tx, user, &hashed_password, actor)
+            .await?;
+        tx.commit().await?;
         Ok(result)
     }
 
-    async fn create_user(&self, user: &CreateUser) -> Result<ResponseUser, LibError> {
-        let hashed_password = self
-            .hash_password(&user.password)
+    #[instrument(skip_all, fields(%id))]
+    async fn update_user(
+        &self,
+        id: &Uuid,
+        user: &UpdateUser,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        let hashed_password = self.hash_new_password(user).await?;
+        let mut tx = self.db_connect.begin().await?;
+        let result = self
+            .update_user_record(
+                &mut tx,
+                id,
+                user,
+                hashed_password.as_deref(),
+                version,
+                actor,
+            )
+            .await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn delete_user(
+        &self,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let deleted = Self::soft_delete_user(&mut tx, id, version, actor).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn get_user_by_id(&self, id: &Uuid) -> Result<ResponseUser, LibError> {
+        let query = sqlx::query_as!(QueryUser,
+            r#"
+            SELECT u.id, u.name, u.email, u.address, u.role_id, r.name as role_name, u.created_at, u.updated_at, u.version FROM users as u
+            INNER JOIN roles as r
+            ON u.role_id = r.id
+            WHERE u.id = $1 AND u.deleted_at IS NULL;
+            "#,
+            &id
+        )
+        .fetch_one(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(query.to_response_user())
+    }
+
+    #[instrument(skip_all)]
+    async fn get_users(&self, query: &ListQuery) -> Result<Page<ResponseUser>, LibError> {
+        let page = USER_LIST
+            .fetch::<QueryUser>(self.db_connect.as_ref(), query)
+            .await?;
+        Ok(page.map(|query| query.to_response_user()))
+    }
+
+    #[instrument(skip_all)]
+    async fn get_user_by_email(&self, email: &str) -> Result<CurrentUser, LibError> {
+        let query = sqlx::query_as!(
+            CurrentUser,
+            r#"
+            SELECT u.id, u.name, u.email, u.hash as hashed_password, r.name as role_name,
+                u.failed_login_attempts, u.last_failed_login_at, u.locked_until
+            FROM users as u
+            INNER JOIN roles as r
+            ON u.role_id = r.id
+            WHERE u.email = $1 AND u.deleted_at IS NULL;
+            "#,
+            &email
+        )
+        .fetch_one(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(query)
+    }
+
+    #[instrument(skip_all)]
+    async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        client: &ClientInfo,
+    ) -> Result<AuthBody, LibError> {
+        if let Some(ip_address) = &client.ip_address {
+            let failures = self.count_recent_ip_failures(ip_address).await?;
+            if failures >= self.login_policy.ip_max_attempts {
+                self.record_login_event(None, email, client, LoginEventReason::IpBlocked)
+                    .await?;
+                return Err(LibError::TooManyRequests(
+                    "Too many failed login attempts, try again later".to_string(),
+                ));
+            }
+        }
+
+        let user = match self.get_user_by_email(email).await {
+            Ok(user) => user,
+            Err(LibError::SqlxError(sqlx::Error::RowNotFound)) => {
+                // as slow as a wrong password, the timing must not tell which emails exist
+                let dummy_hash = self.dummy_hash().await?;
+                let _ = self.verify_password(password, dummy_hash).await;
+                self.record_login_event(None, email, client, LoginEventReason::UnknownEmail)
+                    .await?;
+                return Err(invalid_credentials());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let now = Utc::now();
+        let is_valid = self
+            .verify_password(password, &user.hashed_password)
             .await
-            .map_err(|e| LibError::BcryptError(e))?;
+            .map_err(LibError::BcryptError)?;
+        // locked and throttled accounts are refused like a wrong password, a different answer
+        // would confirm the account exists. The password is checked anyway to take as long.
+        let refused = match user.is_locked(now) {
+            true => Some(LoginEventReason::AccountLocked),
+            false => user
+                .login_retry_after(&self.login_policy, now)
+                .map(|_| LoginEventReason::Throttled),
+        };
+        if let Some(reason) = refused {
+            self.record_login_event(Some(&user.id), email, client, reason)
+                .await?;
+            return Err(invalid_credentials());
+        }
+
+        if !is_valid {
+            self.register_login_failure(&user.id, now).await?;
+            self.record_login_event(
+                Some(&user.id),
+                email,
+                client,
+                LoginEventReason::InvalidPassword,
+            )
+            .await?;
+            return Err(invalid_credentials());
+        }
+
+        self.unlock_user(&user.id).await?;
+        self.record_login_event(Some(&user.id), email, client, LoginEventReason::Success)
+            .await?;
+
+        PgSessionRepository::start_session(&self.db_connect, &user, client, &self.auth).await
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn unlock_user(&self, id: &Uuid) -> Result<bool, LibError> {
+        let mut db_connect = self.db_connect.acquire().await?;
+        let unlocked = Self::clear_lockout(&mut db_connect, id).await?;
+        Ok(unlocked)
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn reset_password(&self, id: &Uuid, password: &str) -> Result<u64, LibError> {
+        let hashed_password = self.hash_password(password).await?;
+        let changes = UpdateUser {
+            name: None,
+            email: None,
+            password: Some(password.to_string()),
+            address: None,
+            role: None,
+        };
+        let mut tx = self.db_connect.begin().await?;
+        self.update_user_record(&mut tx, id, &changes, Some(&hashed_password), None, None)
+            .await?;
+        let revoked = PgSessionRepository::revoke_user_sessions(&mut tx, id).await?;
+        Self::clear_lockout(&mut tx, id).await?;
+        tx.commit().await?;
+        Ok(revoked)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_deleted_users(&self) -> Result<Vec<DeletedRecord<ResponseUser>>, LibError> {
+        let query = sqlx::query!(
+            r#"
+            SELECT u.id, u.name, u.email, u.address, u.role_id, r.name as role_name, u.created_at, u.updated_at, u.version,
+                u.deleted_at as "deleted_at!", u.deleted_by
+            FROM users as u
+            INNER JOIN roles as r
+            ON u.role_id = r.id
+            WHERE u.deleted_at IS NOT NULL
+            ORDER BY u.deleted_at DESC;
+            "#
+        )
+        .fetch_all(self.db_connect.clone().as_ref())
+        .await?;
+        let result = query
+            .into_iter()
+            .map(|row| DeletedRecord {
+                record: QueryUser {
+                    id: row.id,
+                    name: row.name,
+                    email: row.email,
+                    address: row.address,
+                    role_id: row.role_id,
+                    role_name: row.role_name,
+                    created_at: Some(row.created_at),
+                    updated_at: Some(row.updated_at),
+                    version: row.version,
+                }
+                .to_response_user(),
+                deleted_at: row.deleted_at,
+                deleted_by: row.deleted_by,
+            })
+            .collect();
+        Ok(result)
+    }
+
+    #[instrument(skip_all, fields(%id))]
+    async fn restore_user(
+        &self,
+        id: &Uuid,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
+        let mut tx = self.db_connect.begin().await?;
+        let role_deleted = sqlx::query_scalar!(
+            r#"
+            UPDATE users as u
+            SET deleted_at = NULL,
+                deleted_by = NULL,
+                updated_at = now(),
+                version = u.version + 1
+            FROM roles as r
+            WHERE u.id = $1 AND u.deleted_at IS NOT NULL AND r.id = u.role_id
+            RETURNING r.deleted_at IS NOT NULL as "role_deleted!";
+            "#,
+            &id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if role_deleted {
+            return Err(LibError::Conflict(
+                "Role of the user has been deleted".to_string(),
+            ));
+        }
+        let result = Self::query_user(&mut tx, id).await?.to_response_user();
+        PgAuditLogRepository::record(
+            &mut tx,
+            &AuditEntry::restored(actor, AuditEntityType::User, result.id, &result),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    #[instrument(skip_all)]
+    async fn get_login_events(
+        &self,
+        filter: &LoginEventFilter,
+    ) -> Result<Vec<ResponseLoginEvent>, LibError> {
+        let query = sqlx::query_as!(
+            ResponseLoginEvent,
+            r#"
+            SELECT id, user_id, email, ip_address, success, reason, created_at
+            FROM login_events
+            WHERE ($1::uuid IS NULL OR user_id = $1)
+              AND ($2::text IS NULL OR email = $2)
+              AND ($3::text IS NULL OR ip_address = $3)
+            ORDER BY created_at DESC
+            LIMIT $4;
+            "#,
+            filter.user_id,
+            filter.email,
+            filter.ip_address,
+            filter.limit
+        )
+        .fetch_all(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(query)
+    }
+
+    #[instrument(skip_all)]
+    async fn bulk_users(
+        &self,
+        operations: &[BulkOperation<CreateUser, UpdateUser>],
+        mode: BulkMode,
+        actor: Option<&Uuid>,
+    ) -> Result<BulkResult<ResponseUser>, LibError> {
+        // bcrypt takes its time on purpose, no connection is held while it runs
+        let mut hashed_passwords = Vec::with_capacity(operations.len());
+        for operation in operations {
+            hashed_passwords.push(match operation {
+                BulkOperation::Create(user) => Some(self.hash_password(&user.password).await?),
+                BulkOperation::Update { record, .. } => self.hash_new_password(record).await?,
+                BulkOperation::Delete { .. } => None,
+            });
+        }
+        let mut tx = self.db_connect.begin().await?;
+        let mut outcomes = Vec::with_capacity(operations.len());
+        for (operation, hashed_password) in operations.iter().zip(hashed_passwords) {
+            let hashed_password = hashed_password.as_deref();
+            let mut savepoint = Connection::begin(&mut *tx).await?;
+            let outcome = match operation {
+                BulkOperation::Create(user) => self
+                    .insert_user(
+                        &mut savepoint,
+                        user,
+                        hashed_password.unwrap_or_default(),
+                        actor,
+                    )
+                    .await
+                    .map(BulkApplied::Created),
+                BulkOperation::Update {
+                    id,
+                    version,
+                    record,
+                } => self
+                    .update_user_record(
+                        &mut savepoint,
+                        id,
+                        record,
+                        hashed_password,
+                        *version,
+                        actor,
+                    )
+                    .await
+                    .map(BulkApplied::Updated),
+                BulkOperation::Delete { id, version } => {
+                    match Self::soft_delete_user(&mut savepoint, id, *version, actor).await {
+                        Ok(true) => Ok(BulkApplied::Deleted(*id)),
+                        Ok(false) => Err(LibError::SqlxError(sqlx::Error::RowNotFound)),
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+            end_savepoint(savepoint, &outcome).await?;
+            outcomes.push(outcome);
+        }
+        end_bulk(tx, mode, outcomes).await
+    }
+
+    #[instrument(skip_all)]
+    async fn export_users(
+        &self,
+        query: &ListQuery,
+        sender: &Sender<ResponseUser>,
+    ) -> Result<(), LibError> {
+        USER_LIST
+            .export(
+                self.db_connect.as_ref(),
+                query,
+                |query: QueryUser| query.to_response_user(),
+                sender,
+            )
+            .await
+    }
+}
+
+impl PgUserRepository {
+    pub async fn insert_user(
+        &self,
+        db_connect: &mut PgConnection,
+        user: &CreateUser,
+        hashed_password: &str,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
         let role_id = PgRoleRepository::find_role(&self.db_connect, &user.role.to_str())
             .await
-            .map_err(|e| LibError::SqlxError(e))?;
+            .map_err(LibError::from)?;
         let id = sqlx::query_as!(
             Id,
             r#"
@@ -56,33 +527,65 @@ impl UserRepository for PgUserRepository {
             "#,
             &user.name,
             &user.email,
-            &hashed_password,
+            hashed_password,
             &user.address,
             &role_id,
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .fetch_one(&mut *db_connect)
         .await?;
-        let query = self.get_user_by_id(&id.id).await?;
-        Ok(query)
+        let result = Self::query_user(db_connect, &id.id)
+            .await?
+            .to_response_user();
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::created(actor, AuditEntityType::User, result.id, &result),
+        )
+        .await?;
+        Ok(result)
     }
 
-    async fn update_user(&self, id: &Uuid, user: &UpdateUser) -> Result<ResponseUser, LibError> {
-        let hashed_password = match &user.password {
-            Some(password) => Some(
-                self.hash_password(password)
-                    .await
-                    .map_err(|e| LibError::BcryptError(e))?,
-            ),
-            None => None,
-        };
+    async fn clear_lockout(db_connect: &mut PgConnection, id: &Uuid) -> Result<bool, sqlx::Error> {
+        let query = sqlx::query!(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = 0,
+                last_failed_login_at = NULL,
+                locked_until = NULL
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            &id
+        )
+        .execute(db_connect)
+        .await?;
+        Ok(query.rows_affected() > 0)
+    }
+
+    async fn hash_new_password(&self, user: &UpdateUser) -> Result<Option<String>, LibError> {
+        match &user.password {
+            Some(password) => Ok(Some(self.hash_password(password).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn update_user_record(
+        &self,
+        db_connect: &mut PgConnection,
+        id: &Uuid,
+        user: &UpdateUser,
+        hashed_password: Option<&str>,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<ResponseUser, LibError> {
         let role_id = match &user.role {
             Some(role) => Some(
                 PgRoleRepository::find_role(&self.db_connect, &role.to_str())
                     .await
-                    .map_err(|e| LibError::SqlxError(e))?,
+                    .map_err(LibError::from)?,
             ),
             None => None,
         };
+        let before = Self::query_user(db_connect, id).await?.to_response_user();
+        check_version(version, before.version)?;
         sqlx::query!(
             r#"
             UPDATE users
@@ -91,109 +594,146 @@ impl UserRepository for PgUserRepository {
                 hash = COALESCE($3, hash), 
                 address = COALESCE($4, address),
                 role_id = COALESCE($5, role_id),
-                updated_at = now()
-            WHERE id = $6;
+                updated_at = now(),
+                version = version + 1
+            WHERE id = $6 AND deleted_at IS NULL;
             "#,
             user.name.as_ref(),
             user.email.as_ref(),
-            hashed_password.as_ref(),
+            hashed_password,
             user.address.as_ref(),
             role_id.as_ref(),
             &id
         )
-        .execute(self.db_connect.clone().as_ref())
+        .execute(&mut *db_connect)
         .await?;
-        let query = self.get_user_by_id(id).await?;
-        Ok(query)
+        let result = Self::query_user(db_connect, id).await?.to_response_user();
+        let mut entry =
+            AuditEntry::updated(actor, AuditEntityType::User, result.id, &before, &result);
+        if user.password.is_some() {
+            entry = entry.with_redacted_change("password");
+        }
+        PgAuditLogRepository::record(db_connect, &entry).await?;
+        Ok(result)
     }
 
-    async fn delete_user(&self, id: &Uuid) -> Result<bool, LibError> {
-        let query = sqlx::query!(
+    pub async fn soft_delete_user(
+        db_connect: &mut PgConnection,
+        id: &Uuid,
+        version: Option<i32>,
+        actor: Option<&Uuid>,
+    ) -> Result<bool, LibError> {
+        let before = match Self::query_user(db_connect, id).await {
+            Ok(before) => before.to_response_user(),
+            Err(sqlx::Error::RowNotFound) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        check_version(version, before.version)?;
+        sqlx::query!(
             r#"
-            DELETE FROM users WHERE id = $1
+            UPDATE users
+            SET deleted_at = now(),
+                deleted_by = $2
+            WHERE id = $1;
             "#,
-            &id
+            &id,
+            actor
         )
-        .execute(self.db_connect.clone().as_ref())
+        .execute(&mut *db_connect)
         .await?;
-        Ok(query.rows_affected() > 0)
+        PgSessionRepository::revoke_user_sessions(db_connect, id).await?;
+        PgAuditLogRepository::record(
+            db_connect,
+            &AuditEntry::deleted(actor, AuditEntityType::User, before.id, &before),
+        )
+        .await?;
+        Ok(true)
     }
 
-    async fn get_user_by_id(&self, id: &Uuid) -> Result<ResponseUser, LibError> {
-        let query = sqlx::query_as!(QueryUser,
+    // locks the user row for the rest of the transaction
+    pub async fn query_user(
+        db_connect: &mut PgConnection,
+        id: &Uuid,
+    ) -> Result<QueryUser, sqlx::Error> {
+        sqlx::query_as!(
+            QueryUser,
             r#"
-            SELECT u.id, u.name, u.email, u.address, u.role_id, r.name as role_name, u.created_at, u.updated_at FROM users as u
+            SELECT u.id, u.name, u.email, u.address, u.role_id, r.name as role_name, u.created_at, u.updated_at, u.version
+            FROM users as u
             INNER JOIN roles as r
             ON u.role_id = r.id
-            WHERE u.id = $1;
+            WHERE u.id = $1 AND u.deleted_at IS NULL
+            FOR UPDATE OF u;
             "#,
             &id
         )
-        .fetch_one(self.db_connect.clone().as_ref())
-        .await?;
-        Ok(query.to_response_user())
+        .fetch_one(db_connect)
+        .await
     }
 
-    async fn get_users(&self) -> Result<Vec<ResponseUser>, LibError> {
-        let query = sqlx::query_as!(
-            QueryUser,
+    async fn count_recent_ip_failures(&self, ip_address: &str) -> Result<i64, LibError> {
+        let since = Utc::now() - Duration::seconds(self.login_policy.attempt_window_seconds);
+        let query = sqlx::query_scalar!(
             r#"
-            SELECT u.id, u.name, u.email, u.address, u.role_id, r.name as role_name, u.created_at, u.updated_at
-            FROM users as u
-            INNER JOIN roles as r
-            ON u.role_id = r.id;
-            "#
+            SELECT COUNT(*) as "count!"
+            FROM login_events
+            WHERE ip_address = $1
+              AND reason IN ($2, $3)
+              AND created_at > $4;
+            "#,
+            ip_address,
+            LoginEventReason::InvalidPassword.to_str(),
+            LoginEventReason::UnknownEmail.to_str(),
+            since
         )
-        .fetch_all(self.db_connect.clone().as_ref())
+        .fetch_one(self.db_connect.clone().as_ref())
         .await?;
-        let result: Vec<ResponseUser> = query
-            .into_iter()
-            .map(|query: QueryUser| query.to_response_user())
-            .collect();
-        Ok(result)
+        Ok(query)
     }
 
-    async fn get_user_by_email(&self, email: &String) -> Result<CurrentUser, LibError> {
-        let query = sqlx::query_as!(
-            CurrentUser,
+    async fn register_login_failure(&self, id: &Uuid, now: DateTime<Utc>) -> Result<(), LibError> {
+        let locked_until = now + Duration::seconds(self.login_policy.lockout_seconds);
+        sqlx::query!(
             r#"
-            SELECT u.id, u.name, u.email, u.hash as hashed_password, r.name as role_name
-            FROM users as u
-            INNER JOIN roles as r
-            ON u.role_id = r.id
-            WHERE u.email = $1;
+            UPDATE users
+            SET failed_login_attempts = failed_login_attempts + 1,
+                last_failed_login_at = $2,
+                locked_until = CASE
+                    WHEN failed_login_attempts + 1 >= $3 THEN $4
+                    ELSE locked_until
+                END
+            WHERE id = $1;
             "#,
-            &email
+            &id,
+            now,
+            self.login_policy.max_attempts,
+            locked_until
         )
-        .fetch_one(self.db_connect.clone().as_ref())
+        .execute(self.db_connect.clone().as_ref())
         .await?;
-        Ok(query)
+        Ok(())
     }
 
-    async fn login(&self, email: &String, password: &String) -> Result<AuthBody, LibError> {
-        let user = self
-            .get_user_by_email(email)
-            .await
-            .map_err(|_| LibError::Unauthorized("Invalid email or password".to_string()))?;
-
-        let is_valid = self
-            .verify_password(password, &user.hashed_password)
-            .await
-            .map_err(|e| LibError::BcryptError(e))?;
-
-        if !is_valid {
-            return Err(LibError::Unauthorized(
-                "Invalid email or password".to_string(),
-            ));
-        }
-
-        let token = encode(
-            &Header::new(Algorithm::HS256),
-            &user.to_claims(),
-            &Keys::default().encoding,
+    async fn record_login_event(
+        &self,
+        user_id: Option<&Uuid>,
+        email: &str,
+        client: &ClientInfo,
+        reason: LoginEventReason,
+    ) -> Result<(), LibError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO login_events (user_id, email, ip_address, success, reason)
+            VALUES ($1, $2, $3, $4, $5);
+            "#,
+            user_id,
+            email,
+            client.ip_address,
+            reason == LoginEventReason::Success,
+            reason.to_str()
         )
-        .map_err(|e| LibError::JwtError(e))?;
-
-        Ok(AuthBody { token })
+        .execute(self.db_connect.clone().as_ref())
+        .await?;
+        Ok(())
     }
 }
@@ -1,3 +1,5 @@
+use sqlx::error::ErrorKind;
+use sqlx::postgres::PgDatabaseError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,7 +9,9 @@ pub enum LibError {
     #[error("Storage error cccured with message '{0}'")]
     Storage(String),
     #[error("Sqlx error occured with message '{0}'")]
-    SqlxError(#[from] sqlx::Error),
+    SqlxError(sqlx::Error),
+    #[error("Constraint violation occured with message '{0}'")]
+    Constraint(ConstraintError),
     #[error("API error with message '{0}'")]
     Http(String),
     #[error("Not found error occured with message '{0}'")]
@@ -18,6 +22,14 @@ pub enum LibError {
     Unauthorized(String),
     #[error("Jwt error occured with message '{0}'")]
     JwtError(#[from] jsonwebtoken::errors::Error),
+    #[error("Too many requests error occured with message '{0}'")]
+    TooManyRequests(String),
+    #[error("Conflict error occured with message '{0}'")]
+    Conflict(String),
+    #[error("Invalid input error occured with message '{0}'")]
+    InvalidInput(String),
+    #[error("Precondition failed error occured with message '{0}'")]
+    PreconditionFailed(String),
 }
 
 #[derive(Error, Debug)]
@@ -27,3 +39,200 @@ pub enum DatabaseError {
     #[error("Other error occured with message '{0}'")]
     Other(String),
 }
+
+// a write rejected by a database constraint, `field` is the column it guards
+#[derive(Error, Debug)]
+pub enum ConstraintError {
+    #[error("{field} is already taken")]
+    Duplicate { field: String },
+    #[error("{field} does not refer to an existing record")]
+    InvalidReference { field: String },
+    #[error("Record is still referenced from {table}")]
+    StillReferenced { table: String },
+    #[error("{field} is required")]
+    Missing { field: String },
+    #[error("{field} is not valid")]
+    Check { field: String },
+}
+
+impl From<sqlx::Error> for LibError {
+    fn from(err: sqlx::Error) -> Self {
+        match ConstraintError::from_sqlx(&err) {
+            Some(constraint) => LibError::Constraint(constraint),
+            None => LibError::SqlxError(err),
+        }
+    }
+}
+
+// the parts of a postgres error a constraint violation is read from
+struct Violation<'a> {
+    kind: ErrorKind,
+    constraint: &'a str,
+    table: &'a str,
+    column: &'a str,
+    detail: Option<&'a str>,
+}
+
+impl ConstraintError {
+    fn from_sqlx(err: &sqlx::Error) -> Option<Self> {
+        let sqlx::Error::Database(db_err) = err else {
+            return None;
+        };
+        let pg_err = db_err.try_downcast_ref::<PgDatabaseError>()?;
+        Self::from_violation(Violation {
+            kind: db_err.kind(),
+            constraint: pg_err.constraint().unwrap_or_default(),
+            table: pg_err.table().unwrap_or_default(),
+            column: pg_err.column().unwrap_or_default(),
+            detail: pg_err.detail(),
+        })
+    }
+
+    fn from_violation(violation: Violation) -> Option<Self> {
+        let Violation {
+            kind,
+            constraint,
+            table,
+            column,
+            detail,
+        } = violation;
+        // `Key (email)=(...) already exists.`, only the column names are kept
+        let key_columns = detail
+            .and_then(|detail| detail.strip_prefix("Key ("))
+            .and_then(|detail| detail.split_once(")="))
+            .map(|(columns, _)| columns);
+        // postgres names constraints `{table}_{columns}_{suffix}` unless told otherwise
+        let name = constraint
+            .strip_prefix(table)
+            .and_then(|name| name.strip_prefix('_'))
+            .unwrap_or(constraint);
+        let name_columns = ["_key", "_fkey", "_check"]
+            .iter()
+            .find_map(|suffix| name.strip_suffix(suffix))
+            .unwrap_or(name);
+        let field = || key_columns.unwrap_or(name_columns).to_string();
+        match kind {
+            ErrorKind::UniqueViolation => Some(ConstraintError::Duplicate { field: field() }),
+            // the same code covers a missing parent on insert and a delete of a referenced row,
+            // and `table` is the referencing one either way. The key shows the referencing
+            // columns in the first case and the referenced ones in the second.
+            ErrorKind::ForeignKeyViolation
+                if key_columns.is_some_and(|columns| columns != name_columns) =>
+            {
+                Some(ConstraintError::StillReferenced {
+                    table: table.to_string(),
+                })
+            }
+            ErrorKind::ForeignKeyViolation => {
+                Some(ConstraintError::InvalidReference { field: field() })
+            }
+            ErrorKind::NotNullViolation => Some(ConstraintError::Missing {
+                field: column.to_string(),
+            }),
+            ErrorKind::CheckViolation => Some(ConstraintError::Check { field: field() }),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            ConstraintError::Duplicate { field }
+            | ConstraintError::InvalidReference { field }
+            | ConstraintError::Missing { field }
+            | ConstraintError::Check { field } => Some(field),
+            ConstraintError::StillReferenced { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // what postgres reports for a violation on `users`
+    fn violation(
+        kind: ErrorKind,
+        constraint: &'static str,
+        detail: Option<&'static str>,
+    ) -> Violation<'static> {
+        Violation {
+            kind,
+            constraint,
+            table: "users",
+            column: "",
+            detail,
+        }
+    }
+
+    #[test]
+    fn duplicates_name_the_key_columns() {
+        let err = ConstraintError::from_violation(violation(
+            ErrorKind::UniqueViolation,
+            "users_email_key",
+            Some("Key (email)=(ann@example.com) already exists."),
+        ));
+        assert!(matches!(err, Some(ConstraintError::Duplicate { field }) if field == "email"));
+    }
+
+    #[test]
+    fn missing_parents_name_the_reference() {
+        let err = ConstraintError::from_violation(violation(
+            ErrorKind::ForeignKeyViolation,
+            "users_role_id_fkey",
+            Some("Key (role_id)=(6a1f0f0e-0000-4000-8000-000000000000) is not present in table \"roles\"."),
+        ));
+        assert!(
+            matches!(err, Some(ConstraintError::InvalidReference { field }) if field == "role_id")
+        );
+    }
+
+    #[test]
+    fn deleted_parents_name_the_referencing_table() {
+        let err = ConstraintError::from_violation(violation(
+            ErrorKind::ForeignKeyViolation,
+            "users_role_id_fkey",
+            Some("Key (id)=(6a1f0f0e-0000-4000-8000-000000000000) is still referenced from table \"users\"."),
+        ));
+        assert!(
+            matches!(err, Some(ConstraintError::StillReferenced { table }) if table == "users")
+        );
+    }
+
+    #[test]
+    fn not_null_names_the_column() {
+        let err = ConstraintError::from_violation(Violation {
+            column: "password",
+            ..violation(ErrorKind::NotNullViolation, "", None)
+        });
+        assert!(matches!(err, Some(ConstraintError::Missing { field }) if field == "password"));
+    }
+
+    #[test]
+    fn fields_fall_back_to_the_constraint_name() {
+        let err = ConstraintError::from_violation(violation(
+            ErrorKind::CheckViolation,
+            "users_phone_check",
+            None,
+        ));
+        assert!(matches!(err, Some(ConstraintError::Check { field }) if field == "phone"));
+        let err = ConstraintError::from_violation(violation(
+            ErrorKind::UniqueViolation,
+            "users_name_key",
+            None,
+        ));
+        assert!(matches!(err, Some(ConstraintError::Duplicate { field }) if field == "name"));
+        // a name that does not follow the convention is kept whole
+        let err = ConstraintError::from_violation(violation(
+            ErrorKind::CheckViolation,
+            "positive_price",
+            None,
+        ));
+        assert!(matches!(err, Some(ConstraintError::Check { field }) if field == "positive_price"));
+    }
+
+    #[test]
+    fn other_errors_are_not_constraints() {
+        let err = ConstraintError::from_violation(violation(ErrorKind::Other, "", None));
+        assert!(err.is_none());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::util::error::LibError;
+use crate::util::model::{ListQuery, Page, SortDirection};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgRow, Postgres};
+use sqlx::{FromRow, Pool, QueryBuilder, Row};
+use tokio::sync::mpsc::Sender;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Uuid,
+    Integer,
+    Date,
+    Timestamp,
+    Timestamptz,
+}
+
+impl ColumnType {
+    fn to_sql(self) -> &'static str {
+        match self {
+            ColumnType::Text => "text",
+            ColumnType::Uuid => "uuid",
+            ColumnType::Integer => "integer",
+            ColumnType::Date => "date",
+            ColumnType::Timestamp => "timestamp",
+            ColumnType::Timestamptz => "timestamptz",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ListColumn {
+    // name used in the query string
+    pub name: &'static str,
+    pub column: &'static str,
+    pub column_type: ColumnType,
+}
+
+// describes how an entity is listed, only the columns named here can be sorted, filtered or searched
+#[derive(Debug)]
+pub struct ListSpec {
+    pub select: &'static str,
+    pub from: &'static str,
+    pub id_column: &'static str,
+    // always applied, like hiding soft deleted rows
+    pub condition: &'static str,
+    // the first column is the default sort
+    pub sort_columns: &'static [ListColumn],
+    pub filter_columns: &'static [ListColumn],
+    pub search_columns: &'static [&'static str],
+}
+
+// keyset position of the last row of a page, bound to the sort it was created for
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Cursor {
+    pub sort: String,
+    pub direction: SortDirection,
+    pub value: String,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub(crate) fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub(crate) fn decode(cursor: &str) -> Result<Self, LibError> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or_else(|| LibError::InvalidInput("Invalid cursor".to_string()))
+    }
+}
+
+impl ListSpec {
+    pub async fn fetch<T>(
+        &self,
+        db_connect: &Pool<Postgres>,
+        query: &ListQuery,
+    ) -> Result<Page<T>, LibError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        let sort = self.sort_column(query)?;
+        let cursor = query.cursor.as_deref().map(Cursor::decode).transpose()?;
+        if let Some(cursor) = &cursor {
+            if cursor.sort != sort.name || cursor.direction != query.direction {
+                return Err(LibError::InvalidInput(
+                    "Cursor does not match the requested sort".to_string(),
+                ));
+            }
+        }
+
+        let mut count = QueryBuilder::<Postgres>::new("SELECT COUNT(*) ");
+        count.push(self.from);
+        self.push_conditions(&mut count, query)?;
+        let total: i64 = count.build_query_scalar().fetch_one(db_connect).await?;
+
+        let direction = query.direction.to_str();
+        let mut list = QueryBuilder::<Postgres>::new(self.select);
+        list.push(format!(
+            ", ({})::text AS list_sort_key, {} AS list_id ",
+            sort.column, self.id_column
+        ));
+        list.push(self.from);
+        self.push_conditions(&mut list, query)?;
+        if let Some(cursor) = &cursor {
+            let operator = match query.direction {
+                SortDirection::Asc => ">",
+                SortDirection::Desc => "<",
+            };
+            list.push(format!(
+                " AND ({}, {}) {operator} (CAST(",
+                sort.column, self.id_column
+            ));
+            list.push_bind(cursor.value.clone());
+            list.push(format!(" AS {}), ", sort.column_type.to_sql()));
+            list.push_bind(cursor.id);
+            list.push(")");
+        }
+        list.push(format!(
+            " ORDER BY {} {direction}, {} {direction} LIMIT ",
+            sort.column, self.id_column
+        ));
+        // one extra row tells whether there is a next page
+        list.push_bind(query.limit + 1);
+        if cursor.is_none() {
+            list.push(" OFFSET ");
+            list.push_bind(query.offset()?);
+        }
+        let rows = list.build().fetch_all(db_connect).await?;
+
+        let has_more = rows.len() as i64 > query.limit;
+        let rows = &rows[..rows.len().min(query.limit as usize)];
+        let next_cursor = match rows.last() {
+            Some(row) if has_more => Some(
+                Cursor {
+                    sort: sort.name.to_string(),
+                    direction: query.direction,
+                    value: row.try_get("list_sort_key")?,
+                    id: row.try_get("list_id")?,
+                }
+                .encode(),
+            ),
+            _ => None,
+        };
+        let items = rows
+            .iter()
+            .map(T::from_row)
+            .collect::<Result<Vec<T>, sqlx::Error>>()?;
+        Ok(Page {
+            items,
+            total,
+            page: cursor.is_none().then_some(query.page),
+            limit: query.limit,
+            next_cursor,
+        })
+    }
+
+    // every matching row in list order without paging, rows are sent as they arrive
+    // so a large table is never held in memory
+    pub async fn export<T, R>(
+        &self,
+        db_connect: &Pool<Postgres>,
+        query: &ListQuery,
+        to_record: impl Fn(T) -> R + Send,
+        sender: &Sender<R>,
+    ) -> Result<(), LibError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        R: Send,
+    {
+        let sort = self.sort_column(query)?;
+        let direction = query.direction.to_str();
+        let mut list = QueryBuilder::<Postgres>::new(self.select);
+        list.push(self.from);
+        self.push_conditions(&mut list, query)?;
+        list.push(format!(
+            " ORDER BY {} {direction}, {} {direction}",
+            sort.column, self.id_column
+        ));
+        let mut rows = list.build_query_as::<T>().fetch(db_connect);
+        while let Some(row) = rows.try_next().await? {
+            // the receiver is gone when the client disconnected
+            if sender.send(to_record(row)).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn sort_column(&self, query: &ListQuery) -> Result<&ListColumn, LibError> {
+        match &query.sort {
+            Some(name) => self
+                .sort_columns
+                .iter()
+                .find(|column| column.name == name)
+                .ok_or_else(|| LibError::InvalidInput(format!("Unknown sort field '{name}'"))),
+            None => Ok(&self.sort_columns[0]),
+        }
+    }
+
+    fn push_conditions(
+        &self,
+        builder: &mut QueryBuilder<'_, Postgres>,
+        query: &ListQuery,
+    ) -> Result<(), LibError> {
+        builder.push(" WHERE ");
+        builder.push(self.condition);
+        for (name, value) in &query.filters {
+            let column = self
+                .filter_columns
+                .iter()
+                .find(|column| column.name == name)
+                .ok_or_else(|| LibError::InvalidInput(format!("Unknown filter field '{name}'")))?;
+            match column.column_type {
+                ColumnType::Uuid => {
+                    let id = Uuid::parse_str(value).map_err(|_| {
+                        LibError::InvalidInput(format!("Invalid value for filter '{name}'"))
+                    })?;
+                    builder.push(format!(" AND {} = ", column.column));
+                    builder.push_bind(id);
+                }
+                _ => {
+                    builder.push(format!(" AND lower(({})::text) = lower(", column.column));
+                    builder.push_bind(value.clone());
+                    builder.push(")");
+                }
+            }
+        }
+        let search = query.search.as_deref().map(str::trim).unwrap_or_default();
+        if !search.is_empty() && !self.search_columns.is_empty() {
+            let pattern = format!("%{}%", escape_like(search));
+            builder.push(" AND (");
+            for (i, column) in self.search_columns.iter().enumerate() {
+                if i > 0 {
+                    builder.push(" OR ");
+                }
+                builder.push(format!("{column} ILIKE "));
+                builder.push_bind(pattern.clone());
+            }
+            builder.push(")");
+        }
+        Ok(())
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
@@ -0,0 +1,279 @@
+use crate::material::memory::{MaterialGroupRow, MaterialRow};
+use crate::role::memory::RoleRow;
+use crate::supplier::memory::SupplierRow;
+use crate::user::memory::UserRow;
+use crate::user::model::ResponseLoginEvent;
+use crate::util::error::LibError;
+use crate::util::list::{ColumnType, Cursor, ListSpec};
+use crate::util::model::{
+    BulkApplied, BulkMode, BulkOperation, BulkResult, ListQuery, Page, SortDirection,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::cmp::Ordering;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use tokio::sync::mpsc::Sender;
+use uuid::Uuid;
+
+// every table the in-memory repositories keep, cloned as a whole to roll a transaction back
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MemoryTables {
+    pub roles: Vec<RoleRow>,
+    pub users: Vec<UserRow>,
+    pub login_events: Vec<ResponseLoginEvent>,
+    pub suppliers: Vec<SupplierRow>,
+    pub material_groups: Vec<MaterialGroupRow>,
+    pub materials: Vec<MaterialRow>,
+}
+
+// stands in for the Postgres pool: the memory repositories built on the same MemoryDb see
+// each other's rows, like users and their roles. Audit entries and sessions are not kept.
+#[derive(Debug)]
+pub struct MemoryDb {
+    tables: Mutex<MemoryTables>,
+}
+
+impl MemoryDb {
+    // starts with the Admin and User roles, like the migrations
+    pub fn new() -> Self {
+        let tables = MemoryTables {
+            roles: vec![RoleRow::new("Admin"), RoleRow::new("User")],
+            ..MemoryTables::default()
+        };
+        Self {
+            tables: Mutex::new(tables),
+        }
+    }
+
+    pub(crate) fn read<T>(&self, f: impl FnOnce(&MemoryTables) -> T) -> T {
+        f(&self.lock())
+    }
+
+    // nothing `f` changed is kept when it fails
+    pub(crate) fn transaction<T>(
+        &self,
+        f: impl FnOnce(&mut MemoryTables) -> Result<T, LibError>,
+    ) -> Result<T, LibError> {
+        let mut tables = self.lock();
+        let snapshot = tables.clone();
+        let result = f(&mut tables);
+        if result.is_err() {
+            *tables = snapshot;
+        }
+        result
+    }
+
+    // the in-memory counterpart of a bulk transaction with one savepoint per item
+    pub(crate) fn bulk<C, U, T>(
+        &self,
+        operations: &[BulkOperation<C, U>],
+        mode: BulkMode,
+        mut apply: impl FnMut(
+            &mut MemoryTables,
+            &BulkOperation<C, U>,
+        ) -> Result<BulkApplied<T>, LibError>,
+    ) -> BulkResult<T> {
+        let mut tables = self.lock();
+        let snapshot = tables.clone();
+        let mut outcomes = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let savepoint = tables.clone();
+            let outcome = apply(&mut tables, operation);
+            if outcome.is_err() {
+                *tables = savepoint;
+            }
+            outcomes.push(outcome);
+        }
+        let committed = mode == BulkMode::BestEffort || outcomes.iter().all(Result::is_ok);
+        if !committed {
+            *tables = snapshot;
+        }
+        BulkResult {
+            committed,
+            outcomes,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryTables> {
+        self.tables.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for MemoryDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the error Postgres gives for a row that does not exist, so use cases cannot tell the difference
+pub(crate) fn not_found() -> LibError {
+    LibError::SqlxError(sqlx::Error::RowNotFound)
+}
+
+// sort keys of timestamp columns, fixed width so they order like the timestamps
+pub(crate) fn timestamp_key(timestamp: &DateTime<Utc>) -> String {
+    naive_timestamp_key(&timestamp.naive_utc())
+}
+
+pub(crate) fn naive_timestamp_key(timestamp: &NaiveDateTime) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S%.6f").to_string()
+}
+
+// a row as the list columns of its ListSpec see it
+pub(crate) trait ListRecord {
+    fn list_id(&self) -> Uuid;
+    // the value of the sort or filter column called `name`
+    fn list_value(&self, name: &str) -> Option<String>;
+    fn search_values(&self) -> Vec<&str>;
+}
+
+impl ListSpec {
+    // sorts, filters, searches and pages rows the way `fetch` does in SQL
+    pub(crate) fn fetch_memory<T: ListRecord>(
+        &self,
+        rows: Vec<T>,
+        query: &ListQuery,
+    ) -> Result<Page<T>, LibError> {
+        let sort = self.sort_column(query)?;
+        let cursor = query.cursor.as_deref().map(Cursor::decode).transpose()?;
+        if let Some(cursor) = &cursor {
+            if cursor.sort != sort.name || cursor.direction != query.direction {
+                return Err(LibError::InvalidInput(
+                    "Cursor does not match the requested sort".to_string(),
+                ));
+            }
+        }
+        let rows = self.sort_memory(rows, query)?;
+        let total = rows.len() as i64;
+        let rows: Vec<T> = match &cursor {
+            Some(cursor) => rows
+                .into_iter()
+                .filter(|row| {
+                    let ordering = compare_key(
+                        sort.column_type,
+                        (&sort_key(row, sort.name), &row.list_id()),
+                        (&cursor.value, &cursor.id),
+                    );
+                    match query.direction {
+                        SortDirection::Asc => ordering == Ordering::Greater,
+                        SortDirection::Desc => ordering == Ordering::Less,
+                    }
+                })
+                .collect(),
+            None => rows
+                .into_iter()
+                .skip(query.offset()?.max(0) as usize)
+                .collect(),
+        };
+
+        let has_more = rows.len() as i64 > query.limit;
+        let items: Vec<T> = rows.into_iter().take(query.limit.max(0) as usize).collect();
+        let next_cursor = match items.last() {
+            Some(row) if has_more => Some(
+                Cursor {
+                    sort: sort.name.to_string(),
+                    direction: query.direction,
+                    value: sort_key(row, sort.name),
+                    id: row.list_id(),
+                }
+                .encode(),
+            ),
+            _ => None,
+        };
+        Ok(Page {
+            items,
+            total,
+            page: cursor.is_none().then_some(query.page),
+            limit: query.limit,
+            next_cursor,
+        })
+    }
+
+    // every matching row in list order, like `export`
+    pub(crate) async fn export_memory<T: ListRecord, R>(
+        &self,
+        rows: Vec<T>,
+        query: &ListQuery,
+        to_record: impl Fn(T) -> R,
+        sender: &Sender<R>,
+    ) -> Result<(), LibError> {
+        for row in self.sort_memory(rows, query)? {
+            if sender.send(to_record(row)).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn sort_memory<T: ListRecord>(
+        &self,
+        rows: Vec<T>,
+        query: &ListQuery,
+    ) -> Result<Vec<T>, LibError> {
+        let sort = self.sort_column(query)?;
+        let mut filters = Vec::with_capacity(query.filters.len());
+        for (name, value) in &query.filters {
+            let column = self
+                .filter_columns
+                .iter()
+                .find(|column| column.name == name)
+                .ok_or_else(|| LibError::InvalidInput(format!("Unknown filter field '{name}'")))?;
+            let value = match column.column_type {
+                ColumnType::Uuid => Uuid::parse_str(value)
+                    .map_err(|_| {
+                        LibError::InvalidInput(format!("Invalid value for filter '{name}'"))
+                    })?
+                    .to_string(),
+                _ => value.to_lowercase(),
+            };
+            filters.push((column.name, value));
+        }
+        let search = query
+            .search
+            .as_deref()
+            .map(str::trim)
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let mut rows: Vec<T> = rows
+            .into_iter()
+            .filter(|row| {
+                filters.iter().all(|(name, value)| {
+                    row.list_value(name)
+                        .is_some_and(|row_value| row_value.to_lowercase() == *value)
+                })
+            })
+            .filter(|row| {
+                search.is_empty()
+                    || self.search_columns.is_empty()
+                    || row
+                        .search_values()
+                        .iter()
+                        .any(|value| value.to_lowercase().contains(&search))
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            let ordering = compare_key(
+                sort.column_type,
+                (&sort_key(a, sort.name), &a.list_id()),
+                (&sort_key(b, sort.name), &b.list_id()),
+            );
+            match query.direction {
+                SortDirection::Asc => ordering,
+                SortDirection::Desc => ordering.reverse(),
+            }
+        });
+        Ok(rows)
+    }
+}
+
+fn sort_key<T: ListRecord>(row: &T, name: &str) -> String {
+    row.list_value(name).unwrap_or_default()
+}
+
+fn compare_key(column_type: ColumnType, a: (&String, &Uuid), b: (&String, &Uuid)) -> Ordering {
+    let value = match column_type {
+        ColumnType::Integer => a.0.parse::<i64>().ok().cmp(&b.0.parse::<i64>().ok()),
+        _ => a.0.cmp(b.0),
+    };
+    value.then_with(|| a.1.cmp(b.1))
+}
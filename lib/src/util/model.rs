@@ -0,0 +1,162 @@
+use crate::util::error::LibError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// a soft deleted row as shown to admins, the record itself plus who deleted it and when
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeletedRecord<T> {
+    #[serde(flatten)]
+    pub record: T,
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: Option<Uuid>,
+}
+
+// optimistic concurrency: `expected` is the version the client last read, None skips the check
+pub fn check_version(expected: Option<i32>, current: i32) -> Result<(), LibError> {
+    match expected {
+        Some(expected) if expected != current => Err(LibError::PreconditionFailed(format!(
+            "Record has been modified, current version is {current}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            SortDirection::Asc => "asc",
+            SortDirection::Desc => "desc",
+        }
+    }
+}
+
+// shared by every list route, sort and filter names are checked against the entity's columns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListQuery {
+    // 1-based, ignored when a cursor is given
+    pub page: i64,
+    pub limit: i64,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,
+    pub direction: SortDirection,
+    pub filters: Vec<(String, String)>,
+    pub search: Option<String>,
+}
+
+impl ListQuery {
+    // rows skipped before the page, a page far past any table would not fit into an i64
+    pub fn offset(&self) -> Result<i64, LibError> {
+        (self.page >= 1)
+            .then(|| (self.page - 1).checked_mul(self.limit))
+            .flatten()
+            .ok_or_else(|| LibError::InvalidInput("Page is out of range".to_string()))
+    }
+}
+
+impl Default for ListQuery {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            limit: 50,
+            cursor: None,
+            sort: None,
+            direction: SortDirection::Asc,
+            filters: Vec::new(),
+            search: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    // only set for page based listing
+    pub page: Option<i64>,
+    pub limit: i64,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            limit: self.limit,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+// a parsed spreadsheet row, `row` is the line number the user sees in the file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportRow<T> {
+    pub row: usize,
+    pub record: T,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    // any failed item rolls back the whole batch
+    #[default]
+    AllOrNothing,
+    // items that succeed are kept, failed ones are reported
+    BestEffort,
+}
+
+// one item of a bulk request
+#[derive(Debug)]
+pub enum BulkOperation<C, U> {
+    Create(C),
+    Update {
+        id: Uuid,
+        version: Option<i32>,
+        record: U,
+    },
+    Delete {
+        id: Uuid,
+        version: Option<i32>,
+    },
+}
+
+#[derive(Debug)]
+pub enum BulkApplied<T> {
+    Created(T),
+    Updated(T),
+    Deleted(Uuid),
+}
+
+// one outcome per operation in request order, `committed` is false when an all-or-nothing
+// batch was rolled back and the successful outcomes were not kept
+#[derive(Debug)]
+pub struct BulkResult<T> {
+    pub committed: bool,
+    pub outcomes: Vec<Result<BulkApplied<T>, LibError>>,
+}
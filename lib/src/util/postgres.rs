@@ -1,9 +1,17 @@
+use crate::util::error::LibError;
+use crate::util::model::{BulkApplied, BulkMode, BulkResult};
 use config::Config;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::postgres::Postgres;
-use sqlx::Pool;
+use sqlx::{Pool, Transaction};
+use std::collections::HashMap;
+use std::time::Duration;
 use thiserror::Error;
 
+// the migrations of /migrations, built into the binary
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
 #[derive(Error, Debug)]
 pub enum PgError {
     #[error("unexpected error with message: \"{msg:?}\"")]
@@ -16,11 +24,104 @@ impl PgError {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("database is unreachable: {0}")]
+    Unreachable(#[from] sqlx::Error),
+    #[error("applied migrations cannot be read: {0}")]
+    Migrate(#[from] MigrateError),
+    #[error("migration {0} failed part way and must be repaired by hand")]
+    Dirty(i64),
+    #[error("migration {0} was changed after it was applied")]
+    Modified(i64),
+    #[error("database schema is behind, pending migrations: {}", .0.join(", "))]
+    Behind(Vec<String>),
+}
+
+// every migration of this build must be applied unchanged. Migrations the build does not know
+// are left alone, a newer release may have applied them before being rolled back.
+pub async fn check_schema(pool: &Pool<Postgres>) -> Result<(), SchemaError> {
+    let mut conn = pool.acquire().await?;
+    // sqlx creates its table in the first schema of the search_path, one further down belongs
+    // to another database user
+    let migrated: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pg_tables \
+         WHERE schemaname = current_schema() AND tablename = '_sqlx_migrations')",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let applied = match migrated {
+        true => {
+            if let Some(version) = conn.dirty_version().await? {
+                return Err(SchemaError::Dirty(version));
+            }
+            conn.list_applied_migrations().await?
+        }
+        false => Vec::new(),
+    };
+    let applied: HashMap<i64, _> = applied
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum))
+        .collect();
+
+    let mut pending = Vec::new();
+    for migration in MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+    {
+        match applied.get(&migration.version) {
+            Some(checksum) if *checksum != migration.checksum => {
+                return Err(SchemaError::Modified(migration.version));
+            }
+            Some(_) => {}
+            None => pending.push(format!("{} {}", migration.version, migration.description)),
+        }
+    }
+    match pending.is_empty() {
+        true => Ok(()),
+        false => Err(SchemaError::Behind(pending)),
+    }
+}
+
 pub fn get_connection_pool(config: Config) -> Pool<Postgres> {
-    let url: String = config.pg_connection;
-    let connection_pool_size = config.connection_pool_size;
+    let database = config.database;
+    // 0 keeps idle or old connections open
+    let seconds = |seconds: u64| (seconds > 0).then(|| Duration::from_secs(seconds));
     PgPoolOptions::new()
-        .max_connections(connection_pool_size)
-        .connect_lazy(&url)
+        .max_connections(database.pool_size)
+        .min_connections(database.min_connections)
+        .acquire_timeout(Duration::from_secs(database.acquire_timeout_seconds))
+        .idle_timeout(seconds(database.idle_timeout_seconds))
+        .max_lifetime(seconds(database.max_lifetime_seconds))
+        .connect_lazy(&database.url)
         .expect("PgPool Connection Error")
 }
+
+// every bulk item runs in its own savepoint, a failed item is undone without aborting the batch
+pub async fn end_savepoint<T>(
+    savepoint: Transaction<'_, Postgres>,
+    outcome: &Result<T, LibError>,
+) -> Result<(), LibError> {
+    match outcome {
+        Ok(_) => savepoint.commit().await?,
+        Err(_) => savepoint.rollback().await?,
+    }
+    Ok(())
+}
+
+pub async fn end_bulk<T>(
+    tx: Transaction<'_, Postgres>,
+    mode: BulkMode,
+    outcomes: Vec<Result<BulkApplied<T>, LibError>>,
+) -> Result<BulkResult<T>, LibError> {
+    let committed = mode == BulkMode::BestEffort || outcomes.iter().all(Result::is_ok);
+    if committed {
+        tx.commit().await?;
+    } else {
+        tx.rollback().await?;
+    }
+    Ok(BulkResult {
+        committed,
+        outcomes,
+    })
+}
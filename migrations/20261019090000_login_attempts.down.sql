@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS login_events;
+ALTER TABLE users
+    DROP COLUMN IF EXISTS failed_login_attempts,
+    DROP COLUMN IF EXISTS last_failed_login_at,
+    DROP COLUMN IF EXISTS locked_until;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS audit_log (
+    id uuid PRIMARY KEY DEFAULT gen_random_uuid(),
+    actor_id uuid REFERENCES users(id) ON DELETE SET NULL,
+    entity_type TEXT NOT NULL,
+    entity_id uuid NOT NULL,
+    action TEXT NOT NULL,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_entity_index ON audit_log (entity_type, entity_id, created_at);
+CREATE INDEX IF NOT EXISTS audit_log_actor_index ON audit_log (actor_id, created_at);
+CREATE INDEX IF NOT EXISTS audit_log_created_at_index ON audit_log (created_at);
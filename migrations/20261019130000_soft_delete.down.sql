@@ -0,0 +1,14 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS material_group_index;
+DROP INDEX IF EXISTS material_supplier_index;
+DROP INDEX IF EXISTS users_role_index;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_id_fkey;
+ALTER TABLE users ADD CONSTRAINT users_role_id_fkey
+    FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE CASCADE;
+
+ALTER TABLE material DROP COLUMN IF EXISTS deleted_by, DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE material_group DROP COLUMN IF EXISTS deleted_by, DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE supplier DROP COLUMN IF EXISTS deleted_by, DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE roles DROP COLUMN IF EXISTS deleted_by, DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE users DROP COLUMN IF EXISTS deleted_by, DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,25 @@
+-- Add up migration script here
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by uuid REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE roles
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by uuid REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE supplier
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by uuid REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE material_group
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by uuid REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE material
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_by uuid REFERENCES users(id) ON DELETE SET NULL;
+
+-- a role still in use must never take its users with it
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_id_fkey;
+ALTER TABLE users ADD CONSTRAINT users_role_id_fkey
+    FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE RESTRICT;
+
+CREATE INDEX IF NOT EXISTS users_role_index ON users (role_id) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS material_supplier_index ON material (supplier_id) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS material_group_index ON material (group_id) WHERE deleted_at IS NULL;
@@ -0,0 +1,17 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS material_group_sub_group_name_trgm_index;
+DROP INDEX IF EXISTS material_group_name_trgm_index;
+DROP INDEX IF EXISTS supplier_email_trgm_index;
+DROP INDEX IF EXISTS supplier_name_trgm_index;
+DROP INDEX IF EXISTS material_description_trgm_index;
+DROP INDEX IF EXISTS material_name_trgm_index;
+
+DROP INDEX IF EXISTS material_group_search_index;
+DROP INDEX IF EXISTS supplier_search_index;
+DROP INDEX IF EXISTS material_search_index;
+
+ALTER TABLE material_group DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE supplier DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE material DROP COLUMN IF EXISTS search_vector;
+
+DROP EXTENSION IF EXISTS pg_trgm;
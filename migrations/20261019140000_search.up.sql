@@ -0,0 +1,30 @@
+-- Add up migration script here
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+ALTER TABLE material ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(description, '')), 'B')
+    ) STORED;
+ALTER TABLE supplier ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(email, '')), 'B')
+    ) STORED;
+ALTER TABLE material_group ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(sub_group_name, '')), 'B')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS material_search_index ON material USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS supplier_search_index ON supplier USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS material_group_search_index ON material_group USING GIN (search_vector);
+
+-- trigram indexes catch typos and partial words the tsvector misses
+CREATE INDEX IF NOT EXISTS material_name_trgm_index ON material USING GIN (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS material_description_trgm_index ON material USING GIN (description gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS supplier_name_trgm_index ON supplier USING GIN (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS supplier_email_trgm_index ON supplier USING GIN (email gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS material_group_name_trgm_index ON material_group USING GIN (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS material_group_sub_group_name_trgm_index ON material_group USING GIN (sub_group_name gin_trgm_ops);
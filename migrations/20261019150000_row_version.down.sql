@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE material_group DROP COLUMN IF EXISTS version;
+ALTER TABLE supplier DROP COLUMN IF EXISTS version;
+ALTER TABLE roles DROP COLUMN IF EXISTS version;
+ALTER TABLE users DROP COLUMN IF EXISTS version;
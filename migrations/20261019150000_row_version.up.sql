@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- optimistic concurrency: every update bumps the version, clients send it back in If-Match
+ALTER TABLE users ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+ALTER TABLE roles ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+ALTER TABLE supplier ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
+ALTER TABLE material_group ADD COLUMN IF NOT EXISTS version INT NOT NULL DEFAULT 1;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+-- fails while a deleted row shares its name or email with another row
+DROP INDEX IF EXISTS roles_name_key;
+DROP INDEX IF EXISTS users_email_key;
+DROP INDEX IF EXISTS users_name_key;
+
+ALTER TABLE roles ADD CONSTRAINT roles_name_key UNIQUE (name);
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+ALTER TABLE users ADD CONSTRAINT users_name_key UNIQUE (name);
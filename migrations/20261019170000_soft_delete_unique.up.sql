@@ -0,0 +1,10 @@
+-- Add up migration script here
+-- a soft deleted user or role must not hold on to its name or email, only rows that are not
+-- deleted have to be unique. The indexes keep the constraint names so errors still name the field.
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_name_key;
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_name_key;
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_name_key ON users (name) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_key ON users (email) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS roles_name_key ON roles (name) WHERE deleted_at IS NULL;
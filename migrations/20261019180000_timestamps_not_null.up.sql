@@ -0,0 +1,18 @@
+-- Add up migration script here
+-- list cursors compare the sort column, a NULL there would drop the row from every later page
+UPDATE users SET created_at = COALESCE(created_at, updated_at, now()) WHERE created_at IS NULL;
+UPDATE users SET updated_at = created_at WHERE updated_at IS NULL;
+UPDATE roles SET created_at = COALESCE(created_at, updated_at, now()) WHERE created_at IS NULL;
+UPDATE roles SET updated_at = created_at WHERE updated_at IS NULL;
+UPDATE supplier SET created_at = COALESCE(created_at, updated_at, now()) WHERE created_at IS NULL;
+UPDATE supplier SET updated_at = created_at WHERE updated_at IS NULL;
+UPDATE material_group SET created_at = COALESCE(created_at, updated_at, now()) WHERE created_at IS NULL;
+UPDATE material_group SET updated_at = created_at WHERE updated_at IS NULL;
+UPDATE material SET created_at = COALESCE(created_at, updated_at, now()) WHERE created_at IS NULL;
+UPDATE material SET updated_at = created_at WHERE updated_at IS NULL;
+
+ALTER TABLE users ALTER COLUMN created_at SET NOT NULL, ALTER COLUMN updated_at SET NOT NULL;
+ALTER TABLE roles ALTER COLUMN created_at SET NOT NULL, ALTER COLUMN updated_at SET NOT NULL;
+ALTER TABLE supplier ALTER COLUMN created_at SET NOT NULL, ALTER COLUMN updated_at SET NOT NULL;
+ALTER TABLE material_group ALTER COLUMN created_at SET NOT NULL, ALTER COLUMN updated_at SET NOT NULL;
+ALTER TABLE material ALTER COLUMN created_at SET NOT NULL, ALTER COLUMN updated_at SET NOT NULL;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- search highlights are HTML with <mark> around the matches, the text around them must not be
+CREATE OR REPLACE FUNCTION html_escape(value text) RETURNS text
+    LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE
+    AS $$
+        SELECT replace(replace(replace(replace(replace(value,
+            '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;')
+    $$;